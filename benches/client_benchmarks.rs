@@ -110,10 +110,12 @@ pub fn streaming_benchmark(c: &mut Criterion) {
                         stop_sequence: None,
                         role: Some(Role::Assistant),
                         type_field: Some("message".to_string()),
+                        usage: None,
                     }),
                     delta: None,
                     usage: None,
                     index: Some(0),
+                    content_block: None,
                 },
                 // Content delta events
                 DeltaEvent {
@@ -121,21 +123,26 @@ pub fn streaming_benchmark(c: &mut Criterion) {
                     message: Some(DeltaMessage {
                         id: "msg_sample123".to_string(),
                         model: "claude-3-sonnet-20240229".to_string(),
-                        content: Some(vec![Content::Text {
-                            text: "This is ".to_string(),
-                        }]),
+                        content: Some(vec![Content::text("This is ")]),
                         stop_reason: None,
                         stop_sequence: None,
                         role: Some(Role::Assistant),
                         type_field: Some("message".to_string()),
+                        usage: None,
                     }),
                     delta: Some(Delta {
                         text: Some("This is ".to_string()),
                         stop_reason: None,
                         stop_sequence: None,
+                        delta_type: None,
+                        partial_json: None,
+                        thinking: None,
+                        signature: None,
+                        citation: None,
                     }),
                     usage: None,
                     index: Some(1),
+                    content_block: None,
                 },
                 // Final event with stop reason
                 DeltaEvent {
@@ -148,17 +155,26 @@ pub fn streaming_benchmark(c: &mut Criterion) {
                         stop_sequence: None,
                         role: Some(Role::Assistant),
                         type_field: Some("message".to_string()),
+                        usage: None,
                     }),
                     delta: Some(Delta {
                         text: None,
                         stop_reason: Some("end_turn".to_string()),
                         stop_sequence: None,
+                        delta_type: None,
+                        partial_json: None,
+                        thinking: None,
+                        signature: None,
+                        citation: None,
                     }),
                     usage: Some(Usage {
                         input_tokens: 10,
                         output_tokens: 5,
+                        cache_creation_input_tokens: 0,
+                        cache_read_input_tokens: 0,
                     }),
                     index: Some(4),
+                    content_block: None,
                 },
             ]
         }
@@ -214,7 +230,7 @@ pub fn streaming_benchmark(c: &mut Criterion) {
                         else if let Some(msg) = &delta.message {
                             if let Some(contents) = &msg.content {
                                 for content in contents {
-                                    if let Content::Text { text } = content {
+                                    if let Content::Text { text, .. } = content {
                                         extracted_text.push_str(text);
                                     }
                                 }
@@ -31,7 +31,7 @@ pub fn context_manager_benchmark(c: &mut Criterion) {
             let mut score = 0.5;
             
             for content in &message.content {
-                if let Content::Text { text } = content {
+                if let Content::Text { text, .. } = content {
                     score += text.len() as f32 / 10000.0;
                 }
             }
@@ -49,9 +49,7 @@ pub fn context_manager_benchmark(c: &mut Criterion) {
             let messages: Vec<Message> = (0..size)
                 .map(|i| Message {
                     role: if i % 2 == 0 { Role::User } else { Role::Assistant },
-                    content: vec![Content::Text {
-                        text: format!("Message content {}", i).repeat(10),
-                    }],
+                    content: vec![Content::text(format!("Message content {}", i).repeat(10))],
                 })
                 .collect();
             
@@ -15,10 +15,12 @@ fn create_test_response(content_type: &str, size: usize) -> MessageResponse {
         model: "claude-3".to_string(),
         r#type: "message".to_string(),
         role: Role::Assistant,
-        content: vec![Content::Text { text: content }],
+        content: vec![Content::text(content)],
         usage: Usage {
             input_tokens: 10,
             output_tokens: 10,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
         },
         stop_reason: Some("end_turn".to_string()),
         stop_sequence: None,
@@ -0,0 +1,419 @@
+//! Derive macros for claude-rs
+//!
+//! This crate is re-exported by `claude-rs` when its `derive` feature is
+//! enabled; depend on that feature rather than on this crate directly.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::meta::ParseNestedMeta;
+use syn::{
+    parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Error, Expr, ExprLit, Fields,
+    Lit, LitStr, Meta, Result, Variant,
+};
+
+/// Derive `claude_rs::schema::JsonSchema` for a struct or enum
+///
+/// Doc comments on the type, its fields and its variants become `description`
+/// entries in the generated schema. The serde attributes that change the JSON
+/// shape are honored: `rename`, `rename_all`, `rename_all_fields`, `default`,
+/// `skip`, `skip_deserializing`, `tag`, `content` and `untagged`.
+/// `flatten` is not supported.
+#[proc_macro_derive(JsonSchema)]
+pub fn derive_json_schema(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+fn expand(mut input: DeriveInput) -> Result<TokenStream2> {
+    let container = SerdeAttrs::parse(&input.attrs)?;
+    let description = doc_comment(&input.attrs);
+
+    let body = match &input.data {
+        Data::Struct(data) => fields_schema(&data.fields, container.rename_all, container.default)?,
+        Data::Enum(data) => enum_schema(&data.variants, &container)?,
+        Data::Union(_) => {
+            return Err(Error::new_spanned(&input.ident, "JsonSchema cannot be derived for unions"));
+        }
+    };
+    let body = describe(body, description.as_deref());
+
+    for param in input.generics.type_params_mut() {
+        param.bounds.push(parse_quote!(::claude_rs::schema::JsonSchema));
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::claude_rs::schema::JsonSchema for #ident #ty_generics #where_clause {
+            fn json_schema() -> ::claude_rs::schema::__private::Value {
+                #body
+            }
+        }
+    })
+}
+
+/// Build the schema for the fields of a struct or enum variant
+fn fields_schema(fields: &Fields, rename_all: Option<RenameRule>, all_default: bool) -> Result<TokenStream2> {
+    match fields {
+        Fields::Named(named) => {
+            let mut entries = Vec::new();
+
+            for field in &named.named {
+                let attrs = SerdeAttrs::parse(&field.attrs)?;
+                if attrs.skip {
+                    continue;
+                }
+                if attrs.flatten {
+                    return Err(Error::new_spanned(field, "#[serde(flatten)] is not supported by JsonSchema"));
+                }
+
+                let ident = field.ident.as_ref().expect("named field");
+                let ident = ident.to_string();
+                let ident = ident.strip_prefix("r#").unwrap_or(&ident);
+                let name = attrs.rename.unwrap_or_else(|| match rename_all {
+                    Some(rule) => rule.apply_to_field(ident),
+                    None => ident.to_string(),
+                });
+
+                let ty = &field.ty;
+                let schema = describe(
+                    quote!(<#ty as ::claude_rs::schema::JsonSchema>::json_schema()),
+                    doc_comment(&field.attrs).as_deref(),
+                );
+                let required = if attrs.default || all_default {
+                    quote!(false)
+                } else {
+                    quote!(!<#ty as ::claude_rs::schema::JsonSchema>::is_optional())
+                };
+
+                entries.push(quote! {
+                    properties.insert(#name.to_string(), #schema);
+                    if #required {
+                        required.push(::claude_rs::schema::__private::Value::from(#name));
+                    }
+                });
+            }
+
+            Ok(quote! {{
+                let mut properties = ::claude_rs::schema::__private::Map::new();
+                let mut required = ::std::vec::Vec::new();
+                #(#entries)*
+                ::claude_rs::schema::__private::object(properties, required)
+            }})
+        }
+        Fields::Unnamed(unnamed) if unnamed.unnamed.len() == 1 => {
+            let ty = &unnamed.unnamed[0].ty;
+            Ok(quote!(<#ty as ::claude_rs::schema::JsonSchema>::json_schema()))
+        }
+        Fields::Unnamed(unnamed) => {
+            let items = unnamed.unnamed.iter().map(|field| {
+                let ty = &field.ty;
+                quote!(<#ty as ::claude_rs::schema::JsonSchema>::json_schema())
+            });
+            let len = unnamed.unnamed.len();
+
+            Ok(quote! {{
+                let items = ::std::vec![#(#items),*];
+                ::claude_rs::schema::__private::json!({
+                    "type": "array",
+                    "prefixItems": items,
+                    "minItems": #len,
+                    "maxItems": #len
+                })
+            }})
+        }
+        Fields::Unit => Ok(quote!(::claude_rs::schema::__private::json!({"type": "null"}))),
+    }
+}
+
+/// Build the schema for an enum according to its serde tagging mode
+fn enum_schema(
+    variants: &syn::punctuated::Punctuated<Variant, syn::token::Comma>,
+    container: &SerdeAttrs,
+) -> Result<TokenStream2> {
+    let mut included = Vec::new();
+    for variant in variants {
+        let attrs = SerdeAttrs::parse(&variant.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+
+        let ident = variant.ident.to_string();
+        let ident = ident.strip_prefix("r#").unwrap_or(&ident);
+        let name = attrs.rename.clone().unwrap_or_else(|| match container.rename_all {
+            Some(rule) => rule.apply_to_variant(ident),
+            None => ident.to_string(),
+        });
+        included.push((variant, attrs, name));
+    }
+
+    // Plain string enum when serde represents every variant as a string
+    let all_unit = included.iter().all(|(variant, _, _)| matches!(variant.fields, Fields::Unit));
+    if all_unit && container.tag.is_none() && !container.untagged {
+        let names = included.iter().map(|(_, _, name)| name);
+        let notes: Vec<String> = included
+            .iter()
+            .filter_map(|(variant, _, name)| doc_comment(&variant.attrs).map(|doc| format!("`{}`: {}", name, doc)))
+            .collect();
+
+        let schema = quote! {
+            ::claude_rs::schema::__private::json!({"type": "string", "enum": [#(#names),*]})
+        };
+        return Ok(if notes.is_empty() {
+            schema
+        } else {
+            describe(schema, Some(&notes.join("\n")))
+        });
+    }
+
+    let mut options = Vec::new();
+    for (variant, attrs, name) in &included {
+        let rename_all = attrs.rename_all.or(container.rename_all_fields);
+        let content = fields_schema(&variant.fields, rename_all, false)?;
+
+        let option = if container.untagged {
+            content
+        } else if let Some(tag) = &container.tag {
+            match (&container.content, &variant.fields) {
+                (_, Fields::Unit) => quote!(::claude_rs::schema::__private::tagged(
+                    ::claude_rs::schema::__private::object(::claude_rs::schema::__private::Map::new(), ::std::vec::Vec::new()),
+                    #tag, #name
+                )),
+                (Some(content_key), _) => quote! {{
+                    let mut properties = ::claude_rs::schema::__private::Map::new();
+                    properties.insert(#content_key.to_string(), #content);
+                    ::claude_rs::schema::__private::tagged(
+                        ::claude_rs::schema::__private::object(properties, vec![#content_key.into()]),
+                        #tag, #name
+                    )
+                }},
+                (None, Fields::Unnamed(unnamed)) if unnamed.unnamed.len() > 1 => {
+                    return Err(Error::new_spanned(
+                        variant,
+                        "tuple variants cannot be internally tagged",
+                    ));
+                }
+                (None, _) => quote!(::claude_rs::schema::__private::tagged(#content, #tag, #name)),
+            }
+        } else if matches!(variant.fields, Fields::Unit) {
+            quote!(::claude_rs::schema::__private::json!({"type": "string", "const": #name}))
+        } else {
+            quote! {{
+                let mut properties = ::claude_rs::schema::__private::Map::new();
+                properties.insert(#name.to_string(), #content);
+                let mut schema = ::claude_rs::schema::__private::object(properties, vec![#name.into()]);
+                schema["additionalProperties"] = false.into();
+                schema
+            }}
+        };
+
+        options.push(describe(option, doc_comment(&variant.attrs).as_deref()));
+    }
+
+    let keyword = if container.untagged { "anyOf" } else { "oneOf" };
+    Ok(quote! {{
+        let options = ::std::vec![#(#options),*];
+        ::claude_rs::schema::__private::json!({ #keyword: options })
+    }})
+}
+
+/// Wrap a schema expression so it carries a description, if there is one
+fn describe(schema: TokenStream2, description: Option<&str>) -> TokenStream2 {
+    match description {
+        Some(description) => quote!(::claude_rs::schema::__private::describe(#schema, #description)),
+        None => schema,
+    }
+}
+
+/// Collect `///` doc comments into a single description
+fn doc_comment(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(nv) => match &nv.value {
+                Expr::Lit(ExprLit { lit: Lit::Str(s), .. }) => Some(s.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+
+    let doc = lines.join("\n").trim().to_string();
+    if doc.is_empty() {
+        None
+    } else {
+        Some(doc)
+    }
+}
+
+/// The serde attributes that affect the JSON shape of a type
+#[derive(Default)]
+struct SerdeAttrs {
+    rename: Option<String>,
+    rename_all: Option<RenameRule>,
+    rename_all_fields: Option<RenameRule>,
+    tag: Option<String>,
+    content: Option<String>,
+    untagged: bool,
+    default: bool,
+    skip: bool,
+    flatten: bool,
+}
+
+impl SerdeAttrs {
+    fn parse(attrs: &[Attribute]) -> Result<Self> {
+        let mut result = SerdeAttrs::default();
+
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident("serde")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    result.rename = deserialize_name(&meta)?.or(result.rename.take());
+                } else if meta.path.is_ident("rename_all") {
+                    if let Some(rule) = deserialize_name(&meta)? {
+                        result.rename_all = Some(RenameRule::parse(&rule, &meta)?);
+                    }
+                } else if meta.path.is_ident("rename_all_fields") {
+                    if let Some(rule) = deserialize_name(&meta)? {
+                        result.rename_all_fields = Some(RenameRule::parse(&rule, &meta)?);
+                    }
+                } else if meta.path.is_ident("tag") {
+                    result.tag = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("content") {
+                    result.content = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("untagged") {
+                    result.untagged = true;
+                } else if meta.path.is_ident("default") {
+                    result.default = true;
+                    skip_meta(&meta)?;
+                } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_deserializing") {
+                    result.skip = true;
+                } else if meta.path.is_ident("flatten") {
+                    result.flatten = true;
+                } else {
+                    skip_meta(&meta)?;
+                }
+                Ok(())
+            })?;
+        }
+
+        Ok(result)
+    }
+}
+
+/// Read `name = "..."` or the `deserialize` half of `name(serialize = "...", deserialize = "...")`
+fn deserialize_name(meta: &ParseNestedMeta) -> Result<Option<String>> {
+    if meta.input.peek(syn::Token![=]) {
+        return Ok(Some(meta.value()?.parse::<LitStr>()?.value()));
+    }
+
+    let mut name = None;
+    meta.parse_nested_meta(|nested| {
+        if nested.path.is_ident("deserialize") {
+            name = Some(nested.value()?.parse::<LitStr>()?.value());
+        } else {
+            skip_meta(&nested)?;
+        }
+        Ok(())
+    })?;
+    Ok(name)
+}
+
+/// Consume the value of a serde attribute that does not affect the schema
+fn skip_meta(meta: &ParseNestedMeta) -> Result<()> {
+    if meta.input.peek(syn::Token![=]) {
+        meta.value()?.parse::<Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        let content;
+        syn::parenthesized!(content in meta.input);
+        content.parse::<TokenStream2>()?;
+    }
+    Ok(())
+}
+
+/// The case conventions supported by `#[serde(rename_all = "...")]`
+#[derive(Clone, Copy)]
+enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    fn parse(rule: &str, meta: &ParseNestedMeta) -> Result<Self> {
+        Ok(match rule {
+            "lowercase" => RenameRule::Lower,
+            "UPPERCASE" => RenameRule::Upper,
+            "PascalCase" => RenameRule::Pascal,
+            "camelCase" => RenameRule::Camel,
+            "snake_case" => RenameRule::Snake,
+            "SCREAMING_SNAKE_CASE" => RenameRule::ScreamingSnake,
+            "kebab-case" => RenameRule::Kebab,
+            "SCREAMING-KEBAB-CASE" => RenameRule::ScreamingKebab,
+            other => return Err(meta.error(format!("unknown rename rule `{}`", other))),
+        })
+    }
+
+    /// Rename a variant, which is written in PascalCase
+    fn apply_to_variant(self, variant: &str) -> String {
+        match self {
+            RenameRule::Pascal => variant.to_string(),
+            RenameRule::Lower => variant.to_ascii_lowercase(),
+            RenameRule::Upper => variant.to_ascii_uppercase(),
+            RenameRule::Camel => {
+                let mut chars = variant.chars();
+                match chars.next() {
+                    Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+                    None => String::new(),
+                }
+            }
+            RenameRule::Snake | RenameRule::ScreamingSnake | RenameRule::Kebab | RenameRule::ScreamingKebab => {
+                let mut snake = String::new();
+                for (i, ch) in variant.char_indices() {
+                    if i > 0 && ch.is_uppercase() {
+                        snake.push('_');
+                    }
+                    snake.push(ch.to_ascii_lowercase());
+                }
+                from_snake_case(&snake, self)
+            }
+        }
+    }
+
+    /// Rename a field, which is written in snake_case
+    fn apply_to_field(self, field: &str) -> String {
+        from_snake_case(field, self)
+    }
+}
+
+/// Convert a snake_case identifier to the given case convention
+fn from_snake_case(field: &str, rule: RenameRule) -> String {
+    match rule {
+        RenameRule::Lower | RenameRule::Snake => field.to_string(),
+        RenameRule::Upper | RenameRule::ScreamingSnake => field.to_ascii_uppercase(),
+        RenameRule::Kebab => field.replace('_', "-"),
+        RenameRule::ScreamingKebab => field.replace('_', "-").to_ascii_uppercase(),
+        RenameRule::Pascal | RenameRule::Camel => {
+            let mut result = String::new();
+            let mut capitalize = matches!(rule, RenameRule::Pascal);
+            for ch in field.chars() {
+                if ch == '_' {
+                    capitalize = true;
+                } else if capitalize {
+                    result.push(ch.to_ascii_uppercase());
+                    capitalize = false;
+                } else {
+                    result.push(ch);
+                }
+            }
+            result
+        }
+    }
+}
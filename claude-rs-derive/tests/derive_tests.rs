@@ -0,0 +1,192 @@
+use claude_rs::schema::{parse_tool_input, JsonSchema};
+use claude_rs::types::Tool;
+use claude_rs::ToolRegistry;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+
+/// Look up the weather forecast
+#[derive(Debug, Deserialize, JsonSchema)]
+#[allow(dead_code)]
+struct WeatherInput {
+    /// City and optional country
+    location: String,
+    /// Number of days to forecast
+    days: Option<u8>,
+    #[serde(default)]
+    unit: Unit,
+    #[serde(skip)]
+    cache_key: String,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Unit {
+    /// Degrees Celsius
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+#[test]
+fn test_struct_schema() {
+    let schema = WeatherInput::json_schema();
+
+    assert_eq!(schema, json!({
+        "type": "object",
+        "description": "Look up the weather forecast",
+        "properties": {
+            "location": {"type": "string", "description": "City and optional country"},
+            "days": {"type": "integer", "minimum": 0, "description": "Number of days to forecast"},
+            "unit": {
+                "type": "string",
+                "enum": ["celsius", "fahrenheit"],
+                "description": "`celsius`: Degrees Celsius"
+            }
+        },
+        "required": ["location"]
+    }));
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+#[allow(dead_code)]
+struct Order {
+    order_id: u64,
+    #[serde(rename = "lineItems")]
+    items: Vec<LineItem>,
+    metadata: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[allow(dead_code)]
+struct LineItem {
+    sku: String,
+    quantity: u32,
+}
+
+#[test]
+fn test_renames_and_nested_types() {
+    let schema = Order::json_schema();
+
+    assert_eq!(schema["required"], json!(["orderId", "lineItems", "metadata"]));
+    assert_eq!(schema["properties"]["lineItems"]["type"], "array");
+    assert_eq!(schema["properties"]["lineItems"]["items"]["required"], json!(["sku", "quantity"]));
+    assert_eq!(schema["properties"]["metadata"]["additionalProperties"], json!({"type": "string"}));
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(tag = "action", rename_all = "snake_case")]
+#[allow(dead_code)]
+enum Command {
+    /// Move to a position
+    MoveTo { x: i32, y: i32 },
+    Stop,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[allow(dead_code)]
+enum Shape {
+    Circle { radius: f64 },
+    Square(f64),
+    Empty,
+}
+
+#[test]
+fn test_internally_tagged_enum() {
+    let schema = Command::json_schema();
+
+    assert_eq!(schema, json!({
+        "oneOf": [
+            {
+                "type": "object",
+                "description": "Move to a position",
+                "properties": {
+                    "action": {"type": "string", "const": "move_to"},
+                    "x": {"type": "integer"},
+                    "y": {"type": "integer"}
+                },
+                "required": ["action", "x", "y"]
+            },
+            {
+                "type": "object",
+                "properties": {"action": {"type": "string", "const": "stop"}},
+                "required": ["action"]
+            }
+        ]
+    }));
+
+    let command: Command = parse_tool_input(&json!({"action": "move_to", "x": 1, "y": 2})).unwrap();
+    assert!(matches!(command, Command::MoveTo { x: 1, y: 2 }));
+}
+
+#[test]
+fn test_externally_tagged_enum() {
+    let schema = Shape::json_schema();
+    let options = schema["oneOf"].as_array().unwrap();
+
+    assert_eq!(options.len(), 3);
+    assert_eq!(options[0]["required"], json!(["Circle"]));
+    assert_eq!(options[0]["properties"]["Circle"]["required"], json!(["radius"]));
+    assert_eq!(options[1]["properties"]["Square"], json!({"type": "number"}));
+    assert_eq!(options[2], json!({"type": "string", "const": "Empty"}));
+}
+
+#[derive(Deserialize, JsonSchema)]
+#[allow(dead_code)]
+struct Page<T> {
+    items: Vec<T>,
+    next: Option<String>,
+}
+
+#[test]
+fn test_generic_struct() {
+    let schema = Page::<LineItem>::json_schema();
+
+    assert_eq!(schema["required"], json!(["items"]));
+    assert_eq!(schema["properties"]["items"]["items"]["type"], "object");
+}
+
+#[test]
+fn test_tool_from_type_and_field_errors() {
+    let tool = Tool::from_type::<WeatherInput>("get_weather", "Get the weather");
+    assert_eq!(tool.input_schema, WeatherInput::json_schema());
+
+    let input: WeatherInput = parse_tool_input(&json!({"location": "Tokyo", "unit": "fahrenheit"})).unwrap();
+    assert_eq!(input.location, "Tokyo");
+    assert_eq!(input.unit, Unit::Fahrenheit);
+
+    let error = parse_tool_input::<WeatherInput>(&json!({"location": "Tokyo", "days": 500})).unwrap_err();
+    assert!(error.to_string().contains("`days`"), "{}", error);
+
+    let error = parse_tool_input::<Order>(&json!({
+        "orderId": 1,
+        "lineItems": [{"sku": "a", "quantity": 1}, {"sku": "b", "quantity": "two"}],
+        "metadata": {}
+    })).unwrap_err();
+    assert!(error.to_string().contains("`lineItems[1].quantity`"), "{}", error);
+
+    let error = parse_tool_input::<WeatherInput>(&json!({"days": 1})).unwrap_err();
+    assert!(error.to_string().contains("missing field `location`"), "{}", error);
+}
+
+#[tokio::test]
+async fn test_typed_registry_handler() {
+    let registry = ToolRegistry::new().with_typed_tool("get_weather", "Get the weather", |input: WeatherInput| async move {
+        Ok(format!("Sunny in {}", input.location))
+    });
+
+    assert_eq!(registry.tools()[0].input_schema, WeatherInput::json_schema());
+
+    let result = registry.execute("toolu_1", "get_weather", json!({"location": "Tokyo"})).await;
+    assert!(matches!(result, claude_rs::Content::ToolResult { is_error: false, .. }));
+
+    // Invalid input is reported back to the model without calling the handler
+    let result = registry.execute("toolu_2", "get_weather", json!({"location": 7})).await;
+    match result {
+        claude_rs::Content::ToolResult { is_error: true, content: Some(claude_rs::types::ToolResultContent::Text(text)), .. } => {
+            assert!(text.contains("`location`"), "{}", text);
+        }
+        other => panic!("Expected tool error, got {:?}", other),
+    }
+}
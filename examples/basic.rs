@@ -39,7 +39,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .await?;
         
     // Extract text from the response
-    if let Some(Content::Text { text }) = response.content.first() {
+    if let Some(Content::Text { text, .. }) = response.content.first() {
         println!("Claude's response: {}", text);
     }
     
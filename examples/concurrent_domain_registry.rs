@@ -1,4 +1,4 @@
-use claude_rs::{Claude, ClaudeModel};
+use claude_rs::{Claude, ClaudeModel, RateLimiter, RateLimits};
 use claude_rs::domains::DomainClient;
 use std::sync::Arc;
 use tokio::task;
@@ -133,8 +133,12 @@ async fn main() {
     println!("Claude-rs Concurrent Domain Registry Example");
     println!("===========================================\n");
     
-    // Create a Claude client
-    let client = Arc::new(Claude::new("test-key").with_model(ClaudeModel::Sonnet));
+    // Create a Claude client; every task sharing it queues behind one rate limiter
+    let client = Arc::new(
+        Claude::new("test-key")
+            .with_model(ClaudeModel::Sonnet)
+            .with_rate_limiter(RateLimiter::new(RateLimits::new().with_requests_per_minute(50)))
+    );
     
     // 1. Register multiple domain clients concurrently
     println!("1. Registering 10 custom domains concurrently...");
@@ -22,7 +22,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     if let Some(content) = response.content.first() {
         match content {
-            Content::Text { text } => {
+            Content::Text { text, .. } => {
                 println!("Sentiment analysis result: {}\n", text);
             },
             _ => println!("Unexpected content type"),
@@ -39,7 +39,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     if let Some(content) = response.content.first() {
         match content {
-            Content::Text { text } => {
+            Content::Text { text, .. } => {
                 println!("Entity extraction result: {}\n", text);
             },
             _ => println!("Unexpected content type"),
@@ -56,7 +56,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     if let Some(content) = response.content.first() {
         match content {
-            Content::Text { text } => {
+            Content::Text { text, .. } => {
                 println!("Generated content:\n{}\n", text);
             },
             _ => println!("Unexpected content type"),
@@ -79,7 +79,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         
     if let Some(content) = response.content.first() {
         match content {
-            Content::Text { text } => {
+            Content::Text { text, .. } => {
                 println!("Code analysis result: {}\n", text);
             },
             _ => println!("Unexpected content type"),
@@ -1,6 +1,6 @@
 // Import the main modules
 use claude_rs::{self, Content};
-use claude_rs::types::{Tool, ToolUse, ToolResult};
+use claude_rs::types::Tool;
 use serde_json::json;
 
 #[tokio::main]
@@ -27,6 +27,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             },
             "required": ["location"]
         }),
+        cache_control: None,
     };
     
     println!("1. Defined weather tool: {}", weather_tool.name);
@@ -35,20 +36,20 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     
     // This would normally be the API response
     // Simulate the content Claude would return when using a tool
-    let tool_use = ToolUse {
-        id: "call_123456".to_string(),
-        name: "get_weather".to_string(),
-        parameters: json!({"location": "Tokyo"}),
+    let tool_use = Content::tool_use("toolu_123456", "get_weather", json!({"location": "Tokyo"}));
+    
+    let Content::ToolUse { id, name, input, .. } = &tool_use else {
+        unreachable!("tool_use always creates a ToolUse block");
     };
     
     // Simulated response content
     println!("2. Claude uses the tool:");
-    println!("   Tool: {}", tool_use.name);
-    println!("   Parameters: {}", tool_use.parameters);
-    println!("   Call ID: {}\n", tool_use.id);
+    println!("   Tool: {}", name);
+    println!("   Input: {}", input);
+    println!("   Tool use ID: {}\n", id);
     
-    // Extract location from the parameters
-    let location = tool_use.parameters.get("location")
+    // Extract location from the input
+    let location = input.get("location")
         .and_then(|v| v.as_str())
         .unwrap_or("unknown location");
     
@@ -65,18 +66,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("   Weather data: {}\n", weather_data);
     
     // Create a tool result that would be sent to Claude
-    let tool_result = ToolResult { 
-        content: weather_data.to_string()
-    };
-    
-    // In a real application, you'd create a message with this content
-    let _tool_result_content = Content::ToolResult { 
-        tool_result,
-        tool_call_id: tool_use.id.clone(),
-    };
+    // In a real application, you'd send this content in a user message.
+    // Use Content::tool_error instead if the tool call failed.
+    let tool_result = Content::tool_result(id.clone(), weather_data.to_string());
     
     println!("4. Creating user message with tool result");
-    println!("   Tool call ID: {}", tool_use.id);
+    println!("   Wire format: {}", serde_json::to_string(&tool_result)?);
+    println!("   Tool use ID: {}", id);
     println!("   Result content: {}\n", weather_data);
     
     // Simulated final response from Claude after receiving the tool result
@@ -129,6 +129,9 @@ mod test_examples {
                 message: "Invalid request".to_string(),
                 response_body: None,
                 location: None,
+                retry_after: None,
+                kind: ApiErrorKind::InvalidRequestError,
+                request_id: None,
             }
         );
         
@@ -0,0 +1,59 @@
+// Typed tools: the input schema is derived from the struct the handler receives
+//
+// Run with: cargo run --example typed_tools --features derive
+use claude_rs::{Claude, JsonSchema, ToolRegistry};
+use claude_rs::types::Tool;
+use serde::Deserialize;
+
+/// Get the current weather for a location
+#[derive(Debug, Deserialize, JsonSchema)]
+struct WeatherInput {
+    /// The city and optional country, e.g. "Tokyo, Japan"
+    location: String,
+    /// Temperature unit to report in
+    #[serde(default)]
+    unit: TemperatureUnit,
+}
+
+#[derive(Debug, Default, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+enum TemperatureUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // The schema sent to the API is generated from WeatherInput, including doc comments
+    let tool = Tool::from_type::<WeatherInput>("get_weather", "Get the current weather for a location");
+    println!("Generated schema:\n{}\n", serde_json::to_string_pretty(&tool.input_schema)?);
+
+    // Typed handlers receive the deserialized input; invalid input is reported
+    // back to the model with the name of the offending field
+    let registry = ToolRegistry::new().with_typed_tool(
+        "get_weather",
+        "Get the current weather for a location",
+        |input: WeatherInput| async move {
+            let temperature = match input.unit {
+                TemperatureUnit::Celsius => "22°C",
+                TemperatureUnit::Fahrenheit => "72°F",
+            };
+            Ok(format!("It is {} and sunny in {}", temperature, input.location))
+        },
+    );
+
+    let Ok(api_key) = std::env::var("ANTHROPIC_API_KEY") else {
+        println!("Set ANTHROPIC_API_KEY to run the tool loop against the API");
+        return Ok(());
+    };
+
+    let run = Claude::new(api_key)
+        .message()
+        .user_content("What's the weather like in Tokyo, in fahrenheit?")
+        .run_tools(&registry)
+        .await?;
+
+    println!("Final response after {} calls: {:?}", run.iterations, run.response.content);
+    Ok(())
+}
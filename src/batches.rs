@@ -0,0 +1,416 @@
+// Message Batches API
+
+use crate::builder::MessageBuilder;
+use crate::cancel::{cancellable, cancellable_stream};
+use crate::client::{chunks_within, Claude};
+use crate::types::*;
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Maximum number of requests in a single batch
+pub const MAX_BATCH_REQUESTS: usize = 100_000;
+
+/// Maximum length of a batch request's `custom_id`
+pub const MAX_CUSTOM_ID_LENGTH: usize = 64;
+
+/// Stream of the results of an ended batch
+pub type BatchResultStream = Pin<Box<dyn Stream<Item = ClaudeResult<BatchResult>> + Send>>;
+
+/// A single request in a message batch
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchRequest {
+    /// Identifies the request's result; must be unique within the batch
+    pub custom_id: String,
+    /// The parameters that would be sent to the Messages API
+    pub params: MessageRequest,
+}
+
+impl BatchRequest {
+    /// Create a batch request from an id and Messages API parameters
+    pub fn new(custom_id: impl Into<String>, params: MessageRequest) -> Self {
+        Self {
+            custom_id: custom_id.into(),
+            params,
+        }
+    }
+}
+
+impl<S: Into<String>> From<(S, MessageRequest)> for BatchRequest {
+    fn from((custom_id, params): (S, MessageRequest)) -> Self {
+        Self::new(custom_id, params)
+    }
+}
+
+/// Processing status of a message batch
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchStatus {
+    InProgress,
+    Canceling,
+    Ended,
+}
+
+/// Number of requests in a batch by processing state
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BatchRequestCounts {
+    pub processing: u32,
+    pub succeeded: u32,
+    pub errored: u32,
+    pub canceled: u32,
+    pub expired: u32,
+}
+
+impl BatchRequestCounts {
+    /// Get the total number of requests in the batch
+    pub fn total(&self) -> u32 {
+        self.processing + self.succeeded + self.errored + self.canceled + self.expired
+    }
+}
+
+/// A message batch, as returned when it is created, retrieved or canceled
+///
+/// Timestamps are RFC 3339 strings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageBatch {
+    pub id: String,
+    pub processing_status: BatchStatus,
+    pub request_counts: BatchRequestCounts,
+    pub created_at: String,
+    pub expires_at: String,
+    #[serde(default)]
+    pub ended_at: Option<String>,
+    #[serde(default)]
+    pub cancel_initiated_at: Option<String>,
+    #[serde(default)]
+    pub archived_at: Option<String>,
+    /// Where the results can be downloaded, once the batch has ended
+    #[serde(default)]
+    pub results_url: Option<String>,
+}
+
+impl MessageBatch {
+    /// Check whether the batch has finished processing
+    pub fn is_ended(&self) -> bool {
+        self.processing_status == BatchStatus::Ended
+    }
+}
+
+/// The outcome of a single batch request
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BatchOutcome {
+    /// The request completed and produced a response
+    Succeeded { message: MessageResponse },
+    /// The request failed with an API error
+    Errored { error: ApiErrorResponse },
+    /// The batch expired before the request was processed
+    Expired,
+    /// The batch was canceled before the request was processed
+    Canceled,
+}
+
+/// The result of a single batch request, identified by its `custom_id`
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchResult {
+    pub custom_id: String,
+    pub result: BatchOutcome,
+}
+
+impl BatchResult {
+    /// Check whether the request succeeded
+    pub fn is_success(&self) -> bool {
+        matches!(self.result, BatchOutcome::Succeeded { .. })
+    }
+
+    /// Get the response of a succeeded request
+    ///
+    /// Errored requests return their typed API error; expired and canceled
+    /// requests return a `RequestError`.
+    pub fn message(&self) -> ClaudeResult<&MessageResponse> {
+        match &self.result {
+            BatchOutcome::Succeeded { message } => Ok(message),
+            BatchOutcome::Errored { error } => Err(ClaudeError::from_stream_error(
+                error.error.clone(),
+                Some(concat!(file!(), ":", line!()))
+            )),
+            BatchOutcome::Expired => Err(self.unprocessed_error("expired")),
+            BatchOutcome::Canceled => Err(self.unprocessed_error("was canceled")),
+        }
+    }
+
+    /// Take the response of a succeeded request
+    pub fn into_message(self) -> ClaudeResult<MessageResponse> {
+        self.message()?;
+        match self.result {
+            BatchOutcome::Succeeded { message } => Ok(message),
+            _ => unreachable!("non-success outcomes return an error above"),
+        }
+    }
+
+    fn unprocessed_error(&self, reason: &str) -> ClaudeError {
+        ClaudeError::request_error(
+            format!("Batch request '{}' {} before it was processed", self.custom_id, reason),
+            None,
+            None::<reqwest::Error>,
+            Some(concat!(file!(), ":", line!()))
+        )
+    }
+}
+
+/// Client for the Message Batches API
+///
+/// Batches process many Messages API requests asynchronously, at a lower
+/// price than synchronous calls. Results become available once the whole
+/// batch has ended, which can take up to 24 hours.
+///
+/// ## Example
+///
+/// ```no_run
+/// # use claude_rs::{Claude, BatchRequest};
+/// # use std::time::Duration;
+/// # async fn example(claude: Claude) -> Result<(), claude_rs::ClaudeError> {
+/// use futures::StreamExt;
+///
+/// let sentiment = claude.sentiment();
+/// let mut requests = Vec::new();
+/// for (id, review) in [("review-1", "Great product"), ("review-2", "Broke in a day")] {
+///     requests.push(sentiment.batch_request(id, review).await?);
+/// }
+///
+/// let batches = claude.batches();
+/// let batch = batches.submit(requests).await?;
+/// let batch = batches.wait_until_ended(&batch.id, Duration::from_secs(60), None).await?;
+///
+/// let mut results = batches.results(&batch.id).await?;
+/// while let Some(result) = results.next().await {
+///     let result = result?;
+///     match sentiment.parse_batch_result(&result).await {
+///         Ok(analysis) => println!("{}: {:?}", result.custom_id, analysis.sentiment),
+///         Err(e) => println!("{}: {}", result.custom_id, e),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct BatchClient {
+    claude: Arc<Claude>,
+}
+
+impl BatchClient {
+    pub(crate) fn new(claude: Arc<Claude>) -> Self {
+        Self { claude }
+    }
+
+    /// Submit a batch of `(custom_id, MessageRequest)` pairs or `BatchRequest`s
+    ///
+    /// Ids must be unique, 1 to 64 characters long, and use only ASCII letters,
+    /// digits, `-` and `_`. Batch requests cannot stream, so `stream` is cleared.
+    pub async fn submit<I, R>(&self, requests: I) -> ClaudeResult<MessageBatch>
+    where
+        I: IntoIterator<Item = R>,
+        R: Into<BatchRequest>,
+    {
+        let mut requests: Vec<BatchRequest> = requests.into_iter().map(Into::into).collect();
+        Self::validate_requests(&requests)?;
+        for request in &mut requests {
+            request.params.stream = None;
+        }
+
+        let body = serde_json::json!({ "requests": requests });
+        let endpoint = self.endpoint("");
+        self.claude.send_json(|http| http.post(&endpoint).json(&body)).await
+    }
+
+    /// Get the current state of a batch
+    pub async fn retrieve(&self, batch_id: &str) -> ClaudeResult<MessageBatch> {
+        let endpoint = self.endpoint(&format!("/{}", batch_id));
+        self.claude.send_json(|http| http.get(&endpoint)).await
+    }
+
+    /// Poll a batch every `poll_interval` until it has ended
+    ///
+    /// With `max_wait`, the batch is polled one last time once it has passed
+    /// and an error is returned if it still has not ended. Polling stops with
+    /// `ClaudeError::Cancelled` when the client's cancellation token fires.
+    pub async fn wait_until_ended(
+        &self,
+        batch_id: &str,
+        poll_interval: Duration,
+        max_wait: Option<Duration>,
+    ) -> ClaudeResult<MessageBatch> {
+        let deadline = max_wait.map(|wait| tokio::time::Instant::now() + wait);
+        cancellable(self.claude.cancellation.as_ref(), async {
+            loop {
+                let batch = self.retrieve(batch_id).await?;
+                if batch.is_ended() {
+                    return Ok(batch);
+                }
+
+                let mut delay = poll_interval;
+                if let Some(deadline) = deadline {
+                    let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+                    if remaining.is_zero() {
+                        return Err(ClaudeError::request_error(
+                            format!("Batch '{}' did not end within {:?}", batch_id, max_wait.unwrap_or_default()),
+                            Some(format!("Last status: {:?}", batch.processing_status)),
+                            None::<reqwest::Error>,
+                            Some(concat!(file!(), ":", line!()))
+                        ));
+                    }
+                    delay = delay.min(remaining);
+                }
+                tokio::time::sleep(delay).await;
+            }
+        }).await
+    }
+
+    /// Cancel a batch
+    ///
+    /// The batch moves to `canceling` until requests already being processed
+    /// finish; requests that were not started are reported as canceled.
+    pub async fn cancel(&self, batch_id: &str) -> ClaudeResult<MessageBatch> {
+        let endpoint = self.endpoint(&format!("/{}/cancel", batch_id));
+        self.claude.send_json(|http| http.post(&endpoint)).await
+    }
+
+    /// Stream the results of an ended batch
+    ///
+    /// Results are decoded from the JSONL results file as they are downloaded
+    /// and may be in any order; use `custom_id` to match them to requests.
+    /// The stream ends with `ClaudeError::Cancelled` when the client's
+    /// cancellation token fires.
+    pub async fn results(&self, batch_id: &str) -> ClaudeResult<BatchResultStream> {
+        let batch = self.retrieve(batch_id).await?;
+        let results_url = match (&batch.processing_status, batch.results_url) {
+            (BatchStatus::Ended, Some(url)) => url,
+            (status, _) => {
+                return Err(ClaudeError::ValidationError(format!(
+                    "Results of batch '{}' are not available while it is {:?}",
+                    batch_id, status
+                )));
+            }
+        };
+
+        let response = cancellable(self.claude.cancellation.as_ref(), self.claude.retry_policy
+            .execute(|| async {
+                let request = self.claude.authorize(self.claude.http_client.get(&results_url));
+                let response = self.claude.execute(request).await?;
+                MessageBuilder::handle_error_response(response).await
+            }))
+            .await?;
+
+        // Decode lines as they arrive; the final line may not end with a newline
+        let results = chunks_within(self.claude.read_timeout, response.bytes_stream())
+            .map(|chunk| chunk.map(Some))
+            .chain(futures::stream::once(async { Ok::<_, ClaudeError>(None) }))
+            .scan(JsonlDecoder::default(), |decoder, chunk| {
+                let items: Vec<ClaudeResult<BatchResult>> = match chunk {
+                    Ok(Some(bytes)) => decoder.decode(&bytes).into_iter().filter_map(Self::parse_result_line).collect(),
+                    Ok(None) => decoder.finish().into_iter().filter_map(Self::parse_result_line).collect(),
+                    Err(e) => vec![Err(e)],
+                };
+                futures::future::ready(Some(futures::stream::iter(items)))
+            })
+            .flatten()
+            .boxed();
+
+        Ok(cancellable_stream(results, self.claude.cancellation.clone()))
+    }
+
+    /// Check the batch size and request ids before anything is sent
+    fn validate_requests(requests: &[BatchRequest]) -> ClaudeResult<()> {
+        if requests.is_empty() {
+            return Err(ClaudeError::ValidationError("A batch needs at least one request".to_string()));
+        }
+        if requests.len() > MAX_BATCH_REQUESTS {
+            return Err(ClaudeError::ValidationError(format!(
+                "A batch can have at most {} requests, found {}",
+                MAX_BATCH_REQUESTS,
+                requests.len()
+            )));
+        }
+
+        let mut seen = HashSet::new();
+        for request in requests {
+            let id = &request.custom_id;
+            let valid = !id.is_empty()
+                && id.len() <= MAX_CUSTOM_ID_LENGTH
+                && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid {
+                return Err(ClaudeError::ValidationError(format!(
+                    "Invalid custom_id '{}': use 1 to {} ASCII letters, digits, '-' or '_'",
+                    id, MAX_CUSTOM_ID_LENGTH
+                )));
+            }
+            if !seen.insert(id.as_str()) {
+                return Err(ClaudeError::ValidationError(format!("Duplicate custom_id '{}'", id)));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Parse a line of the results file, skipping blank lines
+    fn parse_result_line(line: Vec<u8>) -> Option<ClaudeResult<BatchResult>> {
+        let line = String::from_utf8_lossy(&line);
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+
+        Some(serde_json::from_str(line).map_err(|e| ClaudeError::parse_error(
+            format!("Failed to parse batch result: {}", e),
+            Some(line.to_string()),
+            Some(e),
+            Some(concat!(file!(), ":", line!()))
+        )))
+    }
+
+    fn endpoint(&self, path: &str) -> String {
+        format!("{}/messages/batches{}", self.claude.base_url, path)
+    }
+}
+
+impl std::fmt::Debug for BatchClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BatchClient")
+            .field("base_url", &self.claude.base_url)
+            .finish()
+    }
+}
+
+/// Splits a byte stream into lines, buffering partial lines across chunks
+#[derive(Debug, Default)]
+struct JsonlDecoder {
+    buffer: Vec<u8>,
+}
+
+impl JsonlDecoder {
+    /// Feed a chunk and return the lines it completed
+    fn decode(&mut self, chunk: &[u8]) -> Vec<Vec<u8>> {
+        self.buffer.extend_from_slice(chunk);
+
+        let mut lines = Vec::new();
+        while let Some(end) = self.buffer.iter().position(|&b| b == b'\n') {
+            let mut line: Vec<u8> = self.buffer.drain(..=end).collect();
+            line.pop();
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// Return the final line if the stream did not end with a newline
+    fn finish(&mut self) -> Option<Vec<u8>> {
+        if self.buffer.is_empty() {
+            None
+        } else {
+            Some(std::mem::take(&mut self.buffer))
+        }
+    }
+}
@@ -1,14 +1,28 @@
 // Message Builder
 
 use crate::types::*;
-use crate::client::Claude;
+use crate::client::{chunks_within, read_within, Claude};
+use crate::client_builder::DEFAULT_API_VERSION;
 use crate::middleware::{ContextManager, RequestMiddleware, ResponseMiddleware};
-use crate::utils::{validate_range, StringValidator};
+use crate::retry::RetryPolicy;
+use crate::tools::{ToolRegistry, ToolRun, DEFAULT_MAX_TOOL_ITERATIONS};
+use crate::streaming::{ContentEvent, ContentStream};
+use crate::count_tokens::{CountTokensRequest, TokenCount};
+use crate::models::ModelInfo;
+use crate::rate_limit::RateLimiter;
+use crate::metadata::{ResponseMetadata, StreamMetadata};
+use crate::cancel::{cancellable, cancellable_stream};
+use crate::stall::{stall_guard, StreamTimeouts};
+use crate::continuation::{continuation_messages, join_continuation};
+use crate::images::validate_request_images;
+use crate::utils::{validate_range, CollectionValidator, StringValidator};
+use crate::utils::sse::{SseDecoder, SseEvent};
 
 use reqwest::Client as HttpClient;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use futures::StreamExt;
+use tokio_util::sync::CancellationToken;
 
 /// A struct for building Claude message requests with a fluent interface.
 pub struct MessageBuilder {
@@ -22,14 +36,23 @@ pub struct MessageBuilder {
     
     // Common request parameters
     model: ClaudeModel,
-    system: Option<String>,
+    system: Option<SystemPrompt>,
     messages: Vec<Message>,
     temperature: Option<f32>,
     max_tokens: Option<u32>,
     tools: Vec<Tool>,
+    tools_cache_control: Option<CacheControl>,
+    tool_choice: Option<ToolChoice>,
+    thinking: Option<ThinkingConfig>,
     top_p: Option<f32>,
     top_k: Option<u32>,
     stop_sequences: Vec<String>,
+    retry_policy: Option<RetryPolicy>,
+    cancellation: Option<CancellationToken>,
+    stream_timeouts: StreamTimeouts,
+    max_continuations: u32,
+    max_tool_iterations: u32,
+    cost_tag: Option<String>,
     
     // Middleware components
     context_manager: Option<Arc<dyn ContextManager>>,
@@ -62,9 +85,18 @@ impl MessageBuilder {
             temperature: None,
             max_tokens: None,
             tools: Vec::new(),
+            tools_cache_control: None,
+            tool_choice: None,
+            thinking: None,
             top_p: None,
             top_k: None,
             stop_sequences: Vec::new(),
+            retry_policy: None,
+            cancellation: None,
+            stream_timeouts: StreamTimeouts::default(),
+            max_continuations: 0,
+            max_tool_iterations: DEFAULT_MAX_TOOL_ITERATIONS,
+            cost_tag: None,
             context_manager,
             request_middleware,
             response_middleware,
@@ -85,11 +117,20 @@ impl MessageBuilder {
             system: None,
             messages: Vec::new(),
             temperature: None,
-            max_tokens: client.default_max_tokens, // Get client default max_tokens if available
+            max_tokens: None, // The client default is resolved against the model's limits
             tools: Vec::new(),
+            tools_cache_control: None,
+            tool_choice: None,
+            thinking: None,
             top_p: None,
             top_k: None,
             stop_sequences: Vec::new(),
+            retry_policy: None, // Will be retrieved from client as needed
+            cancellation: None, // Will be retrieved from client as needed
+            stream_timeouts: StreamTimeouts::default(),
+            max_continuations: 0,
+            max_tool_iterations: DEFAULT_MAX_TOOL_ITERATIONS,
+            cost_tag: None,
             context_manager: None, // Will be retrieved from client as needed
             request_middleware: Vec::new(), // Will be retrieved from client as needed
             response_middleware: Vec::new(), // Will be retrieved from client as needed
@@ -109,12 +150,14 @@ impl MessageBuilder {
         }
     }
     
-    /// Get the API key to use for requests
-    fn get_api_key(&self) -> &SecureApiKey {
+    /// Add the authentication and API headers to a request
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
         if let Some(client) = &self.client_ref {
-            &client.api_key
+            client.authorize(request)
         } else if let Some(api_key) = &self.api_key {
-            api_key
+            request
+                .header("x-api-key", api_key.as_str())
+                .header("anthropic-version", DEFAULT_API_VERSION)
         } else {
             panic!("No API key available")
         }
@@ -131,6 +174,29 @@ impl MessageBuilder {
         }
     }
     
+    /// Get the client's default max_tokens (if any)
+    fn get_default_max_tokens(&self) -> Option<u32> {
+        self.client_ref.as_ref().and_then(|client| client.default_max_tokens)
+    }
+    
+    /// Send a request through the client, applying its read timeout and certificate pins
+    async fn execute(&self, request: reqwest::RequestBuilder) -> ClaudeResult<reqwest::Response> {
+        match &self.client_ref {
+            Some(client) => client.execute(request).await,
+            None => Ok(request.send().await?),
+        }
+    }
+    
+    /// Get the client's read timeout (if any)
+    fn get_read_timeout(&self) -> Option<Duration> {
+        self.client_ref.as_ref().and_then(|client| client.read_timeout)
+    }
+    
+    /// Get the rate limiter to use (if any)
+    fn get_rate_limiter(&self) -> Option<&RateLimiter> {
+        self.client_ref.as_ref().and_then(|client| client.rate_limiter.as_ref())
+    }
+    
     /// Get the context manager to use (if any)
     fn get_context_manager(&self) -> Option<Arc<dyn ContextManager>> {
         if self.context_manager.is_some() {
@@ -144,6 +210,36 @@ impl MessageBuilder {
         }
     }
     
+    /// Get the retry policy to use
+    fn get_retry_policy(&self) -> RetryPolicy {
+        if let Some(policy) = &self.retry_policy {
+            return policy.clone();
+        }
+        
+        if let Some(client) = &self.client_ref {
+            client.retry_policy.clone()
+        } else {
+            RetryPolicy::none()
+        }
+    }
+    
+    /// Get the cancellation token to observe (if any)
+    fn get_cancellation_token(&self) -> Option<CancellationToken> {
+        if self.cancellation.is_some() {
+            return self.cancellation.clone();
+        }
+        
+        self.client_ref.as_ref().and_then(|client| client.cancellation.clone())
+    }
+    
+    /// Fail early if the request was cancelled before it started
+    fn check_cancelled(token: Option<&CancellationToken>) -> ClaudeResult<()> {
+        match token {
+            Some(token) if token.is_cancelled() => Err(ClaudeError::Cancelled),
+            _ => Ok(()),
+        }
+    }
+    
     /// Get the request middleware to use
     fn get_request_middleware(&self) -> Vec<Arc<dyn RequestMiddleware>> {
         if !self.request_middleware.is_empty() {
@@ -174,7 +270,42 @@ impl MessageBuilder {
     ///
     /// The system prompt provides high-level instructions for the assistant.
     pub fn system(mut self, system: impl Into<String>) -> ClaudeResult<Self> {
-        self.system = Some(StringValidator::not_empty(system, "system")?);
+        self.system = Some(SystemPrompt::Text(StringValidator::not_empty(system, "system")?));
+        Ok(self)
+    }
+    
+    /// Set a system prompt that is cached between requests
+    ///
+    /// The system prompt, and the tools that precede it in the prompt, are
+    /// cached up to this breakpoint, so later requests with the same prefix
+    /// are cheaper and faster.
+    pub fn cached_system(mut self, system: impl Into<String>, cache_control: CacheControl) -> ClaudeResult<Self> {
+        let text = StringValidator::not_empty(system, "system")?;
+        self.system = Some(SystemPrompt::Blocks(vec![SystemBlock::new(text).with_cache_control(cache_control)]));
+        Ok(self)
+    }
+    
+    /// Append a block to the system prompt
+    ///
+    /// Blocks are sent in order, so a stable prefix with a cache breakpoint can
+    /// be kept apart from per-request instructions. A prompt set earlier with
+    /// `system` becomes the first block.
+    pub fn system_block(mut self, block: SystemBlock) -> ClaudeResult<Self> {
+        StringValidator::not_empty(block.text.as_str(), "system block")?;
+        let mut blocks = self.system.take().map(SystemPrompt::into_blocks).unwrap_or_default();
+        blocks.push(block);
+        self.system = Some(SystemPrompt::Blocks(blocks));
+        Ok(self)
+    }
+    
+    /// Set the system prompt to a list of blocks, replacing any previous prompt
+    pub fn system_blocks(mut self, blocks: impl IntoIterator<Item = SystemBlock>) -> ClaudeResult<Self> {
+        let blocks: Vec<SystemBlock> = blocks.into_iter().collect();
+        let blocks = CollectionValidator::not_empty(blocks, "system blocks")?;
+        for block in &blocks {
+            StringValidator::not_empty(block.text.as_str(), "system block")?;
+        }
+        self.system = Some(SystemPrompt::Blocks(blocks));
         Ok(self)
     }
     
@@ -192,7 +323,7 @@ impl MessageBuilder {
     pub fn user_content(mut self, text: impl Into<String>) -> Self {
         let message = Message {
             role: Role::User,
-            content: vec![Content::Text { text: text.into() }],
+            content: vec![Content::text(text)],
         };
         self.messages.push(message);
         self
@@ -213,7 +344,7 @@ impl MessageBuilder {
     pub fn assistant_content(mut self, text: impl Into<String>) -> Self {
         let message = Message {
             role: Role::Assistant,
-            content: vec![Content::Text { text: text.into() }],
+            content: vec![Content::text(text)],
         };
         self.messages.push(message);
         self
@@ -265,6 +396,50 @@ impl MessageBuilder {
         self
     }
     
+    /// Cache the tool definitions between requests
+    ///
+    /// The breakpoint is placed on the last tool when the request is sent, so
+    /// it also covers tools added later, such as those from a `ToolRegistry`.
+    pub fn cache_tools(mut self, cache_control: CacheControl) -> Self {
+        self.tools_cache_control = Some(cache_control);
+        self
+    }
+    
+    /// Control how Claude uses the declared tools
+    ///
+    /// A forced tool name must match one of the tools added with `add_tool`;
+    /// this is checked when the request is sent.
+    pub fn tool_choice(mut self, choice: ToolChoice) -> Self {
+        self.tool_choice = Some(choice);
+        self
+    }
+    
+    /// Limit Claude to at most one tool call per response
+    ///
+    /// Applies to the current tool choice, or to `ToolChoice::auto()` if none is set.
+    pub fn disable_parallel_tool_use(mut self) -> Self {
+        let choice = self.tool_choice.take().unwrap_or_else(ToolChoice::auto);
+        self.tool_choice = Some(choice.disable_parallel_tool_use());
+        self
+    }
+    
+    /// Enable extended thinking with a budget of `budget_tokens`
+    ///
+    /// The model reasons in `thinking` blocks before answering. The budget
+    /// must be at least 1024 tokens and less than `max_tokens`, which is
+    /// checked when the request is sent. Thinking cannot be combined with a
+    /// custom temperature, `top_k`, or a forced tool choice.
+    pub fn thinking(mut self, budget_tokens: u32) -> ClaudeResult<Self> {
+        if budget_tokens < MIN_THINKING_BUDGET_TOKENS {
+            return Err(ClaudeError::ValidationError(format!(
+                "thinking budget_tokens must be at least {}, but got {}",
+                MIN_THINKING_BUDGET_TOKENS, budget_tokens
+            )));
+        }
+        self.thinking = Some(ThinkingConfig::enabled(budget_tokens));
+        Ok(self)
+    }
+    
     /// Set the top_p parameter (between 0.0 and 1.0)
     ///
     /// Controls nucleus sampling - only consider tokens whose cumulative probability
@@ -304,31 +479,139 @@ impl MessageBuilder {
         Ok(self)
     }
     
+    /// Override the client's retry policy for this request
+    ///
+    /// Use `RetryPolicy::none()` to disable retries for a single call.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+    
+    /// Stop the request when `token` is cancelled
+    ///
+    /// Cancelling aborts the HTTP request, along with any retry or rate limit
+    /// wait, and the request returns `ClaudeError::Cancelled`; a stream ends
+    /// with a `ClaudeError::Cancelled` item. Middleware and the context manager
+    /// only ever see complete responses, so they are not left half-updated.
+    /// Overrides the client's token from `Claude::with_cancellation_token`.
+    pub fn cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+    
+    /// Fail a stream that sends no event other than `ping` within `timeout`
+    ///
+    /// The timeout starts once the response headers arrive. A stalled stream
+    /// ends with a `ClaudeError::StreamTimeout` item.
+    pub fn first_event_timeout(mut self, timeout: Duration) -> Self {
+        self.stream_timeouts.first_event = Some(timeout);
+        self
+    }
+    
+    /// Fail a stream that goes longer than `gap` without an event
+    ///
+    /// The server's `ping` events count as keep-alives, so a slow generation
+    /// that is still pinging is not treated as stalled. Applies after the first
+    /// event when `first_event_timeout` is also set. A stalled stream ends with
+    /// a `ClaudeError::StreamTimeout` item.
+    pub fn max_event_gap(mut self, gap: Duration) -> Self {
+        self.stream_timeouts.max_gap = Some(gap);
+        self
+    }
+    
+    /// Continue answers cut off at `max_tokens`, up to `max_continuations` times
+    ///
+    /// When a response stops with `stop_reason == "max_tokens"`, the
+    /// conversation is sent again with the answer so far as an assistant
+    /// prefill, and the continuation is joined onto it. Text the model repeats
+    /// from the end of the answer is dropped, and the usage of all requests is
+    /// combined. Middleware and the context manager see the joined response.
+    ///
+    /// Only text answers are continued; responses with tool calls or extended
+    /// thinking are returned as they are. If the answer is still cut off after
+    /// the last continuation, `MessageResponse::is_truncated` reports it. Off
+    /// by default, and not applied to streams. `send_with_metadata` returns the
+    /// metadata of the last request.
+    pub fn auto_continue(mut self, max_continuations: u32) -> Self {
+        self.max_continuations = max_continuations;
+        self
+    }
+    
+    /// Set the maximum number of model calls made by `run_tools`
+    ///
+    /// Defaults to `DEFAULT_MAX_TOOL_ITERATIONS`.
+    pub fn max_tool_iterations(mut self, max_iterations: u32) -> ClaudeResult<Self> {
+        if max_iterations == 0 {
+            return Err(ClaudeError::ValidationError("max_tool_iterations must be greater than 0".into()));
+        }
+        self.max_tool_iterations = max_iterations;
+        Ok(self)
+    }
+    
+    /// Record the spend of this request under `tag`
+    ///
+    /// A `CostTracker` on the client adds the spend to `CostReport::by_tag` and
+    /// applies tag budgets to the request, instead of those of the tracker's
+    /// own tag from `CostTracker::tagged`.
+    pub fn cost_tag(mut self, tag: impl Into<String>) -> Self {
+        self.cost_tag = Some(tag.into());
+        self
+    }
+    
     /// Prepare a request for sending to the Claude API
     ///
     /// This validates parameters, applies middleware, and formats the request appropriately.
     async fn prepare_request(&self, streaming: bool) -> ClaudeResult<(String, MessageRequest)> {
-        // Get processed messages from context manager (if available)
-        let processed_messages = match self.get_context_manager() {
-            Some(context_manager) => context_manager.process_messages(self.messages.clone()).await?,
-            None => self.messages.clone(),
-        };
+        self.prepare_request_for(self.messages.clone(), streaming).await
+    }
+    
+    /// Prepare a request for `messages` instead of the builder's own messages
+    async fn prepare_request_for(&self, messages: Vec<Message>, streaming: bool) -> ClaudeResult<(String, MessageRequest)> {
+        let messages = self.select_context(messages).await?;
+        self.request_for(messages, streaming).await
+    }
+    
+    /// Get processed messages from context manager (if available)
+    async fn select_context(&self, messages: Vec<Message>) -> ClaudeResult<Vec<Message>> {
+        match self.get_context_manager() {
+            Some(context_manager) => context_manager
+                .process_request_messages(self.system.as_ref(), messages)
+                .await,
+            None => Ok(messages),
+        }
+    }
+    
+    /// Build a request for messages that were already through the context manager
+    async fn request_for(&self, processed_messages: Vec<Message>, streaming: bool) -> ClaudeResult<(String, MessageRequest)> {
+        let model_info = self.model.info();
+        let max_tokens = self.resolve_max_tokens(model_info.as_ref());
+        
+        self.validate_tool_choice()?;
+        self.validate_thinking(max_tokens)?;
         
         // Construct the API endpoint
         let endpoint = format!("{}/messages", self.get_base_url());
         
+        let mut tools = self.tools.clone();
+        if let (Some(cache_control), Some(last)) = (&self.tools_cache_control, tools.last_mut()) {
+            last.cache_control = Some(cache_control.clone());
+        }
+        
         // Create the request body
         let mut request = MessageRequest {
             model: self.model.as_str().to_string(),
             messages: processed_messages,
             system: self.system.clone(),
             temperature: self.temperature,
-            max_tokens: self.max_tokens,
-            tools: if self.tools.is_empty() { None } else { Some(self.tools.clone()) },
+            max_tokens,
+            tools: if tools.is_empty() { None } else { Some(tools) },
+            tool_choice: self.tool_choice.clone(),
+            thinking: self.thinking,
             top_p: self.top_p,
             top_k: self.top_k,
             stop_sequences: self.stop_sequences.clone(),
             stream: if streaming { Some(true) } else { None },
+            cost_tag: self.cost_tag.clone(),
         };
         
         // Apply request middleware (if any)
@@ -336,13 +619,130 @@ impl MessageBuilder {
             request = middleware.process_request(request).await?;
         }
         
+        if request.cache_breakpoints() > MAX_CACHE_BREAKPOINTS {
+            return Err(ClaudeError::ValidationError(format!(
+                "A request can have at most {} cache_control breakpoints, found {}",
+                MAX_CACHE_BREAKPOINTS,
+                request.cache_breakpoints()
+            )));
+        }
+        
+        if let Some(info) = &model_info {
+            Self::validate_model_limits(&request, info)?;
+        }
+        validate_request_images(&request)?;
+        
         Ok((endpoint, request))
     }
     
+    /// Resolve max_tokens with this priority:
+    /// 1. The builder's max_tokens (if set)
+    /// 2. Client default_max_tokens, capped at the model's maximum output
+    /// 3. The model's default from the `ModelRegistry`, plus any thinking budget
+    fn resolve_max_tokens(&self, model_info: Option<&ModelInfo>) -> Option<u32> {
+        if self.max_tokens.is_some() {
+            return self.max_tokens;
+        }
+        
+        match (self.get_default_max_tokens(), model_info) {
+            (Some(max_tokens), Some(info)) => Some(max_tokens.min(info.max_output_tokens)),
+            (Some(max_tokens), None) => Some(max_tokens),
+            (None, Some(info)) => {
+                let budget_tokens = self.thinking.and_then(|thinking| thinking.budget_tokens()).unwrap_or(0);
+                Some(info.default_max_tokens().saturating_add(budget_tokens).min(info.max_output_tokens))
+            }
+            (None, None) => None,
+        }
+    }
+    
+    /// Check a request against the limits and capabilities of its model
+    fn validate_model_limits(request: &MessageRequest, info: &ModelInfo) -> ClaudeResult<()> {
+        if let Some(max_tokens) = request.max_tokens {
+            if max_tokens > info.max_output_tokens {
+                return Err(ClaudeError::ValidationError(format!(
+                    "max_tokens ({}) exceeds the maximum output of {} ({} tokens)",
+                    max_tokens, info.id, info.max_output_tokens
+                )));
+            }
+        }
+        if request.thinking.is_some_and(|thinking| thinking.budget_tokens().is_some()) && !info.supports_thinking {
+            return Err(ClaudeError::ValidationError(format!(
+                "{} does not support extended thinking", info.id
+            )));
+        }
+        if request.tools.as_ref().is_some_and(|tools| !tools.is_empty()) && !info.supports_tools {
+            return Err(ClaudeError::ValidationError(format!(
+                "{} does not support tools", info.id
+            )));
+        }
+        if !info.supports_vision && request.has_images() {
+            return Err(ClaudeError::ValidationError(format!(
+                "{} does not accept images", info.id
+            )));
+        }
+        Ok(())
+    }
+    
+    /// Check that a forced tool choice can be satisfied by the declared tools
+    fn validate_tool_choice(&self) -> ClaudeResult<()> {
+        match &self.tool_choice {
+            Some(ToolChoice::Any { .. }) if self.tools.is_empty() => Err(ClaudeError::ValidationError(
+                "tool_choice 'any' requires at least one tool".to_string()
+            )),
+            Some(ToolChoice::Tool { name, .. }) if !self.tools.iter().any(|tool| &tool.name == name) => {
+                Err(ClaudeError::ValidationError(format!(
+                    "tool_choice names tool '{}', which is not one of the declared tools", name
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+    
+    /// Check that extended thinking fits in `max_tokens` and is not combined
+    /// with sampling settings or tool choices it does not support
+    fn validate_thinking(&self, max_tokens: Option<u32>) -> ClaudeResult<()> {
+        let Some(budget_tokens) = self.thinking.and_then(|thinking| thinking.budget_tokens()) else {
+            return Ok(());
+        };
+        
+        match max_tokens {
+            Some(max_tokens) if budget_tokens < max_tokens => {}
+            Some(max_tokens) => {
+                return Err(ClaudeError::ValidationError(format!(
+                    "thinking budget_tokens ({}) must be less than max_tokens ({})",
+                    budget_tokens, max_tokens
+                )));
+            }
+            None => {
+                return Err(ClaudeError::ValidationError(
+                    "max_tokens must be set when thinking is enabled".to_string()
+                ));
+            }
+        }
+        
+        if self.temperature.is_some_and(|temperature| temperature != 1.0) {
+            return Err(ClaudeError::ValidationError(
+                "temperature cannot be changed when thinking is enabled".to_string()
+            ));
+        }
+        if self.top_k.is_some() {
+            return Err(ClaudeError::ValidationError(
+                "top_k cannot be set when thinking is enabled".to_string()
+            ));
+        }
+        if self.tool_choice.as_ref().is_some_and(ToolChoice::is_forced) {
+            return Err(ClaudeError::ValidationError(
+                "tool_choice cannot force tool use when thinking is enabled".to_string()
+            ));
+        }
+        
+        Ok(())
+    }
+    
     /// Handle error responses from the Claude API
     ///
     /// This method checks for error status codes and formats appropriate error messages.
-    async fn handle_error_response(&self, response: reqwest::Response) -> ClaudeResult<reqwest::Response> {
+    pub(crate) async fn handle_error_response(response: reqwest::Response) -> ClaudeResult<reqwest::Response> {
         if response.status().is_success() {
             return Ok(response);
         }
@@ -350,22 +750,56 @@ impl MessageBuilder {
         let status = response.status().as_u16();
         let headers = response.headers().clone();
         let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        let retry_after = Self::parse_retry_after(&headers);
+        let request_id = headers
+            .get("request-id")
+            .and_then(|h| h.to_str().ok())
+            .map(String::from);
         
-        if status == 429 {
-            // Rate limit handling
-            let retry_after = headers
-                .get("retry-after")
-                .and_then(|h| h.to_str().ok())
-                .and_then(|s| s.parse::<u64>().ok())
-                .map(Duration::from_secs);
-                
-            return Err(ClaudeError::rate_limited(retry_after));
+        // Parse the typed error envelope; messages are sanitized before returning
+        Err(ClaudeError::from_api_response(
+            status,
+            &error_text,
+            request_id,
+            retry_after,
+            Some(concat!(file!(), ":", line!()))
+        ))
+    }
+    
+    /// Update the rate limiter (if any) from the headers of an error response
+    ///
+    /// A 429 pauses all requests through the limiter for the `retry-after`
+    /// delay. Successful responses are applied through the request's permit.
+    fn observe_rate_limits(&self, response: &reqwest::Response) {
+        if response.status().is_success() {
+            return;
+        }
+        if let Some(limiter) = self.get_rate_limiter() {
+            limiter.update_from_headers(response.headers());
+            if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+                if let Some(delay) = Self::parse_retry_after(response.headers()) {
+                    limiter.pause(delay);
+                }
+            }
         }
+    }
+    
+    /// Parse the server-provided retry delay from response headers
+    ///
+    /// Supports `retry-after-ms` (milliseconds) and `retry-after` (seconds).
+    /// Negative values and values too large for a `Duration` are ignored.
+    fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+        let header_value = |name: &str| headers.get(name).and_then(|h| h.to_str().ok());
         
-        // Sanitize error message before returning
-        let sanitized_error = sanitize_error_message(&error_text);
+        if let Some(ms) = header_value("retry-after-ms").and_then(|s| s.trim().parse::<f64>().ok()) {
+            if let Ok(delay) = Duration::try_from_secs_f64(ms / 1000.0) {
+                return Some(delay);
+            }
+        }
         
-        Err(ClaudeError::api_error(sanitized_error, Some(status), None, Some(concat!(file!(), ":", line!()))))
+        header_value("retry-after")
+            .and_then(|s| s.trim().parse::<f64>().ok())
+            .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
     }
     
     /// Send the message and get a response
@@ -373,6 +807,74 @@ impl MessageBuilder {
     /// This method validates parameters and sends the request to the Claude API.
     /// It handles parameter validation, middleware processing, and context management.
     pub async fn send(self) -> ClaudeResult<MessageResponse> {
+        Ok(self.send_request().await?.0)
+    }
+    
+    /// Send the message and get the response with its HTTP metadata
+    ///
+    /// Like `send()`, but also returns the request id, status, headers and
+    /// timings of the response.
+    pub async fn send_with_metadata(self) -> ClaudeResult<(MessageResponse, ResponseMetadata)> {
+        self.send_request().await
+    }
+    
+    /// Build the request `send()` would send, without sending it
+    ///
+    /// The request is validated and passed through the context manager and
+    /// request middleware, so it can be submitted elsewhere, for example as
+    /// part of a message batch.
+    pub async fn build_request(self) -> ClaudeResult<MessageRequest> {
+        if self.messages.is_empty() {
+            return Err(ClaudeError::ValidationError(
+                "At least one message is required".to_string()
+            ));
+        }
+        
+        let (_, request) = self.prepare_request(false).await?;
+        Ok(request)
+    }
+    
+    /// Count the input tokens of the request `send()` would send
+    ///
+    /// Uses the API's token counting endpoint, so the count is exact for the
+    /// model and includes the system prompt, tools and images. No message is
+    /// generated.
+    pub async fn count_tokens(self) -> ClaudeResult<u32> {
+        if self.messages.is_empty() {
+            return Err(ClaudeError::ValidationError(
+                "At least one message is required".to_string()
+            ));
+        }
+        
+        let token = self.get_cancellation_token();
+        Self::check_cancelled(token.as_ref())?;
+        
+        let (_, request) = self.prepare_request(false).await?;
+        let endpoint = format!("{}/messages/count_tokens", self.get_base_url());
+        let body = CountTokensRequest::from(&request);
+        
+        let count = cancellable(token.as_ref(), self.get_retry_policy()
+            .execute(|| async {
+                let request = self.authorize(self.get_http_client().post(&endpoint))
+                    .json(&body);
+                let response = self.execute(request).await?;
+                let response = Self::handle_error_response(response).await?;
+                read_within(self.get_read_timeout(), async {
+                    response.json::<TokenCount>().await.map_err(|e| ClaudeError::parse_error(
+                        e.to_string(),
+                        None,
+                        Some(e),
+                        Some(concat!(file!(), ":", line!()))
+                    ))
+                }).await
+            }))
+            .await?;
+        
+        Ok(count.input_tokens)
+    }
+    
+    /// Send the current messages without consuming the builder
+    async fn send_request(&self) -> ClaudeResult<(MessageResponse, ResponseMetadata)> {
         // Validate minimum requirements
         if self.messages.is_empty() {
             return Err(ClaudeError::ValidationError(
@@ -380,28 +882,178 @@ impl MessageBuilder {
             ));
         }
         
+        let token = self.get_cancellation_token();
+        Self::check_cancelled(token.as_ref())?;
+        
         // Prepare the request
         let (endpoint, request) = self.prepare_request(false).await?;
         
+        let (message_response, metadata) = self.send_prepared(&endpoint, &request).await?;
+        Ok((self.process_response(&request, message_response).await?, metadata))
+    }
+    
+    /// Send a prepared request, continuing the answer if it was cut off at max_tokens
+    ///
+    /// Continuations reuse the prepared request with the answer so far as a
+    /// prefill, so they do not pass through the context manager or request
+    /// middleware again.
+    async fn send_prepared(&self, endpoint: &str, request: &MessageRequest) -> ClaudeResult<(MessageResponse, ResponseMetadata)> {
+        let token = self.get_cancellation_token();
+        
         // Handle the actual sending of the request - could be real or mock
-        let mut message_response = self.execute_request(&endpoint, request.clone()).await?;
+        let (mut message_response, mut metadata) = cancellable(token.as_ref(), self.get_retry_policy()
+            .execute(|| self.execute_request(endpoint, request.clone())))
+            .await?;
         
-        // Apply response middleware (if any)
-        let middlewares = self.get_response_middleware();
-        for middleware in middlewares {
-            message_response = middleware.process_response(message_response).await?;
+        let mut continuations = 0;
+        while continuations < self.max_continuations && self.thinking.is_none() {
+            let Some(messages) = continuation_messages(&request.messages, &message_response) else {
+                break;
+            };
+            let continuation_request = MessageRequest { messages, ..request.clone() };
+            let (continuation, continuation_metadata) = cancellable(token.as_ref(), self.get_retry_policy()
+                .execute(|| self.execute_request(endpoint, continuation_request.clone())))
+                .await?;
+            message_response = join_continuation(message_response, continuation);
+            metadata = continuation_metadata;
+            continuations += 1;
         }
         
-        // Update context manager with the response (if available)
+        Ok((message_response, metadata))
+    }
+    
+    /// Run a complete response through the response middleware and context manager
+    async fn process_response(&self, request: &MessageRequest, message_response: MessageResponse) -> ClaudeResult<MessageResponse> {
+        let message_response = self.apply_response_middleware(request, message_response).await?;
+        self.record_response(&message_response).await?;
+        Ok(message_response)
+    }
+    
+    /// Apply response middleware (if any)
+    async fn apply_response_middleware(&self, request: &MessageRequest, mut message_response: MessageResponse) -> ClaudeResult<MessageResponse> {
+        for middleware in self.get_response_middleware() {
+            message_response = middleware.process_response_for(request, message_response).await?;
+        }
+        Ok(message_response)
+    }
+    
+    /// Update context manager with the response (if available)
+    async fn record_response(&self, message_response: &MessageResponse) -> ClaudeResult<()> {
         if let Some(context_manager) = self.get_context_manager() {
-            context_manager.update_with_response(&message_response).await?;
+            context_manager.update_with_response(message_response).await?;
+        }
+        Ok(())
+    }
+    
+    /// Send the message and automatically execute tool calls until the model is done
+    ///
+    /// The registry's tools are declared alongside any tools added with `add_tool`.
+    /// Whenever the model stops with `stop_reason == "tool_use"`, every `tool_use`
+    /// block in the response is executed concurrently and the results are sent
+    /// back in a single user message. The loop ends when the model stops for any
+    /// other reason, or after `max_tool_iterations` model calls.
+    ///
+    /// A forced `tool_choice` (`any` or a named tool) only applies to the first
+    /// call; later calls use `auto` so the model can finish with a text answer.
+    ///
+    /// A context manager selects the conversation for the first call only, and
+    /// records only the final response.
+    pub async fn run_tools(mut self, registry: &ToolRegistry) -> ClaudeResult<ToolRun> {
+        for tool in registry.tools() {
+            if !self.tools.iter().any(|existing| existing.name == tool.name) {
+                self.tools.push(tool.clone());
+            }
         }
         
-        Ok(message_response)
+        if self.messages.is_empty() {
+            return Err(ClaudeError::ValidationError(
+                "At least one message is required".to_string()
+            ));
+        }
+        Self::check_cancelled(self.get_cancellation_token().as_ref())?;
+        
+        let mut usage = Usage::default();
+        let mut iterations = 0;
+        
+        // The context manager selects the conversation once. Later calls extend
+        // its selection with the new turns, and only the final answer is recorded.
+        let mut conversation = self.select_context(self.messages.clone()).await?;
+        
+        loop {
+            let (endpoint, request) = self.request_for(conversation.clone(), false).await?;
+            let (response, _) = self.send_prepared(&endpoint, &request).await?;
+            let response = self.apply_response_middleware(&request, response).await?;
+            iterations += 1;
+            usage += &response.usage;
+            
+            let turn = Message {
+                role: Role::Assistant,
+                content: response.content.clone(),
+            };
+            conversation.push(turn.clone());
+            self.messages.push(turn);
+            
+            let wants_tools = response.stop_reason.as_deref() == Some("tool_use")
+                && response.content.iter().any(|block| matches!(block, Content::ToolUse { .. }));
+            if !wants_tools || iterations >= self.max_tool_iterations {
+                if wants_tools {
+                    log::warn!("Tool loop stopped after reaching {} iterations", iterations);
+                }
+                self.record_response(&response).await?;
+                
+                return Ok(ToolRun {
+                    messages: self.messages,
+                    response,
+                    usage,
+                    iterations,
+                    max_iterations_reached: wants_tools,
+                });
+            }
+            
+            // Stop forcing tool calls so the model can give its final answer
+            if let Some(choice) = self.tool_choice.take() {
+                self.tool_choice = Some(match choice {
+                    forced if forced.is_forced() && forced.parallel_tool_use_disabled() => {
+                        ToolChoice::auto().disable_parallel_tool_use()
+                    }
+                    forced if forced.is_forced() => ToolChoice::auto(),
+                    other => other,
+                });
+            }
+            
+            let results = Message {
+                role: Role::User,
+                content: registry.execute_all(&response.content).await,
+            };
+            conversation.push(results.clone());
+            self.messages.push(results);
+        }
     }
     
-    /// Execute a request, potentially using a mock handler if one is available
-    async fn execute_request(&self, endpoint: &str, request: MessageRequest) -> ClaudeResult<MessageResponse> {
+    /// Execute a request once the rate limiter (if any) lets it through
+    async fn execute_request(&self, endpoint: &str, request: MessageRequest) -> ClaudeResult<(MessageResponse, ResponseMetadata)> {
+        let permit = match self.get_rate_limiter() {
+            Some(limiter) => Some(limiter.acquire_for(&request).await),
+            None => None,
+        };
+        
+        let result = self.dispatch_request(endpoint, request).await;
+        if let Some(mut permit) = permit {
+            match &result {
+                Ok((response, metadata)) => {
+                    permit.observe_headers(&metadata.headers);
+                    permit.settle(&response.usage);
+                }
+                Err(_) => permit.release(),
+            }
+        }
+        result
+    }
+    
+    /// Send a request, potentially using a mock handler if one is available
+    async fn dispatch_request(&self, endpoint: &str, request: MessageRequest) -> ClaudeResult<(MessageResponse, ResponseMetadata)> {
+        let started = Instant::now();
+        
         // First, check if we have a custom request handler from a mock
         if let Some(client) = &self.client_ref {
             // Get the handler outside of the await
@@ -417,29 +1069,35 @@ impl MessageBuilder {
             // Use the handler if we got one
             if let Some(handler) = handler_opt {
                 // Use the custom handler
-                return handler(request.clone()).await;
+                let response = handler(request.clone()).await?;
+                let mut metadata = ResponseMetadata::without_headers(started);
+                metadata.total_duration = Some(started.elapsed());
+                return Ok((response, metadata));
             }
         }
         
         // If we reach here, use the regular HTTP client
-        let response = self.get_http_client()
-            .post(endpoint)
-            .header("x-api-key", self.get_api_key().as_str())
-            .header("anthropic-version", "2023-06-01")
-            .json(&request)
-            .send()
-            .await?;
+        let http_request = self.authorize(self.get_http_client().post(endpoint))
+            .json(&request);
+        let response = self.execute(http_request).await?;
+        self.observe_rate_limits(&response);
+        let mut metadata = ResponseMetadata::from_response(&response, started);
             
         // Check for errors
-        let response = self.handle_error_response(response).await?;
+        let response = Self::handle_error_response(response).await?;
         
         // Parse the response
-        response.json::<MessageResponse>().await.map_err(|e| ClaudeError::parse_error(
-            e.to_string(), 
-            None, 
-            Some(e), 
-            Some(concat!(file!(), ":", line!()))
-        ))
+        let message_response = read_within(self.get_read_timeout(), async {
+            response.json::<MessageResponse>().await.map_err(|e| ClaudeError::parse_error(
+                e.to_string(), 
+                None, 
+                Some(e), 
+                Some(concat!(file!(), ":", line!()))
+            ))
+        }).await?;
+        metadata.total_duration = Some(started.elapsed());
+        
+        Ok((message_response, metadata))
     }
     
     /// Send the message and get a streaming response
@@ -447,6 +1105,26 @@ impl MessageBuilder {
     /// This method is similar to send(), but returns a stream of delta events
     /// that can be processed incrementally as they arrive.
     pub async fn stream(self) -> ClaudeResult<MessageStream> {
+        Ok(self.open_stream().await?.0)
+    }
+    
+    /// Send the message and get a streaming response with its HTTP metadata
+    ///
+    /// Like `stream()`, but also returns the request id, status and headers of
+    /// the response. Time to first token and total duration are filled in as
+    /// the stream is consumed.
+    pub async fn stream_with_metadata(self) -> ClaudeResult<(MessageStream, StreamMetadata)> {
+        self.open_stream().await
+    }
+    
+    /// Validate, prepare and start a streaming request
+    async fn open_stream(&self) -> ClaudeResult<(MessageStream, StreamMetadata)> {
+        let (endpoint, request) = self.prepare_stream().await?;
+        self.start_stream(&endpoint, &request).await
+    }
+    
+    /// Validate and prepare a streaming request
+    async fn prepare_stream(&self) -> ClaudeResult<(String, MessageRequest)> {
         // Validate minimum requirements
         if self.messages.is_empty() {
             return Err(ClaudeError::ValidationError(
@@ -454,15 +1132,119 @@ impl MessageBuilder {
             ));
         }
         
+        Self::check_cancelled(self.get_cancellation_token().as_ref())?;
+        
         // Prepare the request
-        let (endpoint, request) = self.prepare_request(true).await?;
+        self.prepare_request(true).await
+    }
+    
+    /// Start a prepared streaming request
+    async fn start_stream(&self, endpoint: &str, request: &MessageRequest) -> ClaudeResult<(MessageStream, StreamMetadata)> {
+        let token = self.get_cancellation_token();
         
         // Handle the streaming request - this could be real or mock
-        self.execute_stream_request(&endpoint, request.clone()).await
+        let (stream, metadata) = cancellable(token.as_ref(), self.get_retry_policy()
+            .execute(|| self.execute_stream_request(endpoint, request.clone())))
+            .await?;
+        let stream = stall_guard(stream, self.stream_timeouts);
+        Ok((cancellable_stream(stream, token), metadata))
+    }
+    
+    /// Send the message and get a stream of content changes
+    ///
+    /// Unlike `stream()`, which yields raw delta events, this tracks content
+    /// blocks by index and reassembles `input_json_delta` fragments into parsed
+    /// `tool_use` blocks. The assembled content is available from the stream
+    /// once it ends.
+    pub async fn stream_content(self) -> ClaudeResult<ContentStream> {
+        Ok(ContentStream::new(self.open_stream().await?.0))
+    }
+    
+    /// Stream the message to a callback and return the complete response
+    ///
+    /// `on_event` is called with each content change as it arrives. Once the
+    /// stream ends, the response is rebuilt from the events and, like with
+    /// `send()`, passed through the response middleware and the context
+    /// manager before being returned.
+    pub async fn stream_and_collect<F>(self, mut on_event: F) -> ClaudeResult<MessageResponse>
+    where
+        F: FnMut(&ContentEvent),
+    {
+        let (endpoint, request) = self.prepare_stream().await?;
+        let mut stream = ContentStream::new(self.start_stream(&endpoint, &request).await?.0);
+        while let Some(event) = stream.next().await {
+            on_event(&event?);
+        }
+        
+        self.process_response(&request, stream.into_response()?).await
     }
     
-    /// Execute a streaming request, potentially using a mock handler if one is available
-    async fn execute_stream_request(&self, endpoint: &str, request: MessageRequest) -> ClaudeResult<MessageStream> {
+    /// Execute a streaming request once the rate limiter (if any) lets it through
+    ///
+    /// The reservation is settled when the stream reports its final usage, and
+    /// released if the stream ends or is dropped before that.
+    async fn execute_stream_request(&self, endpoint: &str, request: MessageRequest) -> ClaudeResult<(MessageStream, StreamMetadata)> {
+        let Some(limiter) = self.get_rate_limiter() else {
+            return self.dispatch_stream_request(endpoint, request).await;
+        };
+        
+        let mut permit = limiter.acquire_for(&request).await;
+        let (stream, metadata) = match self.dispatch_stream_request(endpoint, request).await {
+            Ok(opened) => opened,
+            Err(e) => {
+                permit.release();
+                return Err(e);
+            }
+        };
+        permit.observe_headers(&metadata.get().headers);
+        
+        let mut permit = Some(permit);
+        let mut start_usage: Option<Usage> = None;
+        let stream = stream
+            .inspect(move |event| {
+                let Ok(event) = event else { return };
+                match event.event_type.as_str() {
+                    "message_start" => {
+                        start_usage = event.message.as_ref().and_then(|message| message.usage.clone());
+                    }
+                    "message_delta" => {
+                        if let (Some(permit), Some(delta_usage)) = (permit.take(), &event.usage) {
+                            let mut usage = start_usage.take().unwrap_or_else(|| Usage {
+                                input_tokens: permit.input_tokens(),
+                                ..Usage::default()
+                            });
+                            usage.output_tokens = delta_usage.output_tokens;
+                            permit.settle(&usage);
+                        }
+                    }
+                    _ => {}
+                }
+            })
+            .boxed();
+        Ok((stream, metadata))
+    }
+    
+    /// Open a stream, potentially using a mock handler if one is available
+    ///
+    /// The stream records the timing of its events in the returned metadata.
+    async fn dispatch_stream_request(&self, endpoint: &str, request: MessageRequest) -> ClaudeResult<(MessageStream, StreamMetadata)> {
+        let started = Instant::now();
+        let (stream, metadata) = self.open_event_stream(endpoint, request, started).await?;
+        
+        let metadata = StreamMetadata::new(metadata);
+        let observer = metadata.clone();
+        let stream = stream
+            .inspect(move |event| {
+                if let Ok(event) = event {
+                    observer.observe(&event.event_type, started.elapsed());
+                }
+            })
+            .boxed();
+        Ok((stream, metadata))
+    }
+    
+    /// Send a streaming request and decode its events
+    async fn open_event_stream(&self, endpoint: &str, request: MessageRequest, started: Instant) -> ClaudeResult<(MessageStream, ResponseMetadata)> {
         // First, check if we have a custom stream handler from a mock
         if let Some(client) = &self.client_ref {
             // Get the handler outside of the await
@@ -478,7 +1260,8 @@ impl MessageBuilder {
             // Use the handler if we got one
             if let Some(handler) = handler_opt {
                 // Use the custom handler
-                return handler(request.clone()).await;
+                let stream = handler(request.clone()).await?;
+                return Ok((stream, ResponseMetadata::without_headers(started)));
             }
         }
         
@@ -487,88 +1270,96 @@ impl MessageBuilder {
         streaming_request.stream = Some(true);
         
         // Send the HTTP request
-        let response = self.get_http_client()
-            .post(endpoint)
-            .header("x-api-key", self.get_api_key().as_str())
-            .header("anthropic-version", "2023-06-01")
+        let http_request = self.authorize(self.get_http_client().post(endpoint))
             .header("content-type", "application/json")
             .header("accept", "text/event-stream")  // Explicitly request SSE format
-            .json(&streaming_request)
-            .send()
-            .await?;
+            .json(&streaming_request);
+        let response = self.execute(http_request).await?;
+        self.observe_rate_limits(&response);
+        let metadata = ResponseMetadata::from_response(&response, started);
             
         // Check for errors
-        let response = self.handle_error_response(response).await?;
-        
-        // Process the stream
-        let stream = response.bytes_stream();
-        
-        // Transform the bytes stream into a message stream
-        let message_stream = stream
-            .map(|result| {
-                result.map_err(|e| ClaudeError::request_error(
-                    e.to_string(), 
-                    None, 
-                    Some(e), 
-                    Some(concat!(file!(), ":", line!()))
-                ))
-            })
-            .map(|result| {
-                result.and_then(|bytes: bytes::Bytes| {
-                    // SSE event parsing (format: data: {...}\n\n)
-                    let text = String::from_utf8_lossy(&bytes);
-                    let lines: Vec<&str> = text.lines().collect();
-                    
-                    let mut events = Vec::new();
-                    let mut current_data = String::new();
-                    
-                    for line in lines {
-                        if line.is_empty() && !current_data.is_empty() {
-                            // Empty line triggers parsing of existing data
-                            if current_data == "[DONE]" {
-                                // Stream end marker
-                                continue;
-                            }
-                            
-                            match serde_json::from_str::<DeltaEvent>(&current_data) {
-                                Ok(event) => events.push(event),
-                                Err(e) => return Err(ClaudeError::parse_error(
-                                    format!("Failed to parse event: {}", e),
-                                    Some(current_data.clone()),
-                                    Some(e),
-                                    Some(concat!(file!(), ":", line!()))
-                                )),
-                            }
-                            current_data.clear();
-                        } else if let Some(data) = line.strip_prefix("data: ") {
-                            current_data = data.to_string();
-                        }
-                    }
-                    
-                    // Process any remaining data
-                    if !current_data.is_empty() && current_data != "[DONE]" {
-                        match serde_json::from_str::<DeltaEvent>(&current_data) {
-                            Ok(event) => events.push(event),
-                            Err(e) => return Err(ClaudeError::parse_error(
-                                format!("Failed to parse final event: {}", e),
-                                Some(current_data),
-                                Some(e),
-                                Some(concat!(file!(), ":", line!()))
-                            )),
-                        }
-                    }
-                    
-                    Ok(events)
-                })
-            })
-            .flat_map(|result| -> futures::stream::BoxStream<'static, Result<DeltaEvent, ClaudeError>> {
-                match result {
-                    Ok(events) => futures::stream::iter(events.into_iter().map(Ok)).boxed(),
-                    Err(e) => futures::stream::iter(vec![Err(e)]).boxed(),
-                }
+        let response = Self::handle_error_response(response).await?;
+        
+        // Decode the SSE body incrementally so events and UTF-8 characters
+        // split across network chunks are reassembled before parsing
+        let message_stream = chunks_within(self.get_read_timeout(), response.bytes_stream())
+            .scan(SseDecoder::new(), |decoder, result| {
+                let items: Vec<ClaudeResult<DeltaEvent>> = match result {
+                    Ok(bytes) => decoder.decode(&bytes)
+                        .into_iter()
+                        .filter(|event| event.data != "[DONE]")
+                        .map(Self::parse_sse_event)
+                        .collect(),
+                    Err(e) => vec![Err(e)],
+                };
+                futures::future::ready(Some(futures::stream::iter(items)))
             })
+            .flatten()
             .boxed();
         
-        Ok(message_stream)
+        Ok((message_stream, metadata))
     }
-}
\ No newline at end of file
+    
+    /// Parse the data of a decoded SSE event into a DeltaEvent
+    ///
+    /// `error` events are turned into typed API errors.
+    fn parse_sse_event(event: SseEvent) -> ClaudeResult<DeltaEvent> {
+        if event.event == "error" {
+            if let Ok(response) = serde_json::from_str::<ApiErrorResponse>(&event.data) {
+                return Err(ClaudeError::from_stream_error(
+                    response.error,
+                    Some(concat!(file!(), ":", line!()))
+                ));
+            }
+        }
+        
+        serde_json::from_str::<DeltaEvent>(&event.data).map_err(|e| ClaudeError::parse_error(
+            format!("Failed to parse event: {}", e),
+            Some(event.data),
+            Some(e),
+            Some(concat!(file!(), ":", line!()))
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue};
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn test_parse_retry_after_fractional_values() {
+        assert_eq!(
+            MessageBuilder::parse_retry_after(&headers(&[("retry-after", "1.5")])),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(
+            MessageBuilder::parse_retry_after(&headers(&[("retry-after-ms", "250.5"), ("retry-after", "9")])),
+            Some(Duration::from_micros(250_500))
+        );
+    }
+
+    #[test]
+    fn test_parse_retry_after_ignores_unrepresentable_values() {
+        for value in ["1e300", "-1", "inf", "NaN"] {
+            let value = HeaderValue::from_static(value);
+            let mut only_secs = HeaderMap::new();
+            only_secs.insert("retry-after", value.clone());
+            assert_eq!(MessageBuilder::parse_retry_after(&only_secs), None);
+
+            // An unusable `retry-after-ms` falls back to `retry-after`
+            let mut both = headers(&[("retry-after", "2")]);
+            both.insert("retry-after-ms", value);
+            assert_eq!(MessageBuilder::parse_retry_after(&both), Some(Duration::from_secs(2)));
+        }
+    }
+}
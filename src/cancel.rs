@@ -0,0 +1,48 @@
+// Cooperative cancellation
+
+use crate::types::*;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use std::future::Future;
+use tokio_util::sync::CancellationToken;
+
+/// Run `future` until it completes or `token` is cancelled
+///
+/// Cancelling drops the future, which aborts any HTTP request it is waiting on.
+pub(crate) async fn cancellable<T>(
+    token: Option<&CancellationToken>,
+    future: impl Future<Output = ClaudeResult<T>>,
+) -> ClaudeResult<T> {
+    let Some(token) = token else {
+        return future.await;
+    };
+    tokio::select! {
+        biased;
+        _ = token.cancelled() => Err(ClaudeError::Cancelled),
+        result = future => result,
+    }
+}
+
+/// End `stream` with a `ClaudeError::Cancelled` item once `token` is cancelled
+///
+/// The inner stream is dropped when the token fires, closing its connection.
+pub(crate) fn cancellable_stream<T: Send + 'static>(
+    stream: BoxStream<'static, ClaudeResult<T>>,
+    token: Option<CancellationToken>,
+) -> BoxStream<'static, ClaudeResult<T>> {
+    let Some(token) = token else {
+        return stream;
+    };
+    futures::stream::unfold(Some(stream), move |stream| {
+        let token = token.clone();
+        async move {
+            let mut stream = stream?;
+            tokio::select! {
+                biased;
+                _ = token.cancelled() => Some((Err(ClaudeError::Cancelled), None)),
+                item = stream.next() => item.map(|item| (item, Some(stream))),
+            }
+        }
+    })
+    .boxed()
+}
@@ -2,14 +2,26 @@
 
 use crate::types::*;
 use crate::builder::MessageBuilder;
+use crate::batches::BatchClient;
+use crate::count_tokens::{CountTokensRequest, TokenCount};
+use crate::models::ModelsClient;
+use crate::cost::CostTracker;
+use crate::rate_limit::RateLimiter;
 use crate::middleware::{ContextManager, RequestMiddleware, ResponseMiddleware};
+use crate::retry::RetryPolicy;
+use crate::client_builder::{default_user_agent, ClaudeBuilder, DEFAULT_API_VERSION};
+use crate::cancel::cancellable;
 use crate::domains::*;
+use bytes::Bytes;
+use futures::{Stream, StreamExt};
+use futures::stream::BoxStream;
 use reqwest::{Client as HttpClient, header};
 use std::sync::{Arc, Mutex, OnceLock};
 use std::time::Duration;
 use std::pin::Pin;
+use tokio_util::sync::CancellationToken;
 use std::future::Future;
-use lazy_static::lazy_static;
+use serde::de::DeserializeOwned;
 
 // Type aliases to simplify complex types
 /// Handler for a single request operation
@@ -36,31 +48,54 @@ pub trait MockApiHandler: Send + Sync {
     fn process_stream_request(&self, request: MessageRequest) -> StreamHandlerFuture;
 }
 
-lazy_static! {
-    static ref CLIENT_CONFIG: Mutex<TlsConfig> = Mutex::new(TlsConfig::default());
-}
+pub use crate::tls::{set_tls_config, PemIdentity, TlsConfig};
 
-/// Configuration for TLS
-#[derive(Clone, Debug)]
-pub struct TlsConfig {
-    pub min_tls_version: Option<reqwest::tls::Version>,
-    pub cert_verification: bool,
+/// Await a read from the API, failing if it takes longer than `timeout`
+pub(crate) async fn read_within<T>(
+    timeout: Option<Duration>,
+    read: impl Future<Output = ClaudeResult<T>>,
+) -> ClaudeResult<T> {
+    match timeout {
+        Some(timeout) => tokio::time::timeout(timeout, read).await
+            .map_err(|elapsed| read_timeout_error(timeout, elapsed))?,
+        None => read.await,
+    }
 }
 
-impl Default for TlsConfig {
-    fn default() -> Self {
-        Self {
-            min_tls_version: Some(reqwest::tls::Version::TLS_1_2),
-            cert_verification: true,
+/// Read a response body as a stream of chunks, failing if no chunk arrives within `timeout`
+pub(crate) fn chunks_within(
+    timeout: Option<Duration>,
+    chunks: impl Stream<Item = reqwest::Result<Bytes>> + Send + 'static,
+) -> BoxStream<'static, ClaudeResult<Bytes>> {
+    let chunks = chunks.map(|chunk| chunk.map_err(|e| ClaudeError::request_error(
+        e.to_string(),
+        None,
+        Some(e),
+        Some(concat!(file!(), ":", line!()))
+    )));
+    let Some(timeout) = timeout else {
+        return chunks.boxed();
+    };
+
+    futures::stream::unfold(Some(chunks.boxed()), move |chunks| async move {
+        let mut chunks = chunks?;
+        match tokio::time::timeout(timeout, chunks.next()).await {
+            Ok(Some(chunk)) => Some((chunk, Some(chunks))),
+            Ok(None) => None,
+            // End the stream after reporting the timeout
+            Err(elapsed) => Some((Err(read_timeout_error(timeout, elapsed)), None)),
         }
-    }
+    })
+    .boxed()
 }
 
-/// Set global TLS configuration for all Claude clients
-pub fn set_tls_config(config: TlsConfig) {
-    if let Ok(mut cfg) = CLIENT_CONFIG.lock() {
-        *cfg = config;
-    }
+fn read_timeout_error(timeout: Duration, elapsed: tokio::time::error::Elapsed) -> ClaudeError {
+    ClaudeError::request_error(
+        format!("No response data received for {:?}", timeout),
+        None,
+        Some(elapsed),
+        Some(concat!(file!(), ":", line!()))
+    )
 }
 
 #[derive(Clone)]
@@ -70,6 +105,15 @@ pub struct Claude {
     pub base_url: String, // Made public for testing
     pub default_model: ClaudeModel, // Made public for testing
     pub default_max_tokens: Option<u32>, // Global default for max_tokens
+    pub(crate) api_version: String,
+    pub(crate) beta_features: Vec<String>,
+    pub(crate) default_headers: header::HeaderMap,
+    pub(crate) read_timeout: Option<Duration>,
+    pub(crate) retry_policy: RetryPolicy,
+    pub(crate) rate_limiter: Option<RateLimiter>,
+    pub(crate) cancellation: Option<CancellationToken>,
+    pub(crate) domain_cache_control: Option<CacheControl>,
+    pub(crate) domain_max_continuations: u32,
     pub(crate) context_manager: Option<Arc<dyn ContextManager>>,
     pub(crate) request_middleware: Vec<Arc<dyn RequestMiddleware>>,
     pub(crate) response_middleware: Vec<Arc<dyn ResponseMiddleware>>,
@@ -80,21 +124,28 @@ pub struct Claude {
 
 impl Claude {
     /// Create a new Claude client with the specified API key
+    ///
+    /// # Panics
+    ///
+    /// Panics if the HTTP client cannot be created, for example because the
+    /// TLS configuration set with `set_tls_config` is invalid. Use
+    /// `Claude::try_new` to handle that error, or `Claude::builder` to
+    /// configure timeouts, proxies and headers.
     pub fn new(api_key: impl Into<String>) -> Self {
-        let tls_config = match CLIENT_CONFIG.lock() {
-            Ok(guard) => {
-                // Clone the config so we don't hold the lock
-                let config = guard.clone();
-                drop(guard);
-                config
-            },
-            Err(_) => {
-                // If lock is poisoned, create a new default config
-                TlsConfig::default()
-            }
-        };
-        
-        Self::with_tls_config(api_key, tls_config)
+        Self::try_new(api_key).unwrap_or_else(|e| panic!("Failed to create HTTP client: {}", e))
+    }
+    
+    /// Create a new Claude client with the specified API key
+    ///
+    /// Like `new`, but returns an error if the HTTP client cannot be created,
+    /// as `ClaudeBuilder::build` does.
+    pub fn try_new(api_key: impl Into<String>) -> ClaudeResult<Self> {
+        ClaudeBuilder::new(api_key).build()
+    }
+    
+    /// Create a builder for a client with custom HTTP configuration
+    pub fn builder(api_key: impl Into<String>) -> ClaudeBuilder {
+        ClaudeBuilder::new(api_key)
     }
     
     /// Set custom request handler for this client
@@ -119,32 +170,28 @@ impl Claude {
         }
     }
     
-    /// Create a new Claude client with a specific TLS configuration
-    fn with_tls_config(api_key: impl Into<String>, tls_config: TlsConfig) -> Self {
-        let mut headers = header::HeaderMap::new();
-        headers.insert(
-            header::CONTENT_TYPE,
-            header::HeaderValue::from_static("application/json"),
-        );
-        
-        let mut builder = HttpClient::builder()
-            .default_headers(headers)
-            .timeout(Duration::from_secs(300))
-            .danger_accept_invalid_certs(!tls_config.cert_verification);
-            
-        if let Some(version) = tls_config.min_tls_version {
-            builder = builder.min_tls_version(version);
+    /// Create a client with default settings around an HTTP client
+    pub(crate) fn from_parts(http_client: HttpClient, api_key: SecureApiKey, base_url: String) -> Self {
+        let mut default_headers = header::HeaderMap::new();
+        if let Ok(user_agent) = header::HeaderValue::from_str(&default_user_agent()) {
+            default_headers.insert(header::USER_AGENT, user_agent);
         }
         
-        let http_client = builder.build()
-            .expect("Failed to create HTTP client");
-            
         Self {
             http_client,
-            api_key: SecureApiKey::new(api_key),
-            base_url: "https://api.anthropic.com/v1".to_string(),
+            api_key,
+            base_url,
             default_model: ClaudeModel::Sonnet37,
             default_max_tokens: None, // No default max_tokens initially
+            api_version: DEFAULT_API_VERSION.to_string(),
+            beta_features: Vec::new(),
+            default_headers,
+            read_timeout: None,
+            retry_policy: RetryPolicy::none(), // Retries are opt-in
+            rate_limiter: None,
+            cancellation: None,
+            domain_cache_control: None,
+            domain_max_continuations: 0,
             context_manager: None,
             request_middleware: Vec::new(),
             response_middleware: Vec::new(),
@@ -153,16 +200,26 @@ impl Claude {
             stream_handler: Arc::new(Mutex::new(None)),
         }
     }
+    
+    /// Give this client its own domain client registry
+    ///
+    /// Domain clients hold a snapshot of the client they were created from, so
+    /// every setter calls this to keep them from using the old settings.
+    fn reset_domains(&mut self) {
+        self.domain_registry = Arc::new(OnceLock::new());
+    }
 
     /// Set a default model to use for requests
     pub fn with_model(mut self, model: ClaudeModel) -> Self {
         self.default_model = model;
+        self.reset_domains();
         self
     }
     
     /// Set a custom base URL for the API
     pub fn with_base_url(mut self, url: impl Into<String>) -> Self {
         self.base_url = url.into();
+        self.reset_domains();
         self
     }
     
@@ -172,33 +229,194 @@ impl Claude {
             return Err(ClaudeError::ValidationError("max_tokens must be greater than 0".into()));
         }
         self.default_max_tokens = Some(max_tokens);
+        self.reset_domains();
         Ok(self)
     }
     
+    /// Set the retry policy for transient API failures
+    ///
+    /// Retries are disabled by default. Use `RetryPolicy::default()` for
+    /// exponential backoff on rate limits, overload and connection errors.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self.reset_domains();
+        self
+    }
+    
+    /// Get the retry policy used for requests from this client
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+    
+    /// Throttle message requests with a client-side rate limiter
+    ///
+    /// Requests wait in line until they fit within the limiter's requests and
+    /// tokens per minute, instead of failing with a 429. Clones of the client
+    /// share the limiter.
+    pub fn with_rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.rate_limiter = Some(limiter);
+        self.reset_domains();
+        self
+    }
+    
+    /// Get the rate limiter used for requests from this client, if any
+    pub fn rate_limiter(&self) -> Option<&RateLimiter> {
+        self.rate_limiter.as_ref()
+    }
+    
+    /// Stop every request from this client when `token` is cancelled
+    ///
+    /// Applies to messages, streams and domain operations; see
+    /// `MessageBuilder::cancellation_token` for the per-request version and the
+    /// domain clients' `with_cancellation` for per-operation cancellation. The
+    /// returned client has its own domain client registry, so domain clients
+    /// taken from it observe the token, while clients registered on the
+    /// original are not carried over.
+    pub fn with_cancellation_token(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self.reset_domains();
+        self
+    }
+    
+    /// Get the cancellation token observed by requests from this client, if any
+    pub fn cancellation_token(&self) -> Option<&CancellationToken> {
+        self.cancellation.as_ref()
+    }
+    
+    /// Cache the fixed instructions of domain client prompts
+    ///
+    /// Domain operations send their instructions, and any tool they force, as a
+    /// prompt prefix with a `cache_control` breakpoint, so repeated calls read
+    /// the prefix from the prompt cache. Off by default. The returned client
+    /// has its own domain client registry, like with `with_cancellation_token`.
+    ///
+    /// The API only caches prefixes of at least 1024 tokens, or 2048 for Haiku
+    /// models. Most built-in domain instructions are shorter, so the breakpoint
+    /// has no effect, and `cache_read_input_tokens` stays 0, unless a custom
+    /// `DomainPrompt` or the forced tool makes the prefix long enough.
+    pub fn with_domain_prompt_caching(mut self, cache_control: CacheControl) -> Self {
+        self.domain_cache_control = Some(cache_control);
+        self.reset_domains();
+        self
+    }
+    
+    /// Continue domain answers cut off at `max_tokens`, up to `max_continuations` times
+    ///
+    /// Applies `MessageBuilder::auto_continue` to every domain operation, so
+    /// long outputs such as blog posts come back whole. Off by default, in
+    /// which case a truncated domain answer is logged as a warning. The
+    /// returned client has its own domain client registry, like with
+    /// `with_cancellation_token`.
+    pub fn with_domain_continuation(mut self, max_continuations: u32) -> Self {
+        self.domain_max_continuations = max_continuations;
+        self.reset_domains();
+        self
+    }
+    
     /// Add a context manager for handling message history
     pub fn with_context_manager(mut self, manager: impl ContextManager + 'static) -> Self {
         self.context_manager = Some(Arc::new(manager));
+        self.reset_domains();
         self
     }
     
     /// Add middleware that processes requests before they're sent
     pub fn add_request_middleware(mut self, middleware: impl RequestMiddleware + 'static) -> Self {
         self.request_middleware.push(Arc::new(middleware));
+        self.reset_domains();
         self
     }
     
     /// Add middleware that processes responses after they're received
     pub fn add_response_middleware(mut self, middleware: impl ResponseMiddleware + 'static) -> Self {
         self.response_middleware.push(Arc::new(middleware));
+        self.reset_domains();
         self
     }
     
+    /// Track the cost of responses and enforce the tracker's budgets
+    ///
+    /// Adds the tracker as both request and response middleware.
+    pub fn with_cost_tracker(self, tracker: CostTracker) -> Self {
+        self.add_request_middleware(tracker.clone())
+            .add_response_middleware(tracker)
+    }
+    
     /// Create a message builder for constructing a request
     pub fn message(&self) -> MessageBuilder {
         MessageBuilder::from_client(Arc::new(self.clone()))
     }
     
+    /// Add the authentication, API version, beta and custom headers to a request
+    pub(crate) fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let request = request
+            .headers(self.default_headers.clone())
+            .header("x-api-key", self.api_key.as_str())
+            .header("anthropic-version", self.api_version.as_str());
+        if self.beta_features.is_empty() {
+            request
+        } else {
+            request.header("anthropic-beta", self.beta_features.join(","))
+        }
+    }
+    
+    /// Send a request, applying the read timeout
+    pub(crate) async fn execute(&self, request: reqwest::RequestBuilder) -> ClaudeResult<reqwest::Response> {
+        read_within(self.read_timeout, async { Ok(request.send().await?) }).await
+    }
+    
+    /// Send a request built by `build`, retrying per the client's policy, and parse the JSON response
+    pub(crate) async fn send_json<T, F>(&self, build: F) -> ClaudeResult<T>
+    where
+        T: DeserializeOwned,
+        F: Fn(&HttpClient) -> reqwest::RequestBuilder,
+    {
+        cancellable(self.cancellation.as_ref(), self.retry_policy
+            .execute(|| async {
+                let response = self.execute(self.authorize(build(&self.http_client))).await?;
+                let response = MessageBuilder::handle_error_response(response).await?;
+                read_within(self.read_timeout, async {
+                    response.json::<T>().await.map_err(|e| ClaudeError::parse_error(
+                        e.to_string(),
+                        None,
+                        Some(e),
+                        Some(concat!(file!(), ":", line!()))
+                    ))
+                }).await
+            }))
+            .await
+    }
+    
+    /// Count the input tokens of a request with the API's token counting endpoint
+    ///
+    /// The count comes from the model's own tokenizer and includes the system
+    /// prompt, tools and images. No message is generated.
+    pub async fn count_tokens(&self, request: &MessageRequest) -> ClaudeResult<u32> {
+        self.count_tokens_for(&CountTokensRequest::from(request)).await
+    }
+    
+    /// Send a token counting request
+    pub(crate) async fn count_tokens_for(&self, body: &CountTokensRequest<'_>) -> ClaudeResult<u32> {
+        let endpoint = format!("{}/messages/count_tokens", self.base_url);
+        let count: TokenCount = self.send_json(|http| http.post(&endpoint).json(body)).await?;
+        Ok(count.input_tokens)
+    }
+    
+    /// Get a client for the Message Batches API
+    pub fn batches(&self) -> BatchClient {
+        BatchClient::new(Arc::new(self.clone()))
+    }
+    
+    /// Get a client for the Models API
+    pub fn models(&self) -> ModelsClient {
+        ModelsClient::new(Arc::new(self.clone()))
+    }
+    
     /// Get the domain client registry
+    ///
+    /// Every `with_*` and `add_*` setter returns a client with a new registry,
+    /// so domain clients always use the settings of the client they came
+    /// from, and custom domains must be registered after configuring it.
     pub fn domains(&self) -> Arc<DomainClientRegistry> {
         self.domain_registry.get_or_init(|| {
             let claude_arc = Arc::new(self.clone());
@@ -0,0 +1,407 @@
+// Client configuration
+
+use crate::client::Claude;
+use crate::tls::{global_tls_config, TlsConfig};
+use crate::retry::RetryPolicy;
+use crate::types::*;
+use reqwest::header::{self, HeaderMap, HeaderName, HeaderValue};
+use reqwest::Client as HttpClient;
+use std::time::Duration;
+
+/// Base URL of the Anthropic API
+pub const DEFAULT_BASE_URL: &str = "https://api.anthropic.com/v1";
+
+/// Value of the `anthropic-version` header sent by default
+pub const DEFAULT_API_VERSION: &str = "2023-06-01";
+
+/// Timeout for a whole request, unless configured otherwise
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Headers the client sets itself, which cannot be added as custom headers
+const RESERVED_HEADERS: [&str; 3] = ["x-api-key", "anthropic-version", "anthropic-beta"];
+
+/// The `user-agent` header sent by default
+pub(crate) fn default_user_agent() -> String {
+    format!("claude-rs/{}", env!("CARGO_PKG_VERSION"))
+}
+
+/// Builder for a `Claude` client with full control over its HTTP configuration
+///
+/// Created with `Claude::builder`. Unlike `Claude::new`, `build` returns an
+/// error instead of panicking when the HTTP client cannot be created or a
+/// setting is invalid.
+///
+/// TLS, timeouts (other than the read timeout), proxy, connection pool and
+/// HTTP/2 settings configure the HTTP client built by `build`, so they cannot be
+/// combined with an external client passed to `http_client`. Headers, API
+/// version and beta flags are sent with every request either way.
+///
+/// ## Example
+///
+/// ```no_run
+/// # use claude_rs::{Claude, ClaudeModel};
+/// # use std::time::Duration;
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let claude = Claude::builder("your_api_key_here")
+///     .model(ClaudeModel::Sonnet37)
+///     .timeout(Duration::from_secs(600))
+///     .connect_timeout(Duration::from_secs(10))
+///     .proxy(reqwest::Proxy::https("http://proxy.internal:8080")?)
+///     .user_agent("my-app/1.2")
+///     .beta("token-efficient-tools-2025-02-19")
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ClaudeBuilder {
+    api_key: String,
+    base_url: String,
+    model: ClaudeModel,
+    default_max_tokens: Option<u32>,
+    retry_policy: RetryPolicy,
+    api_version: String,
+    beta_features: Vec<String>,
+    headers: HeaderMap,
+    user_agent: String,
+    read_timeout: Option<Duration>,
+    invalid: Option<ClaudeError>,
+
+    // Settings of the HTTP client built by `build`
+    tls_config: TlsConfig,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxies: Vec<reqwest::Proxy>,
+    no_proxy: bool,
+    pool_idle_timeout: Option<Duration>,
+    pool_max_idle_per_host: Option<usize>,
+    tcp_keepalive: Option<Duration>,
+    http2_prior_knowledge: bool,
+    http2_keep_alive_interval: Option<Duration>,
+    http2_adaptive_window: bool,
+    transport_configured: bool,
+
+    http_client: Option<HttpClient>,
+}
+
+impl ClaudeBuilder {
+    /// Create a builder with the default settings and the global TLS configuration
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            base_url: DEFAULT_BASE_URL.to_string(),
+            model: ClaudeModel::Sonnet37,
+            default_max_tokens: None,
+            retry_policy: RetryPolicy::none(),
+            api_version: DEFAULT_API_VERSION.to_string(),
+            beta_features: Vec::new(),
+            headers: HeaderMap::new(),
+            user_agent: default_user_agent(),
+            read_timeout: None,
+            invalid: None,
+            tls_config: global_tls_config(),
+            timeout: Some(DEFAULT_REQUEST_TIMEOUT),
+            connect_timeout: None,
+            proxies: Vec::new(),
+            no_proxy: false,
+            pool_idle_timeout: None,
+            pool_max_idle_per_host: None,
+            tcp_keepalive: None,
+            http2_prior_knowledge: false,
+            http2_keep_alive_interval: None,
+            http2_adaptive_window: false,
+            transport_configured: false,
+            http_client: None,
+        }
+    }
+
+    /// Set the base URL of the API
+    pub fn base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = url.into();
+        self
+    }
+
+    /// Set the default model for requests
+    pub fn model(mut self, model: ClaudeModel) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Set a default max_tokens value for all requests
+    pub fn default_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.default_max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Set the retry policy for transient API failures
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Set the `anthropic-version` header
+    pub fn api_version(mut self, version: impl Into<String>) -> Self {
+        self.api_version = version.into();
+        self
+    }
+
+    /// Enable a beta feature with the `anthropic-beta` header
+    ///
+    /// Call once per feature; all of them are sent with every request.
+    pub fn beta(mut self, feature: impl Into<String>) -> Self {
+        let feature = feature.into();
+        if !self.beta_features.contains(&feature) {
+            self.beta_features.push(feature);
+        }
+        self
+    }
+
+    /// Add a header to every request
+    ///
+    /// The API key, version and beta headers are set with their own methods
+    /// and cannot be added here.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        let parsed = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| format!("Invalid header name '{}': {}", name, e))
+            .and_then(|name| {
+                if RESERVED_HEADERS.contains(&name.as_str()) {
+                    return Err(format!("Header '{}' is set by the client and cannot be overridden", name));
+                }
+                HeaderValue::from_str(value)
+                    .map(|value| (name.clone(), value))
+                    .map_err(|e| format!("Invalid value for header '{}': {}", name, e))
+            });
+        match parsed {
+            Ok((name, value)) => {
+                self.headers.insert(name, value);
+            }
+            Err(message) => self.reject(message),
+        }
+        self
+    }
+
+    /// Set the `user-agent` header, `claude-rs/<version>` by default
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// Fail a read that receives no data for `timeout`
+    ///
+    /// Applies to waiting for the response headers, the response body, and
+    /// each chunk of a stream, so a long stream that keeps producing events is
+    /// not cut off. Read timeouts are retried like other timeouts.
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the TLS configuration of this client
+    ///
+    /// Defaults to the global configuration from `set_tls_config`.
+    pub fn tls_config(mut self, config: TlsConfig) -> Self {
+        self.tls_config = config;
+        self.transport_configured = true;
+        self
+    }
+
+    /// Set the timeout for a whole request, including reading the response body
+    ///
+    /// Defaults to five minutes. Long streams may need a longer timeout, or
+    /// none with `no_timeout` and a `read_timeout` instead.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self.transport_configured = true;
+        self
+    }
+
+    /// Remove the timeout for whole requests
+    pub fn no_timeout(mut self) -> Self {
+        self.timeout = None;
+        self.transport_configured = true;
+        self
+    }
+
+    /// Set the timeout for establishing a connection
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self.transport_configured = true;
+        self
+    }
+
+    /// Send requests through a proxy
+    ///
+    /// Without a proxy, the system proxy from the environment is used.
+    pub fn proxy(mut self, proxy: reqwest::Proxy) -> Self {
+        self.proxies.push(proxy);
+        self.transport_configured = true;
+        self
+    }
+
+    /// Ignore the system proxy from the environment
+    pub fn no_proxy(mut self) -> Self {
+        self.no_proxy = true;
+        self.transport_configured = true;
+        self
+    }
+
+    /// Close idle connections after `timeout`
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self.transport_configured = true;
+        self
+    }
+
+    /// Set the maximum number of idle connections kept per host
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self.transport_configured = true;
+        self
+    }
+
+    /// Send TCP keepalive probes on idle connections
+    pub fn tcp_keepalive(mut self, interval: Duration) -> Self {
+        self.tcp_keepalive = Some(interval);
+        self.transport_configured = true;
+        self
+    }
+
+    /// Use HTTP/2 without negotiating it first
+    pub fn http2_prior_knowledge(mut self) -> Self {
+        self.http2_prior_knowledge = true;
+        self.transport_configured = true;
+        self
+    }
+
+    /// Send HTTP/2 keep-alive pings at `interval`
+    pub fn http2_keep_alive_interval(mut self, interval: Duration) -> Self {
+        self.http2_keep_alive_interval = Some(interval);
+        self.transport_configured = true;
+        self
+    }
+
+    /// Adjust HTTP/2 flow control windows to the connection's throughput
+    pub fn http2_adaptive_window(mut self, enabled: bool) -> Self {
+        self.http2_adaptive_window = enabled;
+        self.transport_configured = true;
+        self
+    }
+
+    /// Use an HTTP client built elsewhere, such as one shared with other services
+    pub fn http_client(mut self, client: HttpClient) -> Self {
+        self.http_client = Some(client);
+        self
+    }
+
+    /// Build the client
+    pub fn build(self) -> ClaudeResult<Claude> {
+        if let Some(error) = self.invalid {
+            return Err(error);
+        }
+        if self.default_max_tokens == Some(0) {
+            return Err(ClaudeError::ValidationError("max_tokens must be greater than 0".into()));
+        }
+
+        let mut headers = self.headers;
+        let user_agent = HeaderValue::from_str(&self.user_agent).map_err(|e| {
+            ClaudeError::InvalidParameter(format!("Invalid user agent '{}': {}", self.user_agent, e))
+        })?;
+        headers.insert(header::USER_AGENT, user_agent);
+        HeaderValue::from_str(&self.api_version).map_err(|e| {
+            ClaudeError::InvalidParameter(format!("Invalid API version '{}': {}", self.api_version, e))
+        })?;
+        HeaderValue::from_str(&self.beta_features.join(",")).map_err(|e| {
+            ClaudeError::InvalidParameter(format!("Invalid beta feature: {}", e))
+        })?;
+
+        let http_client = match self.http_client {
+            Some(_) if self.transport_configured => {
+                return Err(ClaudeError::InvalidParameter(
+                    "TLS, timeout, proxy, pool and HTTP/2 settings cannot be combined with an external HTTP client".into()
+                ));
+            }
+            Some(client) => client,
+            None => {
+                let mut default_headers = HeaderMap::new();
+                default_headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+                if !self.tls_config.pinned_certificates.is_empty() && !self.base_url.starts_with("https://") {
+                    return Err(ClaudeError::InvalidParameter(format!(
+                        "Certificate pinning requires a TLS connection, but the base URL is {}",
+                        self.base_url
+                    )));
+                }
+
+                let mut builder = self.tls_config.apply(HttpClient::builder().default_headers(default_headers))?;
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                for proxy in self.proxies {
+                    builder = builder.proxy(proxy);
+                }
+                if self.no_proxy {
+                    builder = builder.no_proxy();
+                }
+                if let Some(timeout) = self.pool_idle_timeout {
+                    builder = builder.pool_idle_timeout(timeout);
+                }
+                if let Some(max) = self.pool_max_idle_per_host {
+                    builder = builder.pool_max_idle_per_host(max);
+                }
+                if let Some(interval) = self.tcp_keepalive {
+                    builder = builder.tcp_keepalive(interval);
+                }
+                if self.http2_prior_knowledge {
+                    builder = builder.http2_prior_knowledge();
+                }
+                if let Some(interval) = self.http2_keep_alive_interval {
+                    builder = builder.http2_keep_alive_interval(interval);
+                }
+                if self.http2_adaptive_window {
+                    builder = builder.http2_adaptive_window(true);
+                }
+
+                builder.build().map_err(|e| ClaudeError::request_error(
+                    "Failed to create HTTP client",
+                    None,
+                    Some(e),
+                    Some(concat!(file!(), ":", line!()))
+                ))?
+            }
+        };
+
+        let mut claude = Claude::from_parts(http_client, SecureApiKey::new(self.api_key), self.base_url)
+            .with_model(self.model)
+            .with_retry_policy(self.retry_policy);
+        claude.default_max_tokens = self.default_max_tokens;
+        claude.api_version = self.api_version;
+        claude.beta_features = self.beta_features;
+        claude.default_headers = headers;
+        claude.read_timeout = self.read_timeout;
+        Ok(claude)
+    }
+
+    /// Record the first invalid setting, to be returned by `build`
+    fn reject(&mut self, message: String) {
+        if self.invalid.is_none() {
+            self.invalid = Some(ClaudeError::InvalidParameter(message));
+        }
+    }
+}
+
+impl std::fmt::Debug for ClaudeBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClaudeBuilder")
+            .field("base_url", &self.base_url)
+            .field("model", &self.model)
+            .field("api_version", &self.api_version)
+            .field("beta_features", &self.beta_features)
+            .field("user_agent", &self.user_agent)
+            .field("timeout", &self.timeout)
+            .field("read_timeout", &self.read_timeout)
+            .field("external_http_client", &self.http_client.is_some())
+            .finish()
+    }
+}
@@ -2,7 +2,7 @@
 
 use crate::types::*;
 use crate::middleware::ContextManager;
-use crate::utils::token_counter::{TokenCounter, Claude3TokenCounter, get_token_counter};
+use crate::utils::token_counter::{AsyncTokenCounter, Claude3TokenCounter, get_token_counter};
 use async_trait::async_trait;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -16,6 +16,9 @@ use tokio::sync::Mutex;
 /// 2. Prioritizing high-importance messages when the context exceeds the token limit
 /// 3. Always including the most recent message
 /// 
+/// When the builder passes the request's system prompt, its tokens are
+/// subtracted from the limit before any messages are selected.
+/// 
 /// ## Example
 /// 
 /// ```no_run
@@ -41,7 +44,7 @@ pub struct AdaptiveContextManager {
     /// Message history stored between requests
     history: Mutex<Vec<Message>>,
     /// Token counter for accurate token counting
-    token_counter: Arc<dyn TokenCounter>,
+    token_counter: Arc<dyn AsyncTokenCounter>,
     /// Default model for token counting when not specified
     #[allow(dead_code)]
     default_model: ClaudeModel,
@@ -76,23 +79,38 @@ impl AdaptiveContextManager {
             max_tokens,
             importance_scorer: Arc::new(importance_scorer),
             history: Mutex::new(Vec::new()),
-            token_counter: get_token_counter(&model),
+            token_counter: Arc::new(get_token_counter(&model)),
             default_model: model,
         }
     }
     
-    /// Count tokens in a text using the configured TokenCounter
+    /// Create a new AdaptiveContextManager that counts tokens with `token_counter`
     /// 
-    /// This method uses the tiktoken library for accurate token counting
-    /// that matches Claude's actual tokenization.
-    #[allow(dead_code)]
-    fn count_tokens(&self, text: &str) -> u32 {
-        self.token_counter.count_tokens(text)
+    /// Use this with an `ApiTokenCounter` to budget the context with the exact
+    /// counts from the API's token counting endpoint.
+    /// 
+    /// # Arguments
+    /// 
+    /// * `max_tokens` - Maximum number of tokens to use for context
+    /// * `importance_scorer` - Implementation of ImportanceScorer that ranks message importance
+    /// * `token_counter` - Any `TokenCounter` or `AsyncTokenCounter`
+    pub fn with_token_counter(
+        max_tokens: u32,
+        importance_scorer: impl ImportanceScorer + 'static,
+        token_counter: impl AsyncTokenCounter + 'static,
+    ) -> Self {
+        Self {
+            max_tokens,
+            importance_scorer: Arc::new(importance_scorer),
+            history: Mutex::new(Vec::new()),
+            token_counter: Arc::new(token_counter),
+            default_model: ClaudeModel::Sonnet37,
+        }
     }
     
     /// Count tokens in a message using the configured TokenCounter
-    fn count_message_tokens(&self, message: &Message) -> u32 {
-        self.token_counter.count_message_tokens(message)
+    async fn count_message_tokens(&self, message: &Message) -> ClaudeResult<u32> {
+        self.token_counter.message_tokens(message).await
     }
     
     /// Legacy token counting for backward compatibility
@@ -100,7 +118,7 @@ impl AdaptiveContextManager {
     #[allow(dead_code)]
     fn estimate_tokens(text: &str) -> u32 {
         // Approximate tokens as 4 characters per token
-        (text.len() as u32 + 3) / 4
+        (text.len() as u32).div_ceil(4)
     }
     
     /// Clear the message history
@@ -114,60 +132,78 @@ impl AdaptiveContextManager {
         let history = self.history.lock().await;
         history.len()
     }
-}
-
-#[async_trait]
-pub trait ImportanceScorer: Send + Sync {
-    /// Score the importance of a message (0.0 to 1.0)
-    async fn score_importance(&self, message: &Message) -> f32;
-}
-
-#[async_trait]
-impl ContextManager for AdaptiveContextManager {
-    async fn process_messages(&self, mut messages: Vec<Message>) -> Result<Vec<Message>, ClaudeError> {
+    
+    /// Count tokens in a system prompt using the configured TokenCounter
+    async fn count_system_tokens(&self, system: Option<&SystemPrompt>) -> ClaudeResult<u32> {
+        match system {
+            Some(system) => self.token_counter.system_tokens(system).await,
+            None => Ok(0),
+        }
+    }
+    
+    /// Select the messages to send, keeping `reserved_tokens` of the limit free
+    ///
+    /// Messages are counted one at a time, newest first, and counting stops as
+    /// soon as the budget is exceeded, so an `ApiTokenCounter` only makes the
+    /// requests needed to decide what fits.
+    async fn select_messages(&self, messages: Vec<Message>, reserved_tokens: u32) -> Result<Vec<Message>, ClaudeError> {
+        let max_tokens = self.max_tokens.saturating_sub(reserved_tokens);
         let history = self.history.lock().await;
+        let has_latest = !messages.is_empty();
         
         // Add historical context
         let mut all_messages = history.clone();
-        all_messages.extend(messages.clone());
+        all_messages.extend(messages);
         
-        // Calculate total tokens with accurate token counter
-        let total_tokens: u32 = all_messages.iter()
-            .map(|msg| self.count_message_tokens(msg))
-            .sum();
+        // Count from the newest message back until the limit is exceeded
+        let mut message_tokens = vec![None; all_messages.len()];
+        let mut total_tokens: u32 = 0;
+        for (index, msg) in all_messages.iter().enumerate().rev() {
+            let tokens = self.count_message_tokens(msg).await?;
+            message_tokens[index] = Some(tokens);
+            total_tokens = total_tokens.saturating_add(tokens);
+            if total_tokens > max_tokens {
+                break;
+            }
+        }
         
         // If within limit, use all messages
-        if total_tokens <= self.max_tokens {
+        if total_tokens <= max_tokens {
             return Ok(all_messages);
         }
         
-        // Otherwise, prioritize messages based on importance
+        // Take messages until we hit the token limit
+        let mut result = Vec::new();
+        let mut current_tokens = 0;
+        
+        // Always include the most recent message, which was counted first
+        if has_latest {
+            let latest = all_messages.pop().unwrap();
+            current_tokens += message_tokens.pop().flatten().unwrap_or_default();
+            result.push(latest);
+        }
+        
+        // Score the remaining messages by importance
         let mut scored_messages = Vec::with_capacity(all_messages.len());
-        for msg in all_messages {
+        for (msg, tokens) in all_messages.into_iter().zip(message_tokens) {
             let score = self.importance_scorer.score_importance(&msg).await;
-            let tokens = self.count_message_tokens(&msg);
             scored_messages.push((msg, score, tokens));
         }
         
         // Sort by importance (descending)
         scored_messages.sort_by(|(_, a, _), (_, b, _)| b.partial_cmp(a).unwrap());
         
-        // Take messages until we hit the token limit
-        let mut result = Vec::new();
-        let mut current_tokens = 0;
-        
-        // Always include the most recent message
-        if !messages.is_empty() {
-            let latest = messages.pop().unwrap();
-            let latest_tokens = self.count_message_tokens(&latest);
-            current_tokens += latest_tokens;
-            result.push(latest);
-        }
-        
-        // Add other messages based on importance
+        // Add other messages based on importance, counting the rest only while
+        // there is budget left for them
         for (msg, _, tokens) in scored_messages {
-            // We already have the token count for each message
-            if current_tokens + tokens <= self.max_tokens {
+            if current_tokens >= max_tokens {
+                break;
+            }
+            let tokens = match tokens {
+                Some(tokens) => tokens,
+                None => self.count_message_tokens(&msg).await?,
+            };
+            if current_tokens + tokens <= max_tokens {
                 current_tokens += tokens;
                 result.push(msg);
             } else {
@@ -178,6 +214,29 @@ impl ContextManager for AdaptiveContextManager {
         
         Ok(result)
     }
+}
+
+#[async_trait]
+pub trait ImportanceScorer: Send + Sync {
+    /// Score the importance of a message (0.0 to 1.0)
+    async fn score_importance(&self, message: &Message) -> f32;
+}
+
+#[async_trait]
+impl ContextManager for AdaptiveContextManager {
+    async fn process_messages(&self, messages: Vec<Message>) -> Result<Vec<Message>, ClaudeError> {
+        self.select_messages(messages, 0).await
+    }
+    
+    async fn process_request_messages(
+        &self,
+        system: Option<&SystemPrompt>,
+        messages: Vec<Message>,
+    ) -> Result<Vec<Message>, ClaudeError> {
+        // The system prompt is sent with every request, so its tokens come off the limit
+        let system_tokens = self.count_system_tokens(system).await?;
+        self.select_messages(messages, system_tokens).await
+    }
     
     async fn update_with_response(&self, response: &MessageResponse) -> Result<(), ClaudeError> {
         let mut history = self.history.lock().await;
@@ -214,7 +273,7 @@ impl ImportanceScorer for SimpleImportanceScorer {
         // Check for important keywords
         let mut keyword_bonus = 0.0;
         for content in &message.content {
-            if let Content::Text { text } = content {
+            if let Content::Text { text, .. } = content {
                 if text.contains("important") || text.contains("critical") || text.contains("essential") {
                     keyword_bonus += 0.2;
                 }
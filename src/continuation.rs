@@ -0,0 +1,102 @@
+// Continuation of responses cut off at max_tokens
+
+use crate::types::*;
+
+/// Shortest repeated text that is removed when joining a continuation
+const MIN_OVERLAP_CHARS: usize = 20;
+
+/// How far back into the answer a repeated passage is looked for
+const MAX_OVERLAP_BYTES: usize = 2000;
+
+/// Build the messages that ask the model to continue `response`
+///
+/// The answer so far becomes an assistant prefill, appended to any prefill
+/// the conversation already ends with. Returns `None` when the response
+/// cannot be continued: it was not cut off at `max_tokens`, it contains
+/// something other than text, or there is no text to continue from.
+pub(crate) fn continuation_messages(messages: &[Message], response: &MessageResponse) -> Option<Vec<Message>> {
+    if !response.is_truncated() {
+        return None;
+    }
+    let answer = text_only(&response.content)?;
+
+    let mut messages = messages.to_vec();
+    let mut prefill = match messages.last() {
+        Some(Message { role: Role::Assistant, content }) => {
+            let prefill = text_only(content)?;
+            messages.pop();
+            prefill
+        }
+        _ => String::new(),
+    };
+    prefill.push_str(&answer);
+
+    // The API rejects a prefill that ends with whitespace
+    let prefill = prefill.trim_end();
+    if prefill.is_empty() {
+        return None;
+    }
+    messages.push(Message {
+        role: Role::Assistant,
+        content: vec![Content::text(prefill)],
+    });
+    Some(messages)
+}
+
+/// Append a continuation to the response it continues
+///
+/// The continuation's text is joined onto the last text block, dropping any
+/// passage the model repeated from the end of the answer. Usage is summed,
+/// and the stop reason is taken from the continuation.
+pub(crate) fn join_continuation(mut response: MessageResponse, continuation: MessageResponse) -> MessageResponse {
+    let mut blocks = continuation.content.into_iter().peekable();
+    if let Some(Content::Text { text: next, .. }) = blocks.next_if(|block| matches!(block, Content::Text { .. })) {
+        match response.content.iter_mut().rev().find_map(|block| match block {
+            Content::Text { text, .. } => Some(text),
+            _ => None,
+        }) {
+            Some(text) => *text = join_text(text, &next),
+            None => response.content.push(Content::text(next)),
+        }
+    }
+    response.content.extend(blocks);
+
+    response.usage += &continuation.usage;
+    response.stop_reason = continuation.stop_reason;
+    response.stop_sequence = continuation.stop_sequence;
+    response
+}
+
+/// Join two pieces of an answer, as the model saw them in the prefill
+fn join_text(answer: &str, continuation: &str) -> String {
+    let answer = answer.trim_end();
+    let repeated = repeated_prefix(answer, continuation.trim_start());
+    let continuation = match repeated {
+        0 => continuation,
+        len => &continuation.trim_start()[len..],
+    };
+    format!("{}{}", answer, continuation)
+}
+
+/// Length in bytes of the longest start of `continuation` that repeats the end of `answer`
+fn repeated_prefix(answer: &str, continuation: &str) -> usize {
+    let window = answer.len().min(continuation.len()).min(MAX_OVERLAP_BYTES);
+    continuation.char_indices()
+        .map(|(index, _)| index)
+        .chain(std::iter::once(continuation.len()))
+        .take_while(|&index| index <= window)
+        .skip(MIN_OVERLAP_CHARS)
+        .filter(|&index| answer.ends_with(&continuation[..index]))
+        .last()
+        .unwrap_or(0)
+}
+
+/// Concatenate the text of `content`, or `None` if it has other kinds of blocks
+fn text_only(content: &[Content]) -> Option<String> {
+    content.iter()
+        .map(|block| match block {
+            Content::Text { text, .. } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect()
+}
@@ -0,0 +1,493 @@
+// Cost tracking and spending budgets
+
+use crate::middleware::{RequestMiddleware, ResponseMiddleware};
+use crate::models::model_family;
+use crate::types::*;
+use crate::utils::token_counter::get_token_counter;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Discount applied to requests processed with the Message Batches API
+pub const DEFAULT_BATCH_DISCOUNT: f64 = 0.5;
+
+/// Prices of a model in USD per million tokens
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPricing {
+    /// Price of uncached input tokens
+    pub input: f64,
+    /// Price of output tokens, including thinking
+    pub output: f64,
+    /// Price of input tokens written to the prompt cache
+    pub cache_write: f64,
+    /// Price of input tokens read from the prompt cache
+    pub cache_read: f64,
+}
+
+impl ModelPricing {
+    /// Create pricing with the standard cache prices: writes cost 1.25x and
+    /// reads 0.1x the input price
+    pub fn new(input: f64, output: f64) -> Self {
+        Self {
+            input,
+            output,
+            cache_write: input * 1.25,
+            cache_read: input * 0.1,
+        }
+    }
+
+    /// Override the cache write and read prices
+    pub fn with_cache_prices(mut self, cache_write: f64, cache_read: f64) -> Self {
+        self.cache_write = cache_write;
+        self.cache_read = cache_read;
+        self
+    }
+
+    /// Calculate the cost of a response's usage in USD
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        (usage.input_tokens as f64 * self.input
+            + usage.output_tokens as f64 * self.output
+            + usage.cache_creation_input_tokens as f64 * self.cache_write
+            + usage.cache_read_input_tokens as f64 * self.cache_read)
+            / 1_000_000.0
+    }
+}
+
+/// Per-model prices used by a `CostTracker`
+///
+/// Prices are looked up by exact model id first, then by model family, so an
+/// entry for `claude-sonnet-4` covers every snapshot and alias of that model.
+#[derive(Debug, Clone)]
+pub struct PriceTable {
+    prices: HashMap<String, ModelPricing>,
+    batch_discount: f64,
+}
+
+impl PriceTable {
+    /// Create an empty price table
+    pub fn new() -> Self {
+        Self {
+            prices: HashMap::new(),
+            batch_discount: DEFAULT_BATCH_DISCOUNT,
+        }
+    }
+
+    /// Create a price table with the list prices of Claude 3 and later models
+    pub fn with_builtin_prices() -> Self {
+        Self::new()
+            .with_price("claude-3-haiku", ModelPricing::new(0.25, 1.25).with_cache_prices(0.30, 0.03))
+            .with_price("claude-3-sonnet", ModelPricing::new(3.0, 15.0))
+            .with_price("claude-3-opus", ModelPricing::new(15.0, 75.0))
+            .with_price("claude-3-5-haiku", ModelPricing::new(0.80, 4.0))
+            .with_price("claude-3-5-sonnet", ModelPricing::new(3.0, 15.0))
+            .with_price("claude-3-7-sonnet", ModelPricing::new(3.0, 15.0))
+            .with_price("claude-sonnet-4", ModelPricing::new(3.0, 15.0))
+            .with_price("claude-sonnet-4-5", ModelPricing::new(3.0, 15.0))
+            .with_price("claude-opus-4", ModelPricing::new(15.0, 75.0))
+            .with_price("claude-opus-4-1", ModelPricing::new(15.0, 75.0))
+            .with_price("claude-opus-4-5", ModelPricing::new(5.0, 25.0))
+            .with_price("claude-haiku-4-5", ModelPricing::new(1.0, 5.0))
+    }
+
+    /// Set the prices of a model id or model family
+    pub fn with_price(mut self, model: impl Into<String>, pricing: ModelPricing) -> Self {
+        self.prices.insert(model.into(), pricing);
+        self
+    }
+
+    /// Set the discount applied to batch requests, from 0.0 (none) to 1.0 (free)
+    pub fn with_batch_discount(mut self, discount: f64) -> Self {
+        self.batch_discount = discount.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Get the prices of a model
+    pub fn get(&self, model: &str) -> Option<&ModelPricing> {
+        self.prices.get(model).or_else(|| self.prices.get(model_family(model)))
+    }
+
+    /// Calculate the cost of a response's usage in USD, if the model is priced
+    pub fn cost(&self, model: &str, usage: &Usage, batch: bool) -> Option<f64> {
+        let cost = self.get(model)?.cost(usage);
+        Some(if batch { cost * (1.0 - self.batch_discount) } else { cost })
+    }
+}
+
+impl Default for PriceTable {
+    fn default() -> Self {
+        Self::with_builtin_prices()
+    }
+}
+
+/// Spend accumulated for a client, model or tag
+#[derive(Debug, Clone, Default)]
+pub struct Spend {
+    /// Cost in USD
+    pub cost: f64,
+    /// Number of responses recorded
+    pub responses: u64,
+    /// Combined token usage
+    pub usage: Usage,
+}
+
+impl Spend {
+    fn add(&mut self, cost: f64, usage: &Usage) {
+        self.cost += cost;
+        self.responses += 1;
+        self.usage += usage;
+    }
+}
+
+/// Snapshot of the spend recorded by a `CostTracker`
+#[derive(Debug, Clone, Default)]
+pub struct CostReport {
+    /// Spend across all clients, models and tags
+    pub total: Spend,
+    /// Spend by client name, for trackers created with `for_client`
+    pub by_client: HashMap<String, Spend>,
+    /// Spend by model id
+    pub by_model: HashMap<String, Spend>,
+    /// Spend by tag, from `MessageBuilder::cost_tag` or trackers created with `tagged`
+    pub by_tag: HashMap<String, Spend>,
+    /// Responses from models missing from the price table, counted at no cost
+    pub unpriced_responses: u64,
+}
+
+/// What a budget limits
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BudgetScope {
+    /// All spend recorded by the tracker
+    Total,
+    /// Spend of a named client
+    Client(String),
+    /// Spend on a model, including its aliases and snapshots
+    Model(String),
+    /// Spend under a tag
+    Tag(String),
+}
+
+impl std::fmt::Display for BudgetScope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BudgetScope::Total => write!(f, "total"),
+            BudgetScope::Client(name) => write!(f, "client '{}'", name),
+            BudgetScope::Model(model) => write!(f, "model '{}'", model),
+            BudgetScope::Tag(tag) => write!(f, "tag '{}'", tag),
+        }
+    }
+}
+
+/// How strictly a budget is enforced
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetKind {
+    /// Requests fail once the recorded spend reaches the limit
+    ///
+    /// Requests already in flight may take the spend past the limit.
+    Soft,
+    /// Requests fail if their worst-case cost would take the spend past the limit
+    ///
+    /// The worst case is the estimated input tokens plus `max_tokens` of output.
+    Hard,
+}
+
+/// A spending limit in USD
+#[derive(Debug, Clone, PartialEq)]
+pub struct Budget {
+    pub scope: BudgetScope,
+    pub kind: BudgetKind,
+    pub limit: f64,
+}
+
+impl Budget {
+    /// Create a soft limit on the tracker's total spend
+    pub fn soft(limit: f64) -> Self {
+        Self { scope: BudgetScope::Total, kind: BudgetKind::Soft, limit }
+    }
+
+    /// Create a hard limit on the tracker's total spend
+    pub fn hard(limit: f64) -> Self {
+        Self { scope: BudgetScope::Total, kind: BudgetKind::Hard, limit }
+    }
+
+    /// Limit the spend of a named client instead
+    pub fn for_client(mut self, name: impl Into<String>) -> Self {
+        self.scope = BudgetScope::Client(name.into());
+        self
+    }
+
+    /// Limit the spend on a model instead
+    ///
+    /// Responses are matched by model family, so a budget on an alias such as
+    /// `claude-sonnet-4-0` also covers the snapshot that answers it.
+    pub fn for_model(mut self, model: ClaudeModel) -> Self {
+        self.scope = BudgetScope::Model(model.as_str().to_string());
+        self
+    }
+
+    /// Limit the spend under a tag instead
+    pub fn for_tag(mut self, tag: impl Into<String>) -> Self {
+        self.scope = BudgetScope::Tag(tag.into());
+        self
+    }
+}
+
+#[derive(Default)]
+struct TrackerState {
+    report: CostReport,
+    budgets: Vec<Budget>,
+}
+
+/// Tracks the cost of responses and enforces spending budgets
+///
+/// A tracker is both request and response middleware: responses add their
+/// usage, priced with the tracker's `PriceTable`, to the spend, and requests
+/// fail with `ClaudeError::BudgetExceeded` once a budget is used up. Add it to
+/// a client with `Claude::with_cost_tracker`.
+///
+/// Trackers created with `for_client` and `tagged` share their spend and
+/// budgets with the tracker they came from, and also record under a client
+/// name or tag. Give each client one tracker. To tag single requests, use
+/// `MessageBuilder::cost_tag`, which takes precedence over the tracker's tag.
+///
+/// Only responses that pass through response middleware are recorded: `send`,
+/// `run_tools`, collected streams and domain operations. Record batch results
+/// with `record_batch`.
+///
+/// ## Example
+///
+/// ```no_run
+/// # use claude_rs::{Budget, Claude, CostTracker};
+/// # async fn example() -> Result<(), claude_rs::ClaudeError> {
+/// let tracker = CostTracker::default()
+///     .with_budget(Budget::hard(50.0))
+///     .with_budget(Budget::soft(5.0).for_tag("experiments"));
+///
+/// let claude = Claude::new("your_api_key_here").with_cost_tracker(tracker.for_client("backend"));
+/// claude.message()
+///     .cost_tag("experiments")
+///     .user_content("Try something new")
+///     .send()
+///     .await?;
+///
+/// // Later
+/// let report = tracker.report();
+/// println!("Spent ${:.2}", report.total.cost);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct CostTracker {
+    state: Arc<Mutex<TrackerState>>,
+    prices: Arc<PriceTable>,
+    client: Option<String>,
+    tag: Option<String>,
+}
+
+impl CostTracker {
+    /// Create a tracker that prices responses with `prices`
+    pub fn new(prices: PriceTable) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(TrackerState::default())),
+            prices: Arc::new(prices),
+            client: None,
+            tag: None,
+        }
+    }
+
+    /// Get a tracker that also records spend under a client name
+    pub fn for_client(&self, name: impl Into<String>) -> Self {
+        Self {
+            client: Some(name.into()),
+            ..self.clone()
+        }
+    }
+
+    /// Get a tracker that also records spend under a tag
+    pub fn tagged(&self, tag: impl Into<String>) -> Self {
+        Self {
+            tag: Some(tag.into()),
+            ..self.clone()
+        }
+    }
+
+    /// Add a budget, shared with all trackers created from this one
+    pub fn with_budget(self, budget: Budget) -> Self {
+        if let Ok(mut state) = self.state.lock() {
+            state.budgets.push(budget);
+        }
+        self
+    }
+
+    /// Get the price table
+    pub fn prices(&self) -> &PriceTable {
+        &self.prices
+    }
+
+    /// Get a snapshot of the recorded spend
+    pub fn report(&self) -> CostReport {
+        self.state.lock().map(|state| state.report.clone()).unwrap_or_default()
+    }
+
+    /// Get the total recorded spend in USD
+    pub fn total_cost(&self) -> f64 {
+        self.state.lock().map(|state| state.report.total.cost).unwrap_or(0.0)
+    }
+
+    /// Forget all recorded spend, keeping the budgets
+    pub fn reset(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.report = CostReport::default();
+        }
+    }
+
+    /// Record a response's usage and return its cost in USD
+    pub fn record(&self, response: &MessageResponse) -> f64 {
+        self.record_usage(&response.model, &response.usage, self.tag.as_deref(), false)
+    }
+
+    /// Record the response to `request`, under the request's cost tag if it has one
+    pub fn record_response(&self, request: &MessageRequest, response: &MessageResponse) -> f64 {
+        self.record_usage(&response.model, &response.usage, self.tag_for(request), false)
+    }
+
+    /// Record a response from the Message Batches API, with the batch discount
+    pub fn record_batch(&self, response: &MessageResponse) -> f64 {
+        self.record_usage(&response.model, &response.usage, self.tag.as_deref(), true)
+    }
+
+    /// Get the tag a request's spend is recorded under
+    fn tag_for<'a>(&'a self, request: &'a MessageRequest) -> Option<&'a str> {
+        request.cost_tag.as_deref().or(self.tag.as_deref())
+    }
+
+    fn record_usage(&self, model: &str, usage: &Usage, tag: Option<&str>, batch: bool) -> f64 {
+        let cost = self.prices.cost(model, usage, batch);
+        let Ok(mut state) = self.state.lock() else {
+            return cost.unwrap_or(0.0);
+        };
+
+        let report = &mut state.report;
+        if cost.is_none() {
+            report.unpriced_responses += 1;
+        }
+        let cost = cost.unwrap_or(0.0);
+
+        report.total.add(cost, usage);
+        report.by_model.entry(model.to_string()).or_default().add(cost, usage);
+        if let Some(client) = &self.client {
+            report.by_client.entry(client.clone()).or_default().add(cost, usage);
+        }
+        if let Some(tag) = tag {
+            report.by_tag.entry(tag.to_string()).or_default().add(cost, usage);
+        }
+        cost
+    }
+
+    /// Check that a request fits in every budget that applies to it
+    pub fn check_budgets(&self, request: &MessageRequest) -> ClaudeResult<()> {
+        let state = match self.state.lock() {
+            Ok(state) => state,
+            Err(_) => return Ok(()),
+        };
+
+        let tag = self.tag_for(request);
+        let mut worst_case = None;
+        for budget in &state.budgets {
+            let spent = match &budget.scope {
+                BudgetScope::Total => state.report.total.cost,
+                BudgetScope::Client(name) if self.client.as_ref() == Some(name) => {
+                    state.report.by_client.get(name).map_or(0.0, |spend| spend.cost)
+                }
+                // Spend is recorded under the model that answered, which may be
+                // a snapshot of the requested alias, so compare families
+                BudgetScope::Model(model) if model_family(model) == model_family(&request.model) => {
+                    state.report.by_model.iter()
+                        .filter(|(id, _)| model_family(id) == model_family(model))
+                        .map(|(_, spend)| spend.cost)
+                        .sum()
+                }
+                BudgetScope::Tag(budget_tag) if tag == Some(budget_tag.as_str()) => {
+                    state.report.by_tag.get(budget_tag).map_or(0.0, |spend| spend.cost)
+                }
+                _ => continue,
+            };
+
+            let projected = match budget.kind {
+                BudgetKind::Soft => spent,
+                BudgetKind::Hard => spent + *worst_case.get_or_insert_with(|| self.worst_case_cost(request)),
+            };
+            let exceeded = match budget.kind {
+                BudgetKind::Soft => projected >= budget.limit,
+                BudgetKind::Hard => projected > budget.limit,
+            };
+            if exceeded {
+                return Err(ClaudeError::BudgetExceeded {
+                    scope: budget.scope.to_string(),
+                    spent,
+                    limit: budget.limit,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Estimate the most a request can cost: its input plus `max_tokens` of output
+    fn worst_case_cost(&self, request: &MessageRequest) -> f64 {
+        let Some(pricing) = self.prices.get(&request.model) else {
+            return 0.0;
+        };
+
+        let counter = get_token_counter(&ClaudeModel::Custom(request.model.clone()));
+        let input_tokens: u32 = request.messages.iter()
+            .map(|message| counter.count_message_tokens(message))
+            .sum::<u32>()
+            + request.system.as_ref().map_or(0, |system| counter.count_system_tokens(system));
+
+        pricing.cost(&Usage {
+            input_tokens,
+            output_tokens: request.max_tokens.unwrap_or(0),
+            ..Usage::default()
+        })
+    }
+}
+
+impl Default for CostTracker {
+    fn default() -> Self {
+        Self::new(PriceTable::default())
+    }
+}
+
+impl std::fmt::Debug for CostTracker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CostTracker")
+            .field("client", &self.client)
+            .field("tag", &self.tag)
+            .field("total_cost", &self.total_cost())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl RequestMiddleware for CostTracker {
+    async fn process_request(&self, request: MessageRequest) -> Result<MessageRequest, ClaudeError> {
+        self.check_budgets(&request)?;
+        Ok(request)
+    }
+}
+
+#[async_trait]
+impl ResponseMiddleware for CostTracker {
+    async fn process_response(&self, response: MessageResponse) -> Result<MessageResponse, ClaudeError> {
+        self.record(&response);
+        Ok(response)
+    }
+
+    async fn process_response_for(
+        &self,
+        request: &MessageRequest,
+        response: MessageResponse,
+    ) -> Result<MessageResponse, ClaudeError> {
+        self.record_response(request, &response);
+        Ok(response)
+    }
+}
@@ -0,0 +1,241 @@
+// Token counting with the Messages API
+
+use crate::client::Claude;
+use crate::types::*;
+use crate::utils::token_counter::AsyncTokenCounter;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+
+/// Number of counts an `ApiTokenCounter` keeps before its cache is cleared
+pub const DEFAULT_TOKEN_CACHE_CAPACITY: usize = 10_000;
+
+/// Placeholder turn used to measure a system prompt on its own
+const PLACEHOLDER_TEXT: &str = ".";
+
+/// Body of a `/v1/messages/count_tokens` request
+///
+/// Only the fields that affect the input token count are sent.
+#[derive(Debug, Serialize)]
+pub(crate) struct CountTokensRequest<'a> {
+    model: &'a str,
+    messages: &'a [Message],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<&'a SystemPrompt>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<&'a Vec<Tool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_choice: Option<&'a ToolChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thinking: Option<&'a ThinkingConfig>,
+}
+
+impl<'a> From<&'a MessageRequest> for CountTokensRequest<'a> {
+    fn from(request: &'a MessageRequest) -> Self {
+        Self {
+            model: &request.model,
+            messages: &request.messages,
+            system: request.system.as_ref(),
+            tools: request.tools.as_ref(),
+            tool_choice: request.tool_choice.as_ref(),
+            thinking: request.thinking.as_ref(),
+        }
+    }
+}
+
+/// Response of the token counting endpoint
+#[derive(Debug, Deserialize)]
+pub(crate) struct TokenCount {
+    pub(crate) input_tokens: u32,
+}
+
+/// Token counter backed by the API's token counting endpoint
+///
+/// Counts come from Claude's own tokenizer, so they are exact for the model,
+/// including images. Results are cached in memory by request content, so
+/// history messages that are counted again on every turn only cost one call.
+///
+/// As an `AsyncTokenCounter`, each message is counted on its own as a single
+/// user turn: tool calls and results are counted through their text and
+/// images, and redacted thinking is not counted. A message's count includes
+/// the small fixed overhead of a request.
+///
+/// ## Example
+///
+/// ```no_run
+/// # use claude_rs::{Claude, AdaptiveContextManager, ApiTokenCounter, SimpleImportanceScorer};
+/// let claude = Claude::new("your_api_key_here");
+/// let counter = ApiTokenCounter::new(&claude);
+/// let claude = claude.with_context_manager(
+///     AdaptiveContextManager::with_token_counter(4000, SimpleImportanceScorer, counter)
+/// );
+/// ```
+pub struct ApiTokenCounter {
+    claude: Arc<Claude>,
+    model: String,
+    cache: Mutex<HashMap<u64, u32>>,
+    cache_capacity: usize,
+}
+
+impl ApiTokenCounter {
+    /// Create a counter that uses the client's default model
+    pub fn new(claude: &Claude) -> Self {
+        Self {
+            claude: Arc::new(claude.clone()),
+            model: claude.default_model.as_str().to_string(),
+            cache: Mutex::new(HashMap::new()),
+            cache_capacity: DEFAULT_TOKEN_CACHE_CAPACITY,
+        }
+    }
+
+    /// Count tokens for a different model
+    pub fn with_model(mut self, model: ClaudeModel) -> Self {
+        self.model = model.as_str().to_string();
+        self
+    }
+
+    /// Set how many counts are kept before the cache is cleared
+    pub fn with_cache_capacity(mut self, capacity: usize) -> Self {
+        self.cache_capacity = capacity;
+        self
+    }
+
+    /// Count the input tokens of a complete request
+    ///
+    /// The request's own model is used, and the count includes the system
+    /// prompt, tools and images.
+    pub async fn count_request_tokens(&self, request: &MessageRequest) -> ClaudeResult<u32> {
+        self.count(CountTokensRequest::from(request)).await
+    }
+
+    /// Get the number of cached counts
+    pub fn cached_counts(&self) -> usize {
+        self.cache.lock().map(|cache| cache.len()).unwrap_or(0)
+    }
+
+    /// Forget all cached counts
+    pub fn clear_cache(&self) {
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.clear();
+        }
+    }
+
+    /// Count a request body, using the cache when the same body was counted before
+    async fn count(&self, body: CountTokensRequest<'_>) -> ClaudeResult<u32> {
+        let serialized = serde_json::to_string(&body).map_err(|e| ClaudeError::parse_error(
+            format!("Failed to serialize token count request: {}", e),
+            None,
+            Some(e),
+            Some(concat!(file!(), ":", line!()))
+        ))?;
+        let mut hasher = DefaultHasher::new();
+        serialized.hash(&mut hasher);
+        let key = hasher.finish();
+
+        if let Some(tokens) = self.cache.lock().ok().and_then(|cache| cache.get(&key).copied()) {
+            return Ok(tokens);
+        }
+
+        let tokens = self.claude.count_tokens_for(&body).await?;
+        if let Ok(mut cache) = self.cache.lock() {
+            if cache.len() >= self.cache_capacity {
+                cache.clear();
+            }
+            cache.insert(key, tokens);
+        }
+        Ok(tokens)
+    }
+
+    /// Count a conversation of the given messages with the counter's model
+    async fn count_messages(&self, messages: &[Message], system: Option<&SystemPrompt>) -> ClaudeResult<u32> {
+        self.count(CountTokensRequest {
+            model: &self.model,
+            messages,
+            system,
+            tools: None,
+            tool_choice: None,
+            thinking: None,
+        }).await
+    }
+
+    /// Rewrite a message as a user turn the endpoint accepts on its own
+    fn standalone_turn(message: &Message) -> Message {
+        let mut content = Vec::new();
+        for block in &message.content {
+            match block {
+                Content::Text { text, .. } => content.push(Content::text(text.clone())),
+                Content::Image { source, .. } => content.push(Content::Image { source: source.clone(), cache_control: None }),
+                Content::Document { source, title, context, citations, .. } => content.push(Content::Document {
+                    source: source.clone(),
+                    title: title.clone(),
+                    context: context.clone(),
+                    citations: *citations,
+                    cache_control: None,
+                }),
+                Content::ToolUse { id, name, input, .. } => {
+                    content.push(Content::text(format!("{} {} {}", id, name, input)));
+                }
+                Content::ToolResult { tool_use_id, content: result, .. } => {
+                    content.push(Content::text(tool_use_id.clone()));
+                    match result {
+                        Some(ToolResultContent::Text(text)) => content.push(Content::text(text.clone())),
+                        Some(ToolResultContent::Blocks(blocks)) => {
+                            for block in blocks {
+                                content.push(match block {
+                                    ToolResultBlock::Text { text } => Content::text(text.clone()),
+                                    ToolResultBlock::Image { source } => Content::Image { source: source.clone(), cache_control: None },
+                                });
+                            }
+                        }
+                        None => {}
+                    }
+                }
+                Content::Thinking { thinking, .. } => content.push(Content::text(thinking.clone())),
+                Content::RedactedThinking { .. } => {}
+            }
+        }
+
+        // Empty text blocks are rejected by the API
+        content.retain(|block| !matches!(block, Content::Text { text, .. } if text.is_empty()));
+        Message { role: Role::User, content }
+    }
+
+    fn placeholder_turn() -> Message {
+        Message {
+            role: Role::User,
+            content: vec![Content::text(PLACEHOLDER_TEXT)],
+        }
+    }
+}
+
+#[async_trait]
+impl AsyncTokenCounter for ApiTokenCounter {
+    async fn message_tokens(&self, message: &Message) -> ClaudeResult<u32> {
+        let turn = Self::standalone_turn(message);
+        if turn.content.is_empty() {
+            return Ok(0);
+        }
+        self.count_messages(&[turn], None).await
+    }
+
+    async fn system_tokens(&self, system: &SystemPrompt) -> ClaudeResult<u32> {
+        // Measure the system prompt as the difference it makes to a minimal request
+        let placeholder = [Self::placeholder_turn()];
+        let with_system = self.count_messages(&placeholder, Some(system)).await?;
+        let without_system = self.count_messages(&placeholder, None).await?;
+        Ok(with_system.saturating_sub(without_system))
+    }
+}
+
+impl std::fmt::Debug for ApiTokenCounter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApiTokenCounter")
+            .field("model", &self.model)
+            .field("cached_counts", &self.cached_counts())
+            .field("cache_capacity", &self.cache_capacity)
+            .finish()
+    }
+}
@@ -5,6 +5,7 @@
 
 use crate::Claude;
 use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 use crate::domains::{DomainClient, DomainOperations, ValidationOperations};
 
 /// Base client for domain-specific API implementations
@@ -14,6 +15,8 @@ pub struct BaseDomainClient {
     claude: Arc<Claude>,
     /// Domain name for this client
     domain_name: String,
+    /// Token that cancels this client's operations
+    cancellation: Option<CancellationToken>,
 }
 
 impl BaseDomainClient {
@@ -21,7 +24,19 @@ impl BaseDomainClient {
     pub fn new(claude: Arc<Claude>, domain_name: impl Into<String>) -> Self {
         Self { 
             claude, 
-            domain_name: domain_name.into() 
+            domain_name: domain_name.into(),
+            cancellation: None,
+        }
+    }
+    
+    /// Get a copy of this client whose operations stop when `token` is cancelled
+    ///
+    /// The copy shares the Claude client, so its domain registry is kept.
+    pub fn with_cancellation(&self, token: CancellationToken) -> Self {
+        Self {
+            claude: self.claude.clone(),
+            domain_name: self.domain_name.clone(),
+            cancellation: Some(token),
         }
     }
 }
@@ -38,4 +53,8 @@ impl DomainOperations for BaseDomainClient {
     fn claude(&self) -> &Claude {
         &self.claude
     }
+    
+    fn cancellation_token(&self) -> Option<&CancellationToken> {
+        self.cancellation.as_ref()
+    }
 }
\ No newline at end of file
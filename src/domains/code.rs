@@ -2,9 +2,10 @@
 
 use crate::client::Claude;
 use crate::types::*;
-use crate::domains::{DomainClient, DomainOperations, ValidationOperations, base::BaseDomainClient};
+use crate::domains::{DomainClient, DomainOperations, DomainPrompt, ValidationOperations, base::BaseDomainClient};
 use serde::Deserialize;
 use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 
 // Code Assistance Client
 pub struct CodeAssistanceClient {
@@ -51,17 +52,30 @@ impl CodeAssistanceClient {
         }
     }
     
+    /// Get a copy of this client whose operations stop when `token` is cancelled
+    ///
+    /// Unlike `Claude::with_cancellation_token`, this keeps the client's domain
+    /// registry, so one token can be used per operation.
+    pub fn with_cancellation(&self, token: CancellationToken) -> Self {
+        Self {
+            base: self.base.with_cancellation(token),
+        }
+    }
+    
     /// Analyze code for issues and suggestions
     pub async fn analyze_code(&self, code: impl Into<String>, language: impl Into<String>) -> ClaudeResult<CodeAnalysis> {
         let code = self.validate_string(code, "code")?;
         let language = self.validate_string(language, "language")?;
         
-        let prompt = format!(
-            "Analyze this {} code for potential issues, bugs, and improvements. Provide your analysis in JSON format with 'issues' (array of issues with 'line', 'severity', 'description'), 'suggestions' (array of improvement suggestions with 'description', 'original_code', 'suggested_code', 'explanation'), 'complexity_score' (1-10), and 'summary'.\n\nCode:\n```{}\n{}\n```\n\nRespond with valid JSON only.",
-            language, language, code
+        let prompt = DomainPrompt::new(
+            format!(
+                "Analyze this {} code for potential issues, bugs, and improvements. Provide your analysis in JSON format with 'issues' (array of issues with 'line', 'severity', 'description'), 'suggestions' (array of improvement suggestions with 'description', 'original_code', 'suggested_code', 'explanation'), 'complexity_score' (1-10), and 'summary'.\n\n",
+                language
+            ),
+            format!("Code:\n```{}\n{}\n```\n\nRespond with valid JSON only.", language, code)
         );
         
-        self.json_operation(&prompt, Some(0.0), self.domain_name(), Some(1000)).await
+        self.json_operation(prompt, Some(0.0), self.domain_name(), Some(1000)).await
     }
     
     /// Generate documentation for code
@@ -75,12 +89,15 @@ impl CodeAssistanceClient {
         let language = self.validate_string(language, "language")?;
         let style = doc_style.unwrap_or_else(|| "standard".to_string());
         
-        let prompt = format!(
-            "Generate {} documentation for this {} code. The documentation should explain the purpose, parameters, return values, and provide examples where appropriate.\n\nCode:\n```{}\n{}\n```",
-            style, language, language, code
+        let prompt = DomainPrompt::new(
+            format!(
+                "Generate {} documentation for this {} code. The documentation should explain the purpose, parameters, return values, and provide examples where appropriate.\n\n",
+                style, language
+            ),
+            format!("Code:\n```{}\n{}\n```", language, code)
         );
         
-        self.text_operation(&prompt, None, self.domain_name(), Some(1500)).await
+        self.text_operation(prompt, None, self.domain_name(), Some(1500)).await
     }
     
     /// Refactor code
@@ -94,12 +111,15 @@ impl CodeAssistanceClient {
         let language = self.validate_string(language, "language")?;
         let goal = self.validate_string(goal, "goal")?;
         
-        let prompt = format!(
-            "Refactor this {} code to {}. Provide the refactored code with explanations of the changes made.\n\nOriginal Code:\n```{}\n{}\n```",
-            language, goal, language, code
+        let prompt = DomainPrompt::new(
+            format!(
+                "Refactor this {} code to {}. Provide the refactored code with explanations of the changes made.\n\n",
+                language, goal
+            ),
+            format!("Original Code:\n```{}\n{}\n```", language, code)
         );
         
-        self.text_operation(&prompt, None, self.domain_name(), Some(1500)).await
+        self.text_operation(prompt, None, self.domain_name(), Some(1500)).await
     }
 }
 
@@ -115,4 +135,8 @@ impl DomainOperations for CodeAssistanceClient {
     fn claude(&self) -> &Claude {
         self.base.claude()
     }
+    
+    fn cancellation_token(&self) -> Option<&CancellationToken> {
+        self.base.cancellation_token()
+    }
 }
\ No newline at end of file
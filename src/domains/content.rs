@@ -5,6 +5,7 @@ use crate::types::*;
 use crate::domains::{DomainClient, DomainOperations, ValidationOperations, base::BaseDomainClient};
 use std::collections::HashMap;
 use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 use regex::Regex;
 
 // Content Generation Client
@@ -138,6 +139,16 @@ impl ContentGenerationClient {
         }
     }
     
+    /// Get a copy of this client whose operations stop when `token` is cancelled
+    ///
+    /// Unlike `Claude::with_cancellation_token`, this keeps the client's domain
+    /// registry, so one token can be used per operation.
+    pub fn with_cancellation(&self, token: CancellationToken) -> Self {
+        Self {
+            base: self.base.with_cancellation(token),
+        }
+    }
+    
     /// Generate text content using a template
     ///
     /// The template must have all parameters filled before rendering.
@@ -254,4 +265,8 @@ impl DomainOperations for ContentGenerationClient {
     fn claude(&self) -> &Claude {
         self.base.claude()
     }
+    
+    fn cancellation_token(&self) -> Option<&CancellationToken> {
+        self.base.cancellation_token()
+    }
 }
\ No newline at end of file
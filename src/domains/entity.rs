@@ -2,10 +2,12 @@
 
 use crate::client::Claude;
 use crate::types::*;
-use crate::domains::{DomainClient, DomainOperations, ValidationOperations, base::BaseDomainClient};
+use crate::batches::{BatchRequest, BatchResult};
+use crate::domains::{DomainClient, DomainOperations, DomainPrompt, ValidationOperations, base::BaseDomainClient};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 
 // Entity Extraction Client
 pub struct EntityExtractionClient {
@@ -46,20 +48,55 @@ impl EntityExtractionClient {
         }
     }
     
+    /// Get a copy of this client whose operations stop when `token` is cancelled
+    ///
+    /// Unlike `Claude::with_cancellation_token`, this keeps the client's domain
+    /// registry, so one token can be used per operation.
+    pub fn with_cancellation(&self, token: CancellationToken) -> Self {
+        Self {
+            base: self.base.with_cancellation(token),
+        }
+    }
+    
     /// Extract entities from text
     pub async fn extract_from_text<T: Into<String>>(&self, text: T) -> ClaudeResult<Vec<Entity>> {
-        let text = self.validate_string(text, "text")?;
-        
-        let prompt = format!(
-            "Extract named entities from the following text. Provide your response as a JSON array of entity objects, each with 'text', 'entity_type', 'start_idx' (if applicable), 'end_idx' (if applicable), and 'confidence' (between 0 and 1).\n\nEntity types to identify: Person, Organization, Location, Date, Time, Money, Percent, Product, Event, WorkOfArt, Law, Language.\n\nText: {}\n\nRespond with valid JSON only.",
-            text
-        );
-        
-        self.json_operation(&prompt, Some(0.0), self.domain_name(), Some(1000)).await
+        let prompt = self.extract_prompt(text)?;
+        self.json_operation(prompt, Some(0.0), self.domain_name(), Some(1000)).await
     }
     
     /// Extract specific entity types
     pub async fn with_types<T: Into<String>>(&self, text: T, types: Vec<EntityType>) -> ClaudeResult<Vec<Entity>> {
+        let prompt = self.types_prompt(text, types)?;
+        self.json_operation(prompt, Some(0.0), self.domain_name(), Some(1000)).await
+    }
+    
+    /// Build a batch request that extracts entities from text, like `extract_from_text`
+    pub async fn batch_request<T: Into<String>>(&self, custom_id: impl Into<String>, text: T) -> ClaudeResult<BatchRequest> {
+        let prompt = self.extract_prompt(text)?;
+        self.json_batch_request(custom_id, prompt, Some(0.0), Some(1000)).await
+    }
+    
+    /// Build a batch request that extracts specific entity types, like `with_types`
+    pub async fn batch_request_with_types<T: Into<String>>(&self, custom_id: impl Into<String>, text: T, types: Vec<EntityType>) -> ClaudeResult<BatchRequest> {
+        let prompt = self.types_prompt(text, types)?;
+        self.json_batch_request(custom_id, prompt, Some(0.0), Some(1000)).await
+    }
+    
+    /// Parse the result of an entity extraction batch request
+    pub async fn parse_batch_result(&self, result: &BatchResult) -> ClaudeResult<Vec<Entity>> {
+        self.parse_json_batch_result(result, self.domain_name()).await
+    }
+    
+    fn extract_prompt<T: Into<String>>(&self, text: T) -> ClaudeResult<DomainPrompt> {
+        let text = self.validate_string(text, "text")?;
+        
+        Ok(DomainPrompt::new(
+            "Extract named entities from the following text. Provide your response as a JSON array of entity objects, each with 'text', 'entity_type', 'start_idx' (if applicable), 'end_idx' (if applicable), and 'confidence' (between 0 and 1).\n\nEntity types to identify: Person, Organization, Location, Date, Time, Money, Percent, Product, Event, WorkOfArt, Law, Language.\n\n",
+            format!("Text: {}\n\nRespond with valid JSON only.", text)
+        ))
+    }
+    
+    fn types_prompt<T: Into<String>>(&self, text: T, types: Vec<EntityType>) -> ClaudeResult<DomainPrompt> {
         let text = self.validate_string(text, "text")?;
         let types = self.validate_not_empty(types, "entity types")?;
         
@@ -82,12 +119,13 @@ impl EntityExtractionClient {
         
         let types_str = type_strings.join("\", \"");
         
-        let prompt = format!(
-            "Extract only these entity types [\"{}\"] from the following text. Provide your response as a JSON array of entity objects, each with 'text', 'entity_type', 'start_idx' (if applicable), 'end_idx' (if applicable), and 'confidence' (between 0 and 1).\n\nText: {}\n\nRespond with valid JSON only.",
-            types_str, text
-        );
-        
-        self.json_operation(&prompt, Some(0.0), self.domain_name(), Some(1000)).await
+        Ok(DomainPrompt::new(
+            format!(
+                "Extract only these entity types [\"{}\"] from the following text. Provide your response as a JSON array of entity objects, each with 'text', 'entity_type', 'start_idx' (if applicable), 'end_idx' (if applicable), and 'confidence' (between 0 and 1).\n\n",
+                types_str
+            ),
+            format!("Text: {}\n\nRespond with valid JSON only.", text)
+        ))
     }
     
     /// Filter returned entities by type
@@ -108,4 +146,8 @@ impl DomainOperations for EntityExtractionClient {
     fn claude(&self) -> &Claude {
         self.base.claude()
     }
+    
+    fn cancellation_token(&self) -> Option<&CancellationToken> {
+        self.base.cancellation_token()
+    }
 }
\ No newline at end of file
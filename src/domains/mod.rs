@@ -9,8 +9,15 @@
 //! The domain client system uses a trait-based approach:
 //!
 //! - `DomainClient` trait: Defines common validation and error handling methods
-//! - `DomainOperations` trait: Defines common operations like JSON extraction
+//! - `DomainOperations` trait: Defines common operations like JSON extraction and
+//!   forced tool calls for structured output
 //! - `BaseDomainClient`: Implements both traits and serves as a composition base
+//! - `DomainPrompt`: Splits a prompt into fixed instructions and per-call input,
+//!   so the instructions can be cached
+//!
+//! Operations can also be run through the Message Batches API: clients build
+//! `BatchRequest`s from the same prompts they send directly, and parse the
+//! batch results with the same extraction logic.
 //!
 //! Domain clients use composition rather than inheritance by containing a
 //! `BaseDomainClient` instance and delegating trait implementations to it.
@@ -22,6 +29,7 @@
 //! use claude_rs::domains::{DomainClient, DomainOperations, base::BaseDomainClient};
 //! use claude_rs::utils::StringValidator;
 //! use std::sync::Arc;
+//! use tokio_util::sync::CancellationToken;
 //!
 //! struct TranslationClient {
 //!     base: BaseDomainClient
@@ -54,6 +62,10 @@
 //!     fn claude(&self) -> &Claude {
 //!         self.base.claude()
 //!     }
+//!
+//!     fn cancellation_token(&self) -> Option<&CancellationToken> {
+//!         self.base.cancellation_token()
+//!     }
 //! }
 //! ```
 
@@ -73,10 +85,70 @@ pub use translation::{TranslationClient, TranslationResult, TranslationAlternati
 
 use std::sync::{Arc, OnceLock};
 use dashmap::DashMap;
-use crate::Claude;
+use crate::{BatchRequest, BatchResult, Claude, MessageBuilder};
+use crate::schema::parse_tool_input;
 use serde::de::DeserializeOwned;
 use crate::types::*;
 use crate::domain_error;
+use tokio_util::sync::CancellationToken;
+
+/// A domain prompt made of fixed instructions followed by per-call input
+///
+/// When the client has domain prompt caching enabled (see
+/// `Claude::with_domain_prompt_caching`), the instructions are sent as their
+/// own content block with a cache breakpoint, so the tools and instructions
+/// are cached across calls. Otherwise the prompt is sent as a single block.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DomainPrompt {
+    /// Instructions shared by every call of an operation
+    pub instructions: String,
+    /// The part of the prompt that changes between calls
+    pub input: String,
+}
+
+impl DomainPrompt {
+    /// Create a prompt from fixed instructions and per-call input
+    pub fn new(instructions: impl Into<String>, input: impl Into<String>) -> Self {
+        Self {
+            instructions: instructions.into(),
+            input: input.into(),
+        }
+    }
+    
+    /// Get the full prompt text
+    pub fn text(&self) -> String {
+        format!("{}{}", self.instructions, self.input)
+    }
+    
+    /// Build the user message content, placing `cache_control` after the instructions
+    pub fn to_content(&self, cache_control: Option<&CacheControl>) -> Vec<Content> {
+        match cache_control {
+            Some(cache_control) if !self.instructions.is_empty() => vec![
+                Content::text(self.instructions.clone()).with_cache_control(cache_control.clone()),
+                Content::text(self.input.clone()),
+            ],
+            _ => vec![Content::text(self.text())],
+        }
+    }
+}
+
+impl From<String> for DomainPrompt {
+    fn from(input: String) -> Self {
+        Self::new(String::new(), input)
+    }
+}
+
+impl From<&String> for DomainPrompt {
+    fn from(input: &String) -> Self {
+        Self::new(String::new(), input.clone())
+    }
+}
+
+impl From<&str> for DomainPrompt {
+    fn from(input: &str) -> Self {
+        Self::new(String::new(), input)
+    }
+}
 
 /// Common trait for all domain clients
 /// 
@@ -154,30 +226,83 @@ pub trait DomainOperations: DomainClient {
     /// Get a reference to the Claude client
     fn claude(&self) -> &Claude;
     
+    /// Maximum number of times an answer cut off at `max_tokens` is continued
+    ///
+    /// Defaults to the client's `with_domain_continuation` setting. Override it
+    /// to opt a domain client in or out of continuation.
+    fn max_continuations(&self) -> u32 {
+        self.claude().domain_max_continuations
+    }
+    
+    /// Token that cancels this client's operations, if any
+    ///
+    /// Overrides the client's token from `Claude::with_cancellation_token`.
+    /// Defaults to none; clients built on `BaseDomainClient` delegate to it.
+    fn cancellation_token(&self) -> Option<&CancellationToken> {
+        None
+    }
+    
+    /// Create a message builder for a single-prompt domain request
+    fn prompt_builder(&self, prompt: impl Into<DomainPrompt>, temperature: Option<f32>, max_tokens: Option<u32>) -> ClaudeResult<MessageBuilder> {
+        let prompt = prompt.into();
+        let mut builder = match &self.claude().domain_cache_control {
+            Some(cache_control) => self.claude().message().add_message(Message {
+                role: Role::User,
+                content: prompt.to_content(Some(cache_control)),
+            }),
+            None => self.claude().message().user_message(prompt.text())?,
+        };
+        
+        if let Some(temp) = temperature {
+            builder = builder.temperature(temp)?;
+        }
+        
+        // Use max_tokens with this priority:
+        // 1. Method parameter (if provided)
+        // 2. Client default_max_tokens (if set), which the builder caps at the model's limit
+        // 3. Fallback to 1000 as default value
+        if let Some(tokens) = max_tokens {
+            builder = builder.max_tokens(tokens)?;
+        } else if self.claude().default_max_tokens.is_none() {
+            // Fallback default
+            builder = builder.max_tokens(1000)?;
+        }
+        
+        if let Some(token) = self.cancellation_token() {
+            builder = builder.cancellation_token(token.clone());
+        }
+        
+        Ok(builder.auto_continue(self.max_continuations()))
+    }
+    
     /// Execute a prompt and return the raw response
-    fn execute_prompt<'a>(&'a self, prompt: &'a str, temperature: Option<f32>, max_tokens: Option<u32>) -> JsonFuture<'a, MessageResponse> {
+    fn execute_prompt<'a>(&'a self, prompt: impl Into<DomainPrompt>, temperature: Option<f32>, max_tokens: Option<u32>) -> JsonFuture<'a, MessageResponse> {
+        let prompt = prompt.into();
         Box::pin(async move {
-            let mut builder = self.claude().message().user_message(prompt)?;
-            
-            if let Some(temp) = temperature {
-                builder = builder.temperature(temp)?;
-            }
-            
-            // Use max_tokens with this priority:
-            // 1. Method parameter (if provided)
-            // 2. Client default_max_tokens (if set)
-            // 3. Fallback to 1000 as default value
-            if let Some(tokens) = max_tokens.or(self.claude().default_max_tokens) {
-                builder = builder.max_tokens(tokens)?;
-            } else {
-                // Fallback default
-                builder = builder.max_tokens(1000)?;
-            }
-            
-            builder.send().await
+            let response = self.prompt_builder(prompt, temperature, max_tokens)?.send().await?;
+            self.warn_if_truncated(&response);
+            Ok(response)
         })
     }
     
+    /// Log a warning when a response was cut off at `max_tokens`
+    fn warn_if_truncated(&self, response: &MessageResponse) {
+        if !response.is_truncated() {
+            return;
+        }
+        match self.max_continuations() {
+            0 => log::warn!(
+                "{} response was cut off at max_tokens; use Claude::with_domain_continuation to continue it",
+                self.domain_name()
+            ),
+            max => log::warn!(
+                "{} response was still cut off at max_tokens after up to {} continuations",
+                self.domain_name(),
+                max
+            ),
+        }
+    }
+    
     /// Extract and parse JSON from a response
     fn extract_json<'a, T: DeserializeOwned>(&'a self, response: &'a MessageResponse, domain_name: &str) 
         -> JsonFuture<'a, T> {
@@ -198,9 +323,33 @@ pub trait DomainOperations: DomainClient {
         })
     }
     
+    /// Extract the input of a forced tool call and parse it into the result type
+    ///
+    /// Falls back to JSON in the text content when the response has no call to
+    /// `tool_name`, for example when a mock answers in plain text.
+    fn extract_tool_input<'a, T: DeserializeOwned>(&'a self, response: &'a MessageResponse, tool_name: &'a str, domain_name: &str)
+        -> JsonFuture<'a, T> {
+        let domain_name = domain_name.to_string(); // Clone for async move block
+        Box::pin(async move {
+            let input = response.content.iter().find_map(|block| match block {
+                Content::ToolUse { name, input, .. } if name == tool_name => Some(input),
+                _ => None,
+            });
+            
+            match input {
+                Some(input) => parse_tool_input(input).map_err(|e| domain_error!(
+                    domain_name.clone(),
+                    format!("Failed to parse tool input: {}", e),
+                    input.to_string()
+                )),
+                None => self.extract_json(response, &domain_name).await,
+            }
+        })
+    }
+    
     /// Extract text from a response
     fn extract_text(&self, response: &MessageResponse, domain_name: &str) -> ClaudeResult<String> {
-        if let Some(Content::Text { text }) = response.content.first() {
+        if let Some(Content::Text { text, .. }) = response.content.first() {
             Ok(text.clone())
         } else {
             Err(domain_error!(
@@ -213,33 +362,120 @@ pub trait DomainOperations: DomainClient {
     /// Execute a JSON domain operation
     fn json_operation<'a, T: DeserializeOwned>(
         &'a self,
-        prompt: &'a str, 
+        prompt: impl Into<DomainPrompt>, 
         temperature: Option<f32>,
         domain_name: &str,
         max_tokens: Option<u32>
     ) -> JsonFuture<'a, T> {
-        let prompt = prompt.to_string(); // Clone for async move block
+        let prompt = prompt.into(); // Convert before the async move block
         let domain_name = domain_name.to_string(); // Clone for async move block
         Box::pin(async move {
             // Use the updated execute_prompt with max_tokens
-            let response = self.execute_prompt(&prompt, temperature, max_tokens).await?;
+            let response = self.execute_prompt(prompt, temperature, max_tokens).await?;
             self.extract_json(&response, &domain_name).await
         })
     }
     
+    /// Execute a structured domain operation by forcing a call to `tool`
+    ///
+    /// The tool's input schema describes the result type, so the model's answer
+    /// arrives as validated tool input rather than free-form JSON in text.
+    fn tool_operation<'a, T: DeserializeOwned>(
+        &'a self,
+        prompt: impl Into<DomainPrompt>,
+        tool: Tool,
+        temperature: Option<f32>,
+        domain_name: &str,
+        max_tokens: Option<u32>
+    ) -> JsonFuture<'a, T> {
+        let prompt = prompt.into(); // Convert before the async move block
+        let domain_name = domain_name.to_string(); // Clone for async move block
+        Box::pin(async move {
+            let tool_name = tool.name.clone();
+            let response = self.tool_builder(prompt, tool, temperature, max_tokens)?
+                .send()
+                .await?;
+            self.warn_if_truncated(&response);
+            self.extract_tool_input(&response, &tool_name, &domain_name).await
+        })
+    }
+    
+    /// Create a message builder that forces a call to `tool`
+    fn tool_builder(&self, prompt: impl Into<DomainPrompt>, tool: Tool, temperature: Option<f32>, max_tokens: Option<u32>) -> ClaudeResult<MessageBuilder> {
+        let tool_name = tool.name.clone();
+        Ok(self.prompt_builder(prompt, temperature, max_tokens)?
+            .add_tool(tool)
+            .tool_choice(ToolChoice::tool(tool_name)))
+    }
+    
+    /// Build a Message Batches request for a JSON domain operation
+    fn json_batch_request<'a>(
+        &'a self,
+        custom_id: impl Into<String>,
+        prompt: impl Into<DomainPrompt>,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>
+    ) -> JsonFuture<'a, BatchRequest> {
+        let custom_id = custom_id.into();
+        let prompt = prompt.into();
+        Box::pin(async move {
+            let params = self.prompt_builder(prompt, temperature, max_tokens)?.build_request().await?;
+            Ok(BatchRequest::new(custom_id, params))
+        })
+    }
+    
+    /// Build a Message Batches request for a structured domain operation
+    fn tool_batch_request<'a>(
+        &'a self,
+        custom_id: impl Into<String>,
+        prompt: impl Into<DomainPrompt>,
+        tool: Tool,
+        temperature: Option<f32>,
+        max_tokens: Option<u32>
+    ) -> JsonFuture<'a, BatchRequest> {
+        let custom_id = custom_id.into();
+        let prompt = prompt.into();
+        Box::pin(async move {
+            let params = self.tool_builder(prompt, tool, temperature, max_tokens)?.build_request().await?;
+            Ok(BatchRequest::new(custom_id, params))
+        })
+    }
+    
+    /// Parse the result of a request built with `json_batch_request`
+    ///
+    /// Requests that errored, expired or were canceled return their error.
+    fn parse_json_batch_result<'a, T: DeserializeOwned>(&'a self, result: &'a BatchResult, domain_name: &str)
+        -> JsonFuture<'a, T> {
+        let domain_name = domain_name.to_string(); // Clone for async move block
+        Box::pin(async move {
+            self.extract_json(result.message()?, &domain_name).await
+        })
+    }
+    
+    /// Parse the result of a request built with `tool_batch_request`
+    ///
+    /// Requests that errored, expired or were canceled return their error.
+    fn parse_tool_batch_result<'a, T: DeserializeOwned>(&'a self, result: &'a BatchResult, tool_name: &'a str, domain_name: &str)
+        -> JsonFuture<'a, T> {
+        let domain_name = domain_name.to_string(); // Clone for async move block
+        Box::pin(async move {
+            self.extract_tool_input(result.message()?, tool_name, &domain_name).await
+        })
+    }
+    
     /// Execute a text domain operation
     fn text_operation<'a>(
         &'a self,
-        prompt: &'a str, 
+        prompt: impl Into<DomainPrompt>, 
         temperature: Option<f32>,
         domain_name: &str,
         max_tokens: Option<u32>
     ) -> TextFuture<'a> {
-        let prompt = prompt.to_string(); // Clone for async move block
+        let prompt = prompt.into(); // Convert before the async move block
         let domain_name = domain_name.to_string(); // Clone for async move block
         Box::pin(async move {
             // Use the updated execute_prompt with max_tokens
-            let response = self.execute_prompt(&prompt, temperature, max_tokens).await?;
+            let response = self.execute_prompt(prompt, temperature, max_tokens).await?;
             self.extract_text(&response, &domain_name)
         })
     }
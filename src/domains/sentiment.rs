@@ -2,10 +2,14 @@
 
 use crate::client::Claude;
 use crate::types::*;
-use crate::domains::{DomainClient, DomainOperations, ValidationOperations, base::BaseDomainClient};
+use crate::batches::{BatchRequest, BatchResult};
+use crate::domains::{DomainClient, DomainOperations, DomainPrompt, ValidationOperations, base::BaseDomainClient};
+use crate::schema::JsonSchema;
 use serde::Deserialize;
+use serde_json::json;
 use std::collections::HashMap;
 use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 
 // Sentiment Analysis Client
 pub struct SentimentAnalysisClient {
@@ -34,6 +38,56 @@ pub struct AspectSentiment {
     pub highlights: Vec<String>,
 }
 
+impl JsonSchema for Sentiment {
+    fn json_schema() -> serde_json::Value {
+        json!({"type": "string", "enum": ["Positive", "Neutral", "Negative"]})
+    }
+}
+
+impl JsonSchema for AspectSentiment {
+    fn json_schema() -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "score": {"type": "number", "minimum": -1.0, "maximum": 1.0},
+                "sentiment": Sentiment::json_schema(),
+                "highlights": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "description": "Key phrases from the text that support this sentiment"
+                }
+            },
+            "required": ["score", "sentiment", "highlights"]
+        })
+    }
+}
+
+impl JsonSchema for SentimentResult {
+    fn json_schema() -> serde_json::Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "score": {
+                    "type": "number",
+                    "minimum": -1.0,
+                    "maximum": 1.0,
+                    "description": "Overall sentiment from -1.0 (very negative) to 1.0 (very positive)"
+                },
+                "sentiment": Sentiment::json_schema(),
+                "aspects": {
+                    "type": "object",
+                    "additionalProperties": AspectSentiment::json_schema(),
+                    "description": "Sentiment for each requested aspect, keyed by aspect name"
+                }
+            },
+            "required": ["score", "sentiment", "aspects"]
+        })
+    }
+}
+
+/// Name of the tool the model is forced to call to report its analysis
+const SENTIMENT_TOOL: &str = "report_sentiment";
+
 impl SentimentAnalysisClient {
     pub(crate) fn new(claude: Arc<Claude>) -> Self {
         Self { 
@@ -41,32 +95,73 @@ impl SentimentAnalysisClient {
         }
     }
     
+    /// Get a copy of this client whose operations stop when `token` is cancelled
+    ///
+    /// Unlike `Claude::with_cancellation_token`, this keeps the client's domain
+    /// registry, so one token can be used per operation.
+    pub fn with_cancellation(&self, token: CancellationToken) -> Self {
+        Self {
+            base: self.base.with_cancellation(token),
+        }
+    }
+    
     /// Analyze sentiment of text with domain-specific prompt
     pub async fn analyze_text(&self, text: impl Into<String>) -> ClaudeResult<SentimentResult> {
+        let prompt = self.analyze_prompt(text)?;
+        self.tool_operation(prompt, Self::sentiment_tool(), Some(0.0), self.domain_name(), Some(1000)).await
+    }
+    
+    /// Analyze sentiment with aspects
+    pub async fn with_aspects(&self, text: impl Into<String>, aspects: Vec<&str>) -> ClaudeResult<SentimentResult> {
+        let prompt = self.aspects_prompt(text, aspects)?;
+        self.tool_operation(prompt, Self::sentiment_tool(), Some(0.0), self.domain_name(), Some(1000)).await
+    }
+    
+    /// Build a batch request that analyzes the sentiment of text, like `analyze_text`
+    pub async fn batch_request(&self, custom_id: impl Into<String>, text: impl Into<String>) -> ClaudeResult<BatchRequest> {
+        let prompt = self.analyze_prompt(text)?;
+        self.tool_batch_request(custom_id, prompt, Self::sentiment_tool(), Some(0.0), Some(1000)).await
+    }
+    
+    /// Build a batch request that analyzes sentiment with aspects, like `with_aspects`
+    pub async fn batch_request_with_aspects(&self, custom_id: impl Into<String>, text: impl Into<String>, aspects: Vec<&str>) -> ClaudeResult<BatchRequest> {
+        let prompt = self.aspects_prompt(text, aspects)?;
+        self.tool_batch_request(custom_id, prompt, Self::sentiment_tool(), Some(0.0), Some(1000)).await
+    }
+    
+    /// Parse the result of a sentiment batch request
+    pub async fn parse_batch_result(&self, result: &BatchResult) -> ClaudeResult<SentimentResult> {
+        self.parse_tool_batch_result(result, SENTIMENT_TOOL, self.domain_name()).await
+    }
+    
+    fn analyze_prompt(&self, text: impl Into<String>) -> ClaudeResult<DomainPrompt> {
         let text = self.validate_string(text, "text")?;
         
         // Create a prompt that will return JSON
-        let prompt = format!(
-            "Analyze the sentiment of the following text. Provide a JSON response with an overall sentiment score from -1.0 (very negative) to 1.0 (very positive), a sentiment category (Positive, Neutral, or Negative), and no other information.\n\nText: {}\n\nRespond with valid JSON only.", 
-            text
-        );
-        
-        self.json_operation(&prompt, Some(0.0), self.domain_name(), Some(1000)).await
+        Ok(DomainPrompt::new(
+            "Analyze the sentiment of the following text. Provide a JSON response with an overall sentiment score from -1.0 (very negative) to 1.0 (very positive), a sentiment category (Positive, Neutral, or Negative), an empty 'aspects' object, and no other information.\n\n",
+            format!("Text: {}\n\nRespond with valid JSON only.", text)
+        ))
     }
     
-    /// Analyze sentiment with aspects
-    pub async fn with_aspects(&self, text: impl Into<String>, aspects: Vec<&str>) -> ClaudeResult<SentimentResult> {
+    fn aspects_prompt(&self, text: impl Into<String>, aspects: Vec<&str>) -> ClaudeResult<DomainPrompt> {
         let text = self.validate_string(text, "text")?;
         let aspects = self.validate_not_empty(aspects, "aspects")?;
         
         let aspects_str = aspects.join("\", \"");
         
-        let prompt = format!(
-            "Analyze the sentiment of the following text, focusing on these aspects: [\"{}\"].\n\nProvide a JSON response with an overall sentiment score from -1.0 to 1.0, a sentiment category (Positive, Neutral, or Negative), and an 'aspects' object with each aspect containing its own score, sentiment category, and key highlights.\n\nText: {}\n\nRespond with valid JSON only.",
-            aspects_str, text
-        );
-        
-        self.json_operation(&prompt, Some(0.0), self.domain_name(), Some(1000)).await
+        Ok(DomainPrompt::new(
+            format!(
+                "Analyze the sentiment of the following text, focusing on these aspects: [\"{}\"].\n\nProvide a JSON response with an overall sentiment score from -1.0 to 1.0, a sentiment category (Positive, Neutral, or Negative), and an 'aspects' object with each aspect containing its own score, sentiment category, and key highlights.\n\n",
+                aspects_str
+            ),
+            format!("Text: {}\n\nRespond with valid JSON only.", text)
+        ))
+    }
+    
+    /// The tool whose input carries the sentiment analysis result
+    fn sentiment_tool() -> Tool {
+        Tool::from_type::<SentimentResult>(SENTIMENT_TOOL, "Report the result of a sentiment analysis")
     }
 }
 
@@ -82,4 +177,8 @@ impl DomainOperations for SentimentAnalysisClient {
     fn claude(&self) -> &Claude {
         self.base.claude()
     }
+    
+    fn cancellation_token(&self) -> Option<&CancellationToken> {
+        self.base.cancellation_token()
+    }
 }
\ No newline at end of file
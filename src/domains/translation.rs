@@ -1,7 +1,8 @@
 use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 use serde::{Deserialize, Serialize};
 use crate::{Claude, ClaudeResult};
-use crate::domains::{DomainClient, DomainOperations, ValidationOperations};
+use crate::domains::{DomainClient, DomainOperations, DomainPrompt, ValidationOperations};
 use super::base::BaseDomainClient;
 
 /// Result of translation operation
@@ -54,6 +55,16 @@ impl TranslationClient {
         }
     }
     
+    /// Get a copy of this client whose operations stop when `token` is cancelled
+    ///
+    /// Unlike `Claude::with_cancellation_token`, this keeps the client's domain
+    /// registry, so one token can be used per operation.
+    pub fn with_cancellation(&self, token: CancellationToken) -> Self {
+        Self {
+            base: self.base.with_cancellation(token),
+        }
+    }
+    
     /// Translate text to a target language (backward compatibility)
     pub async fn translate(
         &self,
@@ -82,15 +93,17 @@ impl TranslationClient {
             "".to_string()
         };
         
-        let prompt = format!(
-            "Translate the following text {} to {}. Return only the JSON with the translation, no additional text.\n\nText to translate:\n{}\n",
-            source_prompt,
-            target_language,
-            text
+        let prompt = DomainPrompt::new(
+            format!(
+                "Translate the following text {} to {}. Return only the JSON with the translation, no additional text.\n\n",
+                source_prompt,
+                target_language
+            ),
+            format!("Text to translate:\n{}\n", text)
         );
         
         // Use the updated json_operation with max_tokens
-        self.json_operation(&prompt, None, self.domain_name(), max_tokens).await
+        self.json_operation(prompt, None, self.domain_name(), max_tokens).await
     }
     
     /// Detect the language of a text (backward compatibility)
@@ -110,12 +123,12 @@ impl TranslationClient {
     ) -> ClaudeResult<DetectedLanguage> {
         let text = self.validate_string(text, "text")?;
         
-        let prompt = format!(
-            "Analyze the following text and determine what language it is written in. Return only a JSON object with a 'language' field containing the ISO 639-1 code, a 'name' field with the English name of the language, and a 'confidence' score between 0 and 1.\n\nText to analyze:\n{}\n",
-            text
+        let prompt = DomainPrompt::new(
+            "Analyze the following text and determine what language it is written in. Return only a JSON object with a 'language' field containing the ISO 639-1 code, a 'name' field with the English name of the language, and a 'confidence' score between 0 and 1.\n\n",
+            format!("Text to analyze:\n{}\n", text)
         );
         
-        self.json_operation(&prompt, None, self.domain_name(), max_tokens).await
+        self.json_operation(prompt, None, self.domain_name(), max_tokens).await
     }
     
     /// Translate text with multiple alternative translations for key phrases (backward compatibility)
@@ -145,14 +158,16 @@ impl TranslationClient {
             return self.domain_error("num_alternatives must be 5 or fewer");
         }
         
-        let prompt = format!(
-            "Translate the following text to {}. Also identify {} important phrases and provide alternative translations for them. Return only the JSON with the result.\n\nText to translate:\n{}\n",
-            target_language,
-            num_alternatives,
-            text
+        let prompt = DomainPrompt::new(
+            format!(
+                "Translate the following text to {}. Also identify {} important phrases and provide alternative translations for them. Return only the JSON with the result.\n\n",
+                target_language,
+                num_alternatives
+            ),
+            format!("Text to translate:\n{}\n", text)
         );
         
-        self.json_operation(&prompt, None, self.domain_name(), max_tokens).await
+        self.json_operation(prompt, None, self.domain_name(), max_tokens).await
     }
 }
 
@@ -168,6 +183,10 @@ impl DomainOperations for TranslationClient {
     fn claude(&self) -> &Claude {
         self.base.claude()
     }
+    
+    fn cancellation_token(&self) -> Option<&CancellationToken> {
+        self.base.cancellation_token()
+    }
 }
 
 #[cfg(test)]
@@ -177,6 +196,6 @@ mod tests {
     // For now, let's just add a placeholder test
     #[test]
     fn it_works() {
-        assert!(true);
+        // Placeholder: translation behaviour is covered in tests/translation_tests.rs
     }
 }
\ No newline at end of file
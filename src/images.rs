@@ -0,0 +1,392 @@
+// Image input helpers
+
+use crate::types::*;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use std::path::Path;
+
+/// Largest base64 encoded image the API accepts, in bytes
+pub const MAX_IMAGE_BYTES: usize = 5 * 1024 * 1024;
+
+/// Largest width or height the API accepts, in pixels
+pub const MAX_IMAGE_DIMENSION: u32 = 8000;
+
+/// Largest width or height accepted when a request has more than `MANY_IMAGES_THRESHOLD` images
+pub const MAX_IMAGE_DIMENSION_MANY: u32 = 2000;
+
+/// Number of images above which `MAX_IMAGE_DIMENSION_MANY` applies
+pub const MANY_IMAGES_THRESHOLD: usize = 20;
+
+/// Most images a single request can contain
+pub const MAX_IMAGES_PER_REQUEST: usize = 100;
+
+/// Longest edge the API keeps before scaling an image down, in pixels
+pub const OPTIMAL_IMAGE_LONG_EDGE: u32 = 1568;
+
+/// Most tokens a single image costs; larger images are scaled down by the API
+pub const MAX_IMAGE_TOKENS: u32 = 1600;
+
+/// Number of pixels that cost one token
+const PIXELS_PER_TOKEN: f64 = 750.0;
+
+/// Length of the base64 prefix decoded first, which holds every header but JPEG's
+const SHORT_HEADER_CHARS: usize = 64;
+
+/// Length of the longest base64 prefix decoded to find an image's dimensions
+const HEADER_PREFIX_CHARS: usize = 64 * 1024;
+
+/// Image formats the API accepts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ImageMediaType {
+    Jpeg,
+    Png,
+    Gif,
+    Webp,
+}
+
+impl ImageMediaType {
+    /// Get the MIME type sent to the API
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ImageMediaType::Jpeg => "image/jpeg",
+            ImageMediaType::Png => "image/png",
+            ImageMediaType::Gif => "image/gif",
+            ImageMediaType::Webp => "image/webp",
+        }
+    }
+
+    /// Detect the format of image data from its magic bytes
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        match data {
+            [0xFF, 0xD8, 0xFF, ..] => Some(ImageMediaType::Jpeg),
+            [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, ..] => Some(ImageMediaType::Png),
+            [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Some(ImageMediaType::Gif),
+            [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some(ImageMediaType::Webp),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for ImageMediaType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Image data ready to be sent to the API
+///
+/// The media type is detected from the data and the dimensions are read from
+/// the image header, so an unsupported or corrupt file is rejected before it
+/// is uploaded. Converting the image into content checks the API's size and
+/// dimension limits.
+///
+/// ## Example
+///
+/// ```no_run
+/// # use claude_rs::{Claude, Image, Message, Role, Content};
+/// # async fn example(claude: Claude) -> Result<(), claude_rs::ClaudeError> {
+/// let image = Image::from_file("chart.png").await?;
+/// println!("{}x{}, about {} tokens", image.width(), image.height(), image.estimated_tokens());
+///
+/// let response = claude.message()
+///     .add_message(Message {
+///         role: Role::User,
+///         content: vec![image.into_content()?, Content::text("What does this chart show?")],
+///     })
+///     .send()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Image {
+    media_type: ImageMediaType,
+    data: Vec<u8>,
+    width: u32,
+    height: u32,
+}
+
+impl Image {
+    /// Load an image from its encoded bytes
+    ///
+    /// Fails if the data is not a JPEG, PNG, GIF or WebP image, or its
+    /// dimensions cannot be read.
+    pub fn from_bytes(data: impl Into<Vec<u8>>) -> ClaudeResult<Self> {
+        let data = data.into();
+        let media_type = ImageMediaType::detect(&data).ok_or_else(|| ClaudeError::InvalidParameter(
+            "Unsupported image format: expected JPEG, PNG, GIF or WebP data".into()
+        ))?;
+        let (width, height) = image_dimensions(&data).ok_or_else(|| ClaudeError::InvalidParameter(
+            format!("Could not read the dimensions of the {} data", media_type)
+        ))?;
+        Ok(Self { media_type, data, width, height })
+    }
+
+    /// Load an image from a file
+    pub async fn from_file(path: impl AsRef<Path>) -> ClaudeResult<Self> {
+        let path = path.as_ref();
+        let data = tokio::fs::read(path).await.map_err(|e| ClaudeError::InvalidParameter(
+            format!("Failed to read image {}: {}", path.display(), e)
+        ))?;
+        Self::from_bytes(data)
+    }
+
+    /// Get the detected media type
+    pub fn media_type(&self) -> ImageMediaType {
+        self.media_type
+    }
+
+    /// Get the width in pixels
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Get the height in pixels
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Get the encoded image data
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Get the size of the image once base64 encoded, which the size limit applies to
+    pub fn encoded_len(&self) -> usize {
+        self.data.len().div_ceil(3) * 4
+    }
+
+    /// Estimate the input tokens this image costs
+    pub fn estimated_tokens(&self) -> u32 {
+        estimate_image_tokens(self.width, self.height)
+    }
+
+    /// Check the image against the API's size and dimension limits
+    ///
+    /// The stricter dimension limit for requests with many images is checked
+    /// when the request is sent.
+    pub fn validate(&self) -> ClaudeResult<()> {
+        if self.encoded_len() > MAX_IMAGE_BYTES {
+            return Err(ClaudeError::ValidationError(format!(
+                "Image is {} bytes once encoded, more than the maximum of {} bytes",
+                self.encoded_len(), MAX_IMAGE_BYTES
+            )));
+        }
+        check_dimensions(self.width, self.height, MAX_IMAGE_DIMENSION)
+    }
+
+    /// Validate the image and encode it as a base64 image source
+    pub fn into_source(self) -> ClaudeResult<ImageSource> {
+        self.validate()?;
+        Ok(ImageSource::base64(self.media_type.as_str(), BASE64.encode(&self.data)))
+    }
+
+    /// Validate the image and turn it into an image content block
+    pub fn into_content(self) -> ClaudeResult<Content> {
+        Ok(Content::image(self.into_source()?))
+    }
+
+    /// Scale the image down so its longest edge is at most `max_long_edge` pixels
+    ///
+    /// Images that already fit are returned unchanged. JPEG images are
+    /// re-encoded as JPEG, and other formats as PNG.
+    #[cfg(feature = "image")]
+    pub fn downscale(self, max_long_edge: u32) -> ClaudeResult<Self> {
+        if self.width.max(self.height) <= max_long_edge {
+            return Ok(self);
+        }
+
+        let decoded = image::load_from_memory(&self.data).map_err(|e| ClaudeError::InvalidParameter(
+            format!("Failed to decode {} data: {}", self.media_type, e)
+        ))?;
+        let resized = decoded.resize(max_long_edge, max_long_edge, image::imageops::FilterType::Lanczos3);
+
+        let (format, media_type) = match self.media_type {
+            ImageMediaType::Jpeg => (image::ImageOutputFormat::Jpeg(85), ImageMediaType::Jpeg),
+            _ => (image::ImageOutputFormat::Png, ImageMediaType::Png),
+        };
+        let mut data = std::io::Cursor::new(Vec::new());
+        resized.write_to(&mut data, format).map_err(|e| ClaudeError::InvalidParameter(
+            format!("Failed to encode the resized image: {}", e)
+        ))?;
+
+        Ok(Self {
+            media_type,
+            data: data.into_inner(),
+            width: resized.width(),
+            height: resized.height(),
+        })
+    }
+
+    /// Scale the image down until it fits the API's limits
+    ///
+    /// Images with a longest edge over `OPTIMAL_IMAGE_LONG_EDGE` are scaled
+    /// to it, since the API would scale them down anyway, and the image is
+    /// halved further while it is over `MAX_IMAGE_BYTES`.
+    #[cfg(feature = "image")]
+    pub fn fit_to_limits(self) -> ClaudeResult<Self> {
+        let mut image = self.downscale(OPTIMAL_IMAGE_LONG_EDGE)?;
+        while image.encoded_len() > MAX_IMAGE_BYTES {
+            let long_edge = image.width.max(image.height) / 2;
+            if long_edge == 0 {
+                break;
+            }
+            image = image.downscale(long_edge)?;
+        }
+        Ok(image)
+    }
+}
+
+impl std::fmt::Debug for Image {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Image")
+            .field("media_type", &self.media_type)
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .field("bytes", &self.data.len())
+            .finish()
+    }
+}
+
+/// Estimate the input tokens of an image with the given dimensions
+///
+/// Follows the API's sizing: an image costs one token per 750 pixels, after
+/// being scaled down to a longest edge of `OPTIMAL_IMAGE_LONG_EDGE` pixels
+/// and at most `MAX_IMAGE_TOKENS` tokens.
+pub fn estimate_image_tokens(width: u32, height: u32) -> u32 {
+    let (width, height) = (width as f64, height as f64);
+    let edge_scale = OPTIMAL_IMAGE_LONG_EDGE as f64 / width.max(height);
+    let area_scale = (MAX_IMAGE_TOKENS as f64 * PIXELS_PER_TOKEN / (width * height)).sqrt();
+    let scale = edge_scale.min(area_scale).min(1.0);
+
+    let tokens = ((width * scale).floor() * (height * scale).floor() / PIXELS_PER_TOKEN).ceil();
+    (tokens as u32).clamp(1, MAX_IMAGE_TOKENS)
+}
+
+/// Estimate the input tokens of an image source
+///
+/// Sources whose dimensions are unknown, such as URLs, count as the most an
+/// image can cost.
+pub(crate) fn estimate_source_tokens(source: &ImageSource) -> u32 {
+    match source_dimensions(source) {
+        Some((width, height)) => estimate_image_tokens(width, height),
+        None => MAX_IMAGE_TOKENS,
+    }
+}
+
+/// Check the images in a request against the API's count, size and dimension limits
+///
+/// Runs on every send, so only image headers are decoded. Images whose
+/// dimensions cannot be read from the header are only checked for size.
+pub(crate) fn validate_request_images(request: &MessageRequest) -> ClaudeResult<()> {
+    let sources: Vec<&ImageSource> = request.messages.iter()
+        .flat_map(|message| &message.content)
+        .flat_map(|block| match block {
+            Content::Image { source, .. } => vec![source],
+            Content::ToolResult { content: Some(ToolResultContent::Blocks(blocks)), .. } => {
+                blocks.iter().filter_map(|block| match block {
+                    ToolResultBlock::Image { source } => Some(source),
+                    _ => None,
+                }).collect()
+            }
+            _ => Vec::new(),
+        })
+        .collect();
+
+    if sources.len() > MAX_IMAGES_PER_REQUEST {
+        return Err(ClaudeError::ValidationError(format!(
+            "A request can have at most {} images, found {}",
+            MAX_IMAGES_PER_REQUEST, sources.len()
+        )));
+    }
+    let max_dimension = if sources.len() > MANY_IMAGES_THRESHOLD {
+        MAX_IMAGE_DIMENSION_MANY
+    } else {
+        MAX_IMAGE_DIMENSION
+    };
+
+    for source in sources {
+        if let ImageSource::Base64 { data, .. } = source {
+            if data.len() > MAX_IMAGE_BYTES {
+                return Err(ClaudeError::ValidationError(format!(
+                    "Image is {} bytes once encoded, more than the maximum of {} bytes",
+                    data.len(), MAX_IMAGE_BYTES
+                )));
+            }
+        }
+        if let Some((width, height)) = source_dimensions(source) {
+            check_dimensions(width, height, max_dimension)?;
+        }
+    }
+    Ok(())
+}
+
+fn check_dimensions(width: u32, height: u32, max_dimension: u32) -> ClaudeResult<()> {
+    if width > max_dimension || height > max_dimension {
+        return Err(ClaudeError::ValidationError(format!(
+            "Image is {}x{} pixels; the maximum is {} pixels per side",
+            width, height, max_dimension
+        )));
+    }
+    Ok(())
+}
+
+/// Read the dimensions of a base64 image source
+///
+/// Only a prefix is decoded, never the whole image: a short one, then up to
+/// `HEADER_PREFIX_CHARS` for JPEG files with metadata before the frame
+/// header. Dimensions that lie beyond it are unknown.
+fn source_dimensions(source: &ImageSource) -> Option<(u32, u32)> {
+    let ImageSource::Base64 { data, .. } = source else {
+        return None;
+    };
+    [SHORT_HEADER_CHARS, HEADER_PREFIX_CHARS].into_iter().find_map(|chars| {
+        let prefix = &data.as_bytes()[..data.len().min(chars)];
+        BASE64.decode(prefix).ok().and_then(|prefix| image_dimensions(&prefix))
+    })
+}
+
+/// Read the width and height of an image from its header
+pub(crate) fn image_dimensions(data: &[u8]) -> Option<(u32, u32)> {
+    let u16_be = |at: usize| data.get(at..at + 2).map(|b| u16::from_be_bytes([b[0], b[1]]) as u32);
+    let u16_le = |at: usize| data.get(at..at + 2).map(|b| u16::from_le_bytes([b[0], b[1]]) as u32);
+    let u24_le = |at: usize| data.get(at..at + 3).map(|b| u32::from_le_bytes([b[0], b[1], b[2], 0]));
+    let u32_be = |at: usize| data.get(at..at + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]));
+
+    match ImageMediaType::detect(data)? {
+        // IHDR is always the first chunk
+        ImageMediaType::Png => Some((u32_be(16)?, u32_be(20)?)),
+        // Logical screen descriptor
+        ImageMediaType::Gif => Some((u16_le(6)?, u16_le(8)?)),
+        ImageMediaType::Webp => match data.get(12..16)? {
+            b"VP8 " => Some((u16_le(26)? & 0x3FFF, u16_le(28)? & 0x3FFF)),
+            b"VP8L" => {
+                let bits = u32::from_le_bytes(data.get(21..25)?.try_into().ok()?);
+                Some(((bits & 0x3FFF) + 1, ((bits >> 14) & 0x3FFF) + 1))
+            }
+            b"VP8X" => Some((u24_le(24)? + 1, u24_le(27)? + 1)),
+            _ => None,
+        },
+        ImageMediaType::Jpeg => {
+            // Walk the marker segments up to the start of frame
+            let mut at = 2;
+            loop {
+                while *data.get(at)? != 0xFF {
+                    at += 1;
+                }
+                while *data.get(at)? == 0xFF {
+                    at += 1;
+                }
+                let marker = *data.get(at)?;
+                at += 1;
+                match marker {
+                    0xC0..=0xCF if !matches!(marker, 0xC4 | 0xC8 | 0xCC) => {
+                        return Some((u16_be(at + 5)?, u16_be(at + 3)?));
+                    }
+                    0x01 | 0xD0..=0xD8 => {}
+                    _ => at += u16_be(at)? as usize,
+                }
+            }
+        }
+    }
+}
@@ -7,12 +7,30 @@
 //! ## Key Features
 //! 
 //! - Full support for Claude API with streaming and function calling
+//! - Streamed tool calls reassembled into complete `tool_use` blocks
+//! - Automatic tool execution loop driven by a registry of async handlers
+//! - Tool input schemas generated from Rust types (`derive` feature)
 //! - Domain-specific clients with tailored functionality
+//! - Prompt caching breakpoints on tools, system prompts and content blocks
+//! - Extended thinking with streamed reasoning kept apart from answer text
+//! - Message Batches for asynchronous, discounted bulk processing
 //! - Context management for optimizing token usage
+//! - Exact token counts from the API's token counting endpoint
+//! - Models API client and a registry of model limits and capabilities
+//! - Cost tracking with per-model prices and spending budgets
+//! - Client-side rate limiting with fair queueing
+//! - Cooperative cancellation of requests, streams and domain operations
+//! - Stall detection for streams with first event and idle timeouts
+//! - Automatic continuation of answers cut off at max_tokens
+//! - Image input from files and bytes with format detection, limit checks and downscaling
+//! - PDF and text document input with typed citations of the passages used
+//! - Response metadata: request ids, rate limit headers and latency
 //! - Middleware support for request/response processing
+//! - Configurable retries with exponential backoff for transient failures
 //! - Optional reactive extensions for advanced streaming capabilities
+//! - Client builder for timeouts, proxies, headers, API version and beta flags
 //! - Secure API key handling with memory zeroing
-//! - TLS security configuration
+//! - Per-client TLS configuration with custom roots, client certificates and pinning
 //! 
 //! ## Basic Usage
 //! 
@@ -31,7 +49,7 @@
 //!         .await?;
 //!         
 //!     // Extract the response text
-//!     if let Some(Content::Text { text }) = response.content.first() {
+//!     if let Some(Content::Text { text, .. }) = response.content.first() {
 //!         println!("{}", text);
 //!     }
 //!     
@@ -41,27 +59,56 @@
 
 pub mod types;
 pub mod client;
+mod client_builder;
+mod tls;
+mod cancel;
+mod stall;
+mod continuation;
+mod images;
 mod builder;
 mod middleware;
 mod context;
+mod retry;
+mod tools;
+mod streaming;
+mod batches;
+mod count_tokens;
+mod models;
+mod cost;
+mod rate_limit;
+mod metadata;
 pub mod domains;
 pub mod utils;
+pub mod schema;
 
 #[cfg(feature = "reactive")]
 pub mod reactive;
 
 // Re-export core components
-pub use client::{Claude, TlsConfig, set_tls_config, MockApiHandler};
-pub use types::{ClaudeError, ClaudeModel, ClaudeResult, Content, Message, MessageStream, Role, SecureApiKey, sanitize_error_message};
+pub use client::{Claude, TlsConfig, PemIdentity, set_tls_config, MockApiHandler};
+pub use client_builder::{ClaudeBuilder, DEFAULT_API_VERSION, DEFAULT_BASE_URL, DEFAULT_REQUEST_TIMEOUT};
+pub use types::{ClaudeError, ApiErrorKind, CacheControl, CacheTtl, Citation, CitationLocation, CitationsConfig, ClaudeModel, ClaudeResult, Content, DocumentSource, ImageSource, Message, MessageStream, Role, SecureApiKey, StreamStall, SystemBlock, SystemPrompt, sanitize_error_message};
 pub use builder::MessageBuilder;
+pub use retry::{RetryPolicy, DEFAULT_RETRY_STATUSES};
+pub use tools::{ToolRegistry, ToolRun, DEFAULT_MAX_TOOL_ITERATIONS};
+pub use streaming::{ContentEvent, ContentStream, StreamAccumulator};
+pub use batches::{BatchClient, BatchOutcome, BatchRequest, BatchRequestCounts, BatchResult, BatchResultStream, BatchStatus, MessageBatch, MAX_BATCH_REQUESTS};
+pub use schema::{JsonSchema, parse_tool_input};
 pub use middleware::{ContextManager, RequestMiddleware, ResponseMiddleware};
 pub use context::{AdaptiveContextManager, ImportanceScorer, SimpleImportanceScorer};
-pub use utils::token_counter::{TokenCounter, Claude3TokenCounter, Claude2TokenCounter, SimpleTokenCounter, get_token_counter};
+pub use count_tokens::{ApiTokenCounter, DEFAULT_TOKEN_CACHE_CAPACITY};
+pub use cost::{Budget, BudgetKind, BudgetScope, CostReport, CostTracker, ModelPricing, PriceTable, Spend, DEFAULT_BATCH_DISCOUNT};
+pub use rate_limit::{RateLimitPermit, RateLimiter, RateLimits};
+pub use tokio_util::sync::CancellationToken;
+pub use images::{estimate_image_tokens, Image, ImageMediaType, MANY_IMAGES_THRESHOLD, MAX_IMAGES_PER_REQUEST, MAX_IMAGE_BYTES, MAX_IMAGE_DIMENSION, MAX_IMAGE_DIMENSION_MANY, MAX_IMAGE_TOKENS, OPTIMAL_IMAGE_LONG_EDGE};
+pub use metadata::{RateLimitInfo, RateLimitWindow, ResponseMetadata, StreamMetadata};
+pub use models::{ApiModel, ModelInfo, ModelPage, ModelRegistry, ModelsClient, DEFAULT_MAX_TOKENS_CAP};
+pub use utils::token_counter::{AsyncTokenCounter, TokenCounter, Claude3TokenCounter, Claude2TokenCounter, SimpleTokenCounter, get_token_counter};
 
 // Re-export domain-specific components
 pub mod prelude {
     //! Convenient imports for commonly used types and functions
-    pub use crate::{Claude, ClaudeError, ClaudeModel, Content, Message, Role, from_env, SecureApiKey, TlsConfig, set_tls_config};
+    pub use crate::{Claude, ClaudeError, ApiErrorKind, CacheControl, ClaudeModel, Content, Message, Role, from_env, SecureApiKey, TlsConfig, set_tls_config, RetryPolicy, ToolRegistry, JsonSchema};
     pub use crate::domains::{SentimentAnalysisClient, EntityExtractionClient, ContentGenerationClient, CodeAssistanceClient, TranslationClient};
     pub use crate::utils::token_counter::{TokenCounter, Claude3TokenCounter, SimpleTokenCounter, get_token_counter};
     
@@ -80,6 +127,7 @@ pub use domains::{
     // Base traits
     DomainClient,
     DomainOperations,
+    DomainPrompt,
     ValidationOperations,
     
     // Domain-specific client types
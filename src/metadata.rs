@@ -0,0 +1,153 @@
+// Response metadata
+
+use reqwest::header::HeaderMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// One rate limit window reported in `anthropic-ratelimit-*` headers
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RateLimitWindow {
+    /// Maximum allowed in the window
+    pub limit: Option<u32>,
+    /// Remaining before the limit is reached
+    pub remaining: Option<u32>,
+    /// RFC 3339 time at which the window is fully replenished
+    pub reset: Option<String>,
+}
+
+impl RateLimitWindow {
+    fn from_headers(headers: &HeaderMap, name: &str) -> Option<Self> {
+        let value = |suffix: &str| {
+            headers.get(format!("anthropic-ratelimit-{}-{}", name, suffix))
+                .and_then(|value| value.to_str().ok())
+                .map(|value| value.trim().to_string())
+        };
+        let window = Self {
+            limit: value("limit").and_then(|value| value.parse().ok()),
+            remaining: value("remaining").and_then(|value| value.parse().ok()),
+            reset: value("reset"),
+        };
+        (window != Self::default()).then_some(window)
+    }
+}
+
+/// Rate limit state reported with a response
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RateLimitInfo {
+    /// Requests per minute
+    pub requests: Option<RateLimitWindow>,
+    /// Input tokens per minute
+    pub input_tokens: Option<RateLimitWindow>,
+    /// Output tokens per minute
+    pub output_tokens: Option<RateLimitWindow>,
+    /// The most restrictive token limit currently in effect
+    pub tokens: Option<RateLimitWindow>,
+}
+
+impl RateLimitInfo {
+    /// Read the `anthropic-ratelimit-*` headers of a response
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        Self {
+            requests: RateLimitWindow::from_headers(headers, "requests"),
+            input_tokens: RateLimitWindow::from_headers(headers, "input-tokens"),
+            output_tokens: RateLimitWindow::from_headers(headers, "output-tokens"),
+            tokens: RateLimitWindow::from_headers(headers, "tokens"),
+        }
+    }
+}
+
+/// HTTP details and timings of a response
+///
+/// Returned by `MessageBuilder::send_with_metadata` and
+/// `MessageBuilder::stream_with_metadata`. Timings are measured from when the
+/// request was sent, after any wait for the rate limiter; with retries, they
+/// describe the attempt that succeeded. Responses from a mock request handler
+/// have no headers.
+#[derive(Debug, Clone)]
+pub struct ResponseMetadata {
+    /// The `request-id` header, useful when contacting support
+    pub request_id: Option<String>,
+    /// HTTP status code
+    pub status: u16,
+    /// Rate limit state from the `anthropic-ratelimit-*` headers
+    pub rate_limit: RateLimitInfo,
+    /// All response headers
+    pub headers: HeaderMap,
+    /// Time until the response headers arrived
+    pub time_to_headers: Duration,
+    /// Time until the first content delta arrived, for streams
+    pub time_to_first_token: Option<Duration>,
+    /// Time until the whole response was received
+    ///
+    /// For streams, `None` until the `message_stop` event.
+    pub total_duration: Option<Duration>,
+}
+
+impl ResponseMetadata {
+    /// Create metadata for a response whose headers arrived `started.elapsed()` ago
+    pub(crate) fn from_response(response: &reqwest::Response, started: Instant) -> Self {
+        let headers = response.headers().clone();
+        Self {
+            request_id: headers.get("request-id")
+                .and_then(|value| value.to_str().ok())
+                .map(String::from),
+            status: response.status().as_u16(),
+            rate_limit: RateLimitInfo::from_headers(&headers),
+            headers,
+            time_to_headers: started.elapsed(),
+            time_to_first_token: None,
+            total_duration: None,
+        }
+    }
+
+    /// Create metadata for a response produced without an HTTP request
+    pub(crate) fn without_headers(started: Instant) -> Self {
+        Self {
+            request_id: None,
+            status: 200,
+            rate_limit: RateLimitInfo::default(),
+            headers: HeaderMap::new(),
+            time_to_headers: started.elapsed(),
+            time_to_first_token: None,
+            total_duration: None,
+        }
+    }
+}
+
+/// Metadata of a stream, completed as events arrive
+///
+/// The request id, status and headers are available as soon as the stream
+/// opens; `time_to_first_token` and `total_duration` are filled in later.
+#[derive(Debug, Clone)]
+pub struct StreamMetadata {
+    inner: Arc<Mutex<ResponseMetadata>>,
+}
+
+impl StreamMetadata {
+    pub(crate) fn new(metadata: ResponseMetadata) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(metadata)),
+        }
+    }
+
+    /// Get the metadata collected so far
+    pub fn get(&self) -> ResponseMetadata {
+        match self.inner.lock() {
+            Ok(metadata) => metadata.clone(),
+            Err(poisoned) => poisoned.into_inner().clone(),
+        }
+    }
+
+    /// Record the timing of a stream event received at `elapsed`
+    pub(crate) fn observe(&self, event_type: &str, elapsed: Duration) {
+        if let Ok(mut metadata) = self.inner.lock() {
+            match event_type {
+                "content_block_delta" if metadata.time_to_first_token.is_none() => {
+                    metadata.time_to_first_token = Some(elapsed);
+                }
+                "message_stop" => metadata.total_duration = Some(elapsed),
+                _ => {}
+            }
+        }
+    }
+}
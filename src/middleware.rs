@@ -8,6 +8,20 @@ pub trait ContextManager: Send + Sync {
     /// Process and possibly modify the messages before sending
     async fn process_messages(&self, messages: Vec<Message>) -> Result<Vec<Message>, ClaudeError>;
     
+    /// Process the messages of a request that is sent with `system` as its system prompt
+    ///
+    /// The system prompt takes up part of the context window on every request;
+    /// managers that budget tokens should override this to leave room for it.
+    /// Defaults to `process_messages`.
+    async fn process_request_messages(
+        &self,
+        system: Option<&SystemPrompt>,
+        messages: Vec<Message>,
+    ) -> Result<Vec<Message>, ClaudeError> {
+        let _ = system;
+        self.process_messages(messages).await
+    }
+    
     /// Update internal state based on the response
     async fn update_with_response(&self, response: &MessageResponse) -> Result<(), ClaudeError>;
 }
@@ -22,4 +36,17 @@ pub trait RequestMiddleware: Send + Sync {
 pub trait ResponseMiddleware: Send + Sync {
     /// Process and possibly modify the response after receiving
     async fn process_response(&self, response: MessageResponse) -> Result<MessageResponse, ClaudeError>;
+    
+    /// Process the response to `request`
+    ///
+    /// Middleware that needs the request, such as its model or cost tag,
+    /// should override this. Defaults to `process_response`.
+    async fn process_response_for(
+        &self,
+        request: &MessageRequest,
+        response: MessageResponse,
+    ) -> Result<MessageResponse, ClaudeError> {
+        let _ = request;
+        self.process_response(response).await
+    }
 }
\ No newline at end of file
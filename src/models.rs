@@ -0,0 +1,303 @@
+// Models API and model capabilities
+
+use crate::client::Claude;
+use crate::types::*;
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Largest `max_tokens` used for a request that does not set one
+///
+/// Requests for a model's full output length are slow and expensive, so the
+/// default is the smaller of this and the model's maximum output.
+pub const DEFAULT_MAX_TOKENS_CAP: u32 = 4096;
+
+lazy_static! {
+    static ref GLOBAL_REGISTRY: ModelRegistry = ModelRegistry::with_builtin_models();
+}
+
+/// Limits and capabilities of a model
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelInfo {
+    /// Model identifier, as sent in requests
+    pub id: String,
+    /// Human-readable name
+    pub display_name: String,
+    /// Maximum number of input and output tokens in a request
+    pub context_window: u32,
+    /// Maximum value of `max_tokens`
+    pub max_output_tokens: u32,
+    /// Whether the model accepts image content
+    pub supports_vision: bool,
+    /// Whether the model can call tools
+    pub supports_tools: bool,
+    /// Whether the model supports extended thinking
+    pub supports_thinking: bool,
+}
+
+impl ModelInfo {
+    /// Describe a model that supports vision and tools, but not extended thinking
+    pub fn new(
+        id: impl Into<String>,
+        display_name: impl Into<String>,
+        context_window: u32,
+        max_output_tokens: u32,
+    ) -> Self {
+        Self {
+            id: id.into(),
+            display_name: display_name.into(),
+            context_window,
+            max_output_tokens,
+            supports_vision: true,
+            supports_tools: true,
+            supports_thinking: false,
+        }
+    }
+
+    /// Set whether the model accepts image content
+    pub fn with_vision(mut self, supported: bool) -> Self {
+        self.supports_vision = supported;
+        self
+    }
+
+    /// Set whether the model can call tools
+    pub fn with_tools(mut self, supported: bool) -> Self {
+        self.supports_tools = supported;
+        self
+    }
+
+    /// Set whether the model supports extended thinking
+    pub fn with_thinking(mut self, supported: bool) -> Self {
+        self.supports_thinking = supported;
+        self
+    }
+
+    /// The `max_tokens` used when neither the request nor the client sets one
+    pub fn default_max_tokens(&self) -> u32 {
+        self.max_output_tokens.min(DEFAULT_MAX_TOKENS_CAP)
+    }
+}
+
+/// Strip the snapshot date or alias suffix from a model id
+///
+/// `claude-sonnet-4-20250514`, `claude-sonnet-4-0` and `claude-3-7-sonnet-latest`
+/// belong to the families `claude-sonnet-4` and `claude-3-7-sonnet`.
+pub(crate) fn model_family(id: &str) -> &str {
+    if let Some((family, suffix)) = id.rsplit_once('-') {
+        let is_date = suffix.len() == 8 && suffix.bytes().all(|b| b.is_ascii_digit());
+        if is_date || suffix == "latest" || suffix == "0" {
+            return family;
+        }
+    }
+    id
+}
+
+/// Registry of known models and their limits
+///
+/// The global registry starts with built-in entries for Claude models and is
+/// consulted by request validation, default `max_tokens` resolution and
+/// `get_token_counter`. Models are looked up by exact id first, then by
+/// family, so aliases and new snapshots of a known model share its limits.
+/// Register other models with `register`, or add the snapshots and aliases
+/// available to your API key with `ModelsClient::refresh_registry`.
+#[derive(Debug, Default)]
+pub struct ModelRegistry {
+    models: RwLock<HashMap<String, ModelInfo>>,
+}
+
+impl ModelRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a registry with entries for the Claude 3 and later models
+    pub fn with_builtin_models() -> Self {
+        let registry = Self::new();
+        let builtin = [
+            ModelInfo::new("claude-3-haiku-20240307", "Claude Haiku 3", 200_000, 4_096),
+            ModelInfo::new("claude-3-sonnet-20240229", "Claude Sonnet 3", 200_000, 4_096),
+            ModelInfo::new("claude-3-opus-20240229", "Claude Opus 3", 200_000, 4_096),
+            ModelInfo::new("claude-3-5-sonnet-20240620", "Claude Sonnet 3.5 (Old)", 200_000, 8_192),
+            ModelInfo::new("claude-3-5-sonnet-20241022", "Claude Sonnet 3.5 (New)", 200_000, 8_192),
+            ModelInfo::new("claude-3-5-haiku-20241022", "Claude Haiku 3.5", 200_000, 8_192),
+            ModelInfo::new("claude-3-7-sonnet-20250219", "Claude Sonnet 3.7", 200_000, 64_000).with_thinking(true),
+            ModelInfo::new("claude-sonnet-4-20250514", "Claude Sonnet 4", 200_000, 64_000).with_thinking(true),
+            ModelInfo::new("claude-opus-4-20250514", "Claude Opus 4", 200_000, 32_000).with_thinking(true),
+            ModelInfo::new("claude-opus-4-1-20250805", "Claude Opus 4.1", 200_000, 32_000).with_thinking(true),
+            ModelInfo::new("claude-sonnet-4-5-20250929", "Claude Sonnet 4.5", 200_000, 64_000).with_thinking(true),
+            ModelInfo::new("claude-haiku-4-5-20251001", "Claude Haiku 4.5", 200_000, 64_000).with_thinking(true),
+            ModelInfo::new("claude-opus-4-5-20251101", "Claude Opus 4.5", 200_000, 64_000).with_thinking(true),
+        ];
+        for info in builtin {
+            registry.register(info);
+        }
+        registry
+    }
+
+    /// Get the registry used by clients and request validation
+    pub fn global() -> &'static ModelRegistry {
+        &GLOBAL_REGISTRY
+    }
+
+    /// Add or replace a model's entry
+    pub fn register(&self, info: ModelInfo) {
+        if let Ok(mut models) = self.models.write() {
+            models.insert(info.id.clone(), info);
+        }
+    }
+
+    /// Look up a model by id, falling back to the latest snapshot of its family
+    pub fn get(&self, model_id: &str) -> Option<ModelInfo> {
+        let models = self.models.read().ok()?;
+        if let Some(info) = models.get(model_id) {
+            return Some(info.clone());
+        }
+
+        let family = model_family(model_id);
+        models.values()
+            .filter(|info| model_family(&info.id) == family)
+            .max_by(|a, b| a.id.cmp(&b.id))
+            .map(|info| ModelInfo {
+                id: model_id.to_string(),
+                ..info.clone()
+            })
+    }
+
+    /// Get all registered models, sorted by id
+    pub fn models(&self) -> Vec<ModelInfo> {
+        let mut models: Vec<ModelInfo> = self.models.read()
+            .map(|models| models.values().cloned().collect())
+            .unwrap_or_default();
+        models.sort_by(|a, b| a.id.cmp(&b.id));
+        models
+    }
+
+    /// Update the registry from models listed by the Models API
+    ///
+    /// Known models get the API's display name, and models of a known family
+    /// are registered with the family's limits. Returns the ids of models whose
+    /// limits are unknown; register those yourself.
+    pub fn refresh(&self, models: &[ApiModel]) -> Vec<String> {
+        let mut unknown = Vec::new();
+        for model in models {
+            match self.get(&model.id) {
+                Some(info) => self.register(ModelInfo {
+                    display_name: model.display_name.clone(),
+                    ..info
+                }),
+                None => unknown.push(model.id.clone()),
+            }
+        }
+        unknown
+    }
+}
+
+impl ClaudeModel {
+    /// Get this model's limits and capabilities from the global registry
+    pub fn info(&self) -> Option<ModelInfo> {
+        ModelRegistry::global().get(self.as_str())
+    }
+}
+
+/// A model as described by the Models API
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct ApiModel {
+    /// Model identifier
+    pub id: String,
+    /// Human-readable name
+    pub display_name: String,
+    /// RFC 3339 timestamp of the model's release
+    pub created_at: String,
+}
+
+/// A page of models from the Models API, most recently released first
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModelPage {
+    pub data: Vec<ApiModel>,
+    /// Whether there are more models after this page
+    pub has_more: bool,
+    pub first_id: Option<String>,
+    /// Pass as `after_id` to get the next page
+    pub last_id: Option<String>,
+}
+
+/// Client for the Models API
+///
+/// Created with `Claude::models()`.
+///
+/// ## Example
+///
+/// ```no_run
+/// # use claude_rs::Claude;
+/// # async fn example(claude: Claude) -> Result<(), Box<dyn std::error::Error>> {
+/// let models = claude.models();
+/// for model in models.list().await? {
+///     println!("{} ({})", model.display_name, model.id);
+/// }
+///
+/// // Register new snapshots and aliases of known models
+/// let unknown = models.refresh_registry().await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ModelsClient {
+    claude: Arc<Claude>,
+}
+
+impl ModelsClient {
+    pub(crate) fn new(claude: Arc<Claude>) -> Self {
+        Self { claude }
+    }
+
+    /// Get one page of available models
+    pub async fn list_page(&self, after_id: Option<&str>, limit: Option<u32>) -> ClaudeResult<ModelPage> {
+        let endpoint = format!("{}/models", self.claude.base_url);
+        let mut query = Vec::new();
+        if let Some(after_id) = after_id {
+            query.push(("after_id", after_id.to_string()));
+        }
+        if let Some(limit) = limit {
+            query.push(("limit", limit.to_string()));
+        }
+        self.claude.send_json(|http| http.get(&endpoint).query(&query)).await
+    }
+
+    /// Get all available models, following pages
+    pub async fn list(&self) -> ClaudeResult<Vec<ApiModel>> {
+        let mut models = Vec::new();
+        let mut after_id = None;
+        loop {
+            let page = self.list_page(after_id.as_deref(), None).await?;
+            models.extend(page.data);
+            match page.last_id {
+                Some(last_id) if page.has_more => after_id = Some(last_id),
+                _ => return Ok(models),
+            }
+        }
+    }
+
+    /// Get a model by id or alias
+    pub async fn retrieve(&self, model_id: &str) -> ClaudeResult<ApiModel> {
+        let endpoint = format!("{}/models/{}", self.claude.base_url, model_id);
+        self.claude.send_json(|http| http.get(&endpoint)).await
+    }
+
+    /// Update the global registry with the available models
+    ///
+    /// Returns the ids of available models whose limits are unknown.
+    pub async fn refresh_registry(&self) -> ClaudeResult<Vec<String>> {
+        let models = self.list().await?;
+        Ok(ModelRegistry::global().refresh(&models))
+    }
+}
+
+impl std::fmt::Debug for ModelsClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ModelsClient")
+            .field("base_url", &self.claude.base_url)
+            .finish()
+    }
+}
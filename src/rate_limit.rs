@@ -0,0 +1,408 @@
+// Client-side rate limiting
+
+use crate::metadata::{RateLimitInfo, RateLimitWindow};
+use crate::models::DEFAULT_MAX_TOKENS_CAP;
+use crate::types::*;
+use crate::utils::token_counter::get_token_counter;
+use reqwest::header::HeaderMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Requests and tokens per minute a client may use
+///
+/// Unset limits are not enforced until the API reports them in its
+/// `anthropic-ratelimit-*` response headers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimits {
+    /// Maximum requests per minute
+    pub requests_per_minute: Option<u32>,
+    /// Maximum uncached input tokens per minute
+    pub input_tokens_per_minute: Option<u32>,
+    /// Maximum output tokens per minute
+    pub output_tokens_per_minute: Option<u32>,
+}
+
+impl RateLimits {
+    /// Create limits that are learned from response headers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum requests per minute
+    pub fn with_requests_per_minute(mut self, limit: u32) -> Self {
+        self.requests_per_minute = Some(limit);
+        self
+    }
+
+    /// Set the maximum uncached input tokens per minute
+    pub fn with_input_tokens_per_minute(mut self, limit: u32) -> Self {
+        self.input_tokens_per_minute = Some(limit);
+        self
+    }
+
+    /// Set the maximum output tokens per minute
+    pub fn with_output_tokens_per_minute(mut self, limit: u32) -> Self {
+        self.output_tokens_per_minute = Some(limit);
+        self
+    }
+}
+
+/// A token bucket that refills continuously up to its per-minute capacity
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    available: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(per_minute: u32, now: Instant) -> Self {
+        Self {
+            capacity: per_minute as f64,
+            available: per_minute as f64,
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.available = (self.available + elapsed * self.capacity / 60.0).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// Time until `amount` is available; amounts above the capacity wait for a full bucket
+    fn wait_for(&self, amount: f64) -> Duration {
+        let needed = amount.min(self.capacity);
+        if self.available >= needed || self.capacity <= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64((needed - self.available) * 60.0 / self.capacity)
+        }
+    }
+
+    /// Apply a limit and remaining count reported by the API
+    ///
+    /// Returns whether the remaining count lowered the available capacity.
+    fn observe(&mut self, limit: Option<u32>, remaining: Option<u32>) -> bool {
+        if let Some(limit) = limit {
+            self.capacity = limit as f64;
+            self.available = self.available.min(self.capacity);
+        }
+        match remaining {
+            Some(remaining) if (remaining as f64) < self.available => {
+                self.available = remaining as f64;
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    configured: RateLimits,
+    requests: Option<Bucket>,
+    input_tokens: Option<Bucket>,
+    output_tokens: Option<Bucket>,
+    paused_until: Option<Instant>,
+}
+
+impl LimiterState {
+    fn buckets(&mut self) -> [&mut Option<Bucket>; 3] {
+        [&mut self.requests, &mut self.input_tokens, &mut self.output_tokens]
+    }
+
+    fn refill(&mut self, now: Instant) {
+        for bucket in self.buckets().into_iter().flatten() {
+            bucket.refill(now);
+        }
+    }
+
+    fn wait_for(&self, input_tokens: u32, output_tokens: u32, now: Instant) -> Duration {
+        let paused = self.paused_until.map_or(Duration::ZERO, |until| until.saturating_duration_since(now));
+        [
+            (&self.requests, 1.0),
+            (&self.input_tokens, input_tokens as f64),
+            (&self.output_tokens, output_tokens as f64),
+        ]
+        .into_iter()
+        .filter_map(|(bucket, amount)| bucket.as_ref().map(|bucket| bucket.wait_for(amount)))
+        .fold(paused, Duration::max)
+    }
+
+    fn take(&mut self, requests: f64, input_tokens: f64, output_tokens: f64) {
+        let amounts = [requests, input_tokens, output_tokens];
+        for (bucket, amount) in self.buckets().into_iter().zip(amounts) {
+            if let Some(bucket) = bucket {
+                bucket.available = (bucket.available - amount).min(bucket.capacity);
+            }
+        }
+    }
+}
+
+/// Apply a window reported by the API to a bucket, creating it if needed
+///
+/// Returns whether the reported remaining count lowered the bucket.
+fn observe_bucket(
+    slot: &mut Option<Bucket>,
+    configured: Option<u32>,
+    window: Option<&RateLimitWindow>,
+    now: Instant,
+) -> bool {
+    let (limit, remaining) = window.map_or((None, None), |window| (window.limit, window.remaining));
+    // Configured limits are caps; the API can only lower them
+    let limit = match (configured, limit) {
+        (Some(configured), Some(limit)) => Some(configured.min(limit)),
+        (configured, limit) => limit.or(configured),
+    };
+    match slot {
+        Some(bucket) => bucket.observe(limit, remaining),
+        None => match limit {
+            Some(limit) => slot.insert(Bucket::new(limit, now)).observe(None, remaining),
+            None => false,
+        },
+    }
+}
+
+/// Throttles requests to stay within requests, input tokens and output tokens per minute
+///
+/// Each limit is a token bucket that refills continuously, like the API's own
+/// limits. Before a request is sent, its input tokens are estimated with the
+/// model's token counter and its output is assumed to be `max_tokens`; once
+/// the response arrives, the reservation is corrected to the actual usage.
+/// Limits and remaining capacity are also read from the API's
+/// `anthropic-ratelimit-*` headers, and a 429 pauses all requests for the
+/// `retry-after` delay. The remaining counts reported with a response already
+/// include that request, so a limit they lower is not corrected again.
+///
+/// Requests that have to wait are queued and sent in the order they arrived.
+/// Clones share their limits, so one limiter can throttle many clients.
+///
+/// ## Example
+///
+/// ```no_run
+/// # use claude_rs::{Claude, RateLimiter, RateLimits};
+/// let claude = Claude::new("your_api_key_here").with_rate_limiter(RateLimiter::new(
+///     RateLimits::new()
+///         .with_requests_per_minute(50)
+///         .with_input_tokens_per_minute(40_000)
+///         .with_output_tokens_per_minute(8_000)
+/// ));
+/// ```
+#[derive(Clone)]
+pub struct RateLimiter {
+    state: Arc<Mutex<LimiterState>>,
+    // Tokio's mutex is fair, so waiting requests are let through first-in, first-out
+    queue: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl RateLimiter {
+    /// Create a limiter with the given limits
+    pub fn new(limits: RateLimits) -> Self {
+        let now = Instant::now();
+        let bucket = |limit: Option<u32>| limit.map(|limit| Bucket::new(limit, now));
+        Self {
+            state: Arc::new(Mutex::new(LimiterState {
+                configured: limits,
+                requests: bucket(limits.requests_per_minute),
+                input_tokens: bucket(limits.input_tokens_per_minute),
+                output_tokens: bucket(limits.output_tokens_per_minute),
+                paused_until: None,
+            })),
+            queue: Arc::new(tokio::sync::Mutex::new(())),
+        }
+    }
+
+    /// Get the limits currently enforced, including those learned from the API
+    pub fn limits(&self) -> RateLimits {
+        let Ok(state) = self.state.lock() else {
+            return RateLimits::default();
+        };
+        let limit = |bucket: &Option<Bucket>| bucket.as_ref().map(|bucket| bucket.capacity as u32);
+        RateLimits {
+            requests_per_minute: limit(&state.requests),
+            input_tokens_per_minute: limit(&state.input_tokens),
+            output_tokens_per_minute: limit(&state.output_tokens),
+        }
+    }
+
+    /// Wait until a request with the given token counts can be sent
+    ///
+    /// Returns a permit that holds the reservation; settle it with the
+    /// response's usage, or release it if the request failed. Dropping the
+    /// permit releases it.
+    pub async fn acquire(&self, input_tokens: u32, output_tokens: u32) -> RateLimitPermit {
+        let _turn = self.queue.lock().await;
+        loop {
+            let wait = match self.state.lock() {
+                Ok(mut state) => {
+                    let now = Instant::now();
+                    state.refill(now);
+                    let wait = state.wait_for(input_tokens, output_tokens, now);
+                    if wait.is_zero() {
+                        state.take(1.0, input_tokens as f64, output_tokens as f64);
+                    }
+                    wait
+                }
+                Err(_) => Duration::ZERO,
+            };
+            if wait.is_zero() {
+                break;
+            }
+            tokio::time::sleep(wait).await;
+        }
+
+        RateLimitPermit {
+            limiter: self.clone(),
+            input_tokens,
+            output_tokens,
+            input_reported: false,
+            output_reported: false,
+            finished: false,
+        }
+    }
+
+    /// Wait until a request can be sent, estimating its tokens
+    pub async fn acquire_for(&self, request: &MessageRequest) -> RateLimitPermit {
+        let (input_tokens, output_tokens) = Self::estimate(request);
+        self.acquire(input_tokens, output_tokens).await
+    }
+
+    /// Estimate a request's input tokens and reserve `max_tokens` of output
+    pub fn estimate(request: &MessageRequest) -> (u32, u32) {
+        let counter = get_token_counter(&ClaudeModel::Custom(request.model.clone()));
+        let messages: u32 = request.messages.iter()
+            .map(|message| counter.count_message_tokens(message))
+            .sum();
+        let system = request.system.as_ref().map_or(0, |system| counter.count_system_tokens(system));
+        let tools = request.tools.as_ref()
+            .and_then(|tools| serde_json::to_string(tools).ok())
+            .map_or(0, |json| counter.count_tokens(&json));
+
+        (messages + system + tools, request.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS_CAP))
+    }
+
+    /// Stop sending requests for `duration`
+    pub fn pause(&self, duration: Duration) {
+        if let Ok(mut state) = self.state.lock() {
+            let until = Instant::now() + duration;
+            state.paused_until = Some(state.paused_until.map_or(until, |current| current.max(until)));
+        }
+    }
+
+    /// Update limits and remaining capacity from `anthropic-ratelimit-*` headers
+    ///
+    /// Use `RateLimitPermit::observe_headers` for the headers of a request
+    /// that holds a permit.
+    pub fn update_from_headers(&self, headers: &HeaderMap) {
+        self.observe_headers(headers);
+    }
+
+    /// Apply `anthropic-ratelimit-*` headers, returning whether they lowered
+    /// the input and output token buckets
+    fn observe_headers(&self, headers: &HeaderMap) -> (bool, bool) {
+        let info = RateLimitInfo::from_headers(headers);
+        let Ok(mut state) = self.state.lock() else {
+            return (false, false);
+        };
+
+        let now = Instant::now();
+        state.refill(now);
+        let configured = state.configured;
+        observe_bucket(&mut state.requests, configured.requests_per_minute, info.requests.as_ref(), now);
+        let input = observe_bucket(&mut state.input_tokens, configured.input_tokens_per_minute, info.input_tokens.as_ref(), now);
+        let output = observe_bucket(&mut state.output_tokens, configured.output_tokens_per_minute, info.output_tokens.as_ref(), now);
+        (input, output)
+    }
+
+    fn adjust(&self, input_tokens: f64, output_tokens: f64) {
+        if let Ok(mut state) = self.state.lock() {
+            state.refill(Instant::now());
+            state.take(0.0, input_tokens, output_tokens);
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RateLimits::default())
+    }
+}
+
+impl std::fmt::Debug for RateLimiter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RateLimiter")
+            .field("limits", &self.limits())
+            .finish()
+    }
+}
+
+/// Tokens reserved for a request that has been let through by a `RateLimiter`
+///
+/// A permit that is dropped without being settled, for example with a stream
+/// that is abandoned before its final usage arrives, is released.
+#[derive(Debug)]
+#[must_use = "settle the permit with the response's usage, or release it"]
+pub struct RateLimitPermit {
+    limiter: RateLimiter,
+    input_tokens: u32,
+    output_tokens: u32,
+    // Whether the API's remaining counts already include this request
+    input_reported: bool,
+    output_reported: bool,
+    finished: bool,
+}
+
+impl RateLimitPermit {
+    /// Get the reserved input tokens
+    pub fn input_tokens(&self) -> u32 {
+        self.input_tokens
+    }
+
+    /// Get the reserved output tokens
+    pub fn output_tokens(&self) -> u32 {
+        self.output_tokens
+    }
+
+    /// Apply the `anthropic-ratelimit-*` headers of this request's response
+    ///
+    /// The remaining counts in the headers already include this request, so
+    /// limits they lower are not adjusted again when the permit is settled.
+    pub fn observe_headers(&mut self, headers: &HeaderMap) {
+        let (input, output) = self.limiter.observe_headers(headers);
+        self.input_reported |= input;
+        self.output_reported |= output;
+    }
+
+    /// Replace the reservation with the tokens the request actually used
+    ///
+    /// Cache reads do not count towards the input token limit.
+    pub fn settle(mut self, usage: &Usage) {
+        self.finish(usage.input_tokens + usage.cache_creation_input_tokens, usage.output_tokens);
+    }
+
+    /// Return the reserved tokens, for a request that was not processed
+    pub fn release(mut self) {
+        self.finish(0, 0);
+    }
+
+    /// Correct the reservation to the tokens used, unless the API already reported them
+    fn finish(&mut self, input_tokens: u32, output_tokens: u32) {
+        if std::mem::replace(&mut self.finished, true) {
+            return;
+        }
+        let correction = |used: u32, reserved: u32, reported: bool| {
+            if reported { 0.0 } else { used as f64 - reserved as f64 }
+        };
+        self.limiter.adjust(
+            correction(input_tokens, self.input_tokens, self.input_reported),
+            correction(output_tokens, self.output_tokens, self.output_reported),
+        );
+    }
+}
+
+impl Drop for RateLimitPermit {
+    fn drop(&mut self) {
+        self.finish(0, 0);
+    }
+}
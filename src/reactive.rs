@@ -3,9 +3,11 @@
 use crate::types::*;
 use crate::client::Claude;
 use crate::builder::MessageBuilder;
+use crate::cancel::cancellable_stream;
 use futures::stream::{Stream, StreamExt};
 use std::pin::Pin;
 use std::task::{Context, Poll};
+use tokio_util::sync::CancellationToken;
 
 /// Status of the reactive streaming response
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -18,6 +20,10 @@ pub enum ReactiveResponseStatus {
     Complete,
     /// An error occurred during streaming
     Error,
+    /// The stream was stopped by its cancellation token
+    Cancelled,
+    /// The stream stopped sending events and hit a stream timeout
+    TimedOut,
 }
 
 /// Reactive response wrapper for streaming Claude responses
@@ -26,6 +32,8 @@ pub struct ReactiveResponse {
     pub inner: Pin<Box<dyn Stream<Item = Result<DeltaEvent, ClaudeError>> + Send>>,
     /// Accumulated text buffer
     pub text_buffer: String,
+    /// Accumulated extended thinking, kept apart from the answer text
+    pub thinking_buffer: String,
     /// Current status of the reactive response
     pub status: ReactiveResponseStatus,
     /// Last error that occurred, if any
@@ -38,11 +46,22 @@ impl ReactiveResponse {
         Self {
             inner: Box::pin(stream),
             text_buffer: String::new(),
+            thinking_buffer: String::new(),
             status: ReactiveResponseStatus::Initializing,
             last_error: None,
         }
     }
     
+    /// Stop the stream when `token` is cancelled
+    ///
+    /// The stream yields `ClaudeError::Cancelled` and ends, and the status
+    /// becomes `Cancelled`. Text received so far stays available.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        let inner = std::mem::replace(&mut self.inner, Box::pin(futures::stream::empty()));
+        self.inner = cancellable_stream(inner, Some(token));
+        self
+    }
+    
     /// Get the current status of the reactive response
     pub fn status(&self) -> ReactiveResponseStatus {
         self.status
@@ -58,6 +77,13 @@ impl ReactiveResponse {
         &self.text_buffer
     }
     
+    /// Get the extended thinking received so far
+    ///
+    /// Empty unless the request enabled thinking.
+    pub fn current_thinking(&self) -> &str {
+        &self.thinking_buffer
+    }
+    
     /// Get the last error if one occurred during streaming
     pub fn last_error(&self) -> Option<&ClaudeError> {
         self.last_error.as_ref()
@@ -68,6 +94,16 @@ impl ReactiveResponse {
         self.status == ReactiveResponseStatus::Error
     }
     
+    /// Check if the stream was cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.status == ReactiveResponseStatus::Cancelled
+    }
+    
+    /// Check if the stream ended because it stalled
+    pub fn is_timed_out(&self) -> bool {
+        self.status == ReactiveResponseStatus::TimedOut
+    }
+    
     /// Transform the stream to operate on text chunks
     pub fn text_stream(self) -> impl Stream<Item = Result<String, ClaudeError>> {
         let stream = self.inner;
@@ -86,14 +122,28 @@ impl ReactiveResponse {
         })
         .filter(|result| futures::future::ready(!matches!(result, Ok(s) if s.is_empty())))
     }
+    
+    /// Transform the stream to operate on extended thinking chunks
+    pub fn thinking_stream(self) -> impl Stream<Item = Result<String, ClaudeError>> {
+        self.inner
+            .filter_map(|result| futures::future::ready(match result {
+                Ok(delta) => delta.to_thinking().map(Ok),
+                Err(e) => Some(Err(e)),
+            }))
+    }
 }
 
 impl Stream for ReactiveResponse {
     type Item = Result<DeltaEvent, ClaudeError>;
     
     fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        if self.status == ReactiveResponseStatus::Complete || 
-           self.status == ReactiveResponseStatus::Error {
+        if matches!(
+            self.status,
+            ReactiveResponseStatus::Complete
+                | ReactiveResponseStatus::Error
+                | ReactiveResponseStatus::Cancelled
+                | ReactiveResponseStatus::TimedOut
+        ) {
             return Poll::Ready(None);
         }
         
@@ -105,6 +155,9 @@ impl Stream for ReactiveResponse {
                 if let Some(text) = delta.to_text() {
                     self.text_buffer.push_str(&text);
                 }
+                if let Some(thinking) = delta.to_thinking() {
+                    self.thinking_buffer.push_str(&thinking);
+                }
                 
                 if delta.is_final() {
                     self.status = ReactiveResponseStatus::Complete;
@@ -113,7 +166,13 @@ impl Stream for ReactiveResponse {
                 Poll::Ready(Some(Ok(delta)))
             }
             Poll::Ready(Some(Err(e))) => {
-                self.status = ReactiveResponseStatus::Error;
+                self.status = if e.is_cancelled() {
+                    ReactiveResponseStatus::Cancelled
+                } else if e.is_stream_timeout() {
+                    ReactiveResponseStatus::TimedOut
+                } else {
+                    ReactiveResponseStatus::Error
+                };
                 self.last_error = Some(e.clone());
                 Poll::Ready(Some(Err(e)))
             }
@@ -132,7 +191,8 @@ impl Claude {
     /// Extension method for reactive streaming
     /// 
     /// Converts a streaming request into a ReactiveResponse that tracks status
-    /// and provides additional utility methods.
+    /// and provides additional utility methods. The stream observes the
+    /// builder's or client's cancellation token and its stream timeouts.
     pub async fn send_reactive(&self, builder: MessageBuilder) -> Result<ReactiveResponse, ClaudeError> {
         let stream = builder.stream().await?;
         Ok(ReactiveResponse::new(stream))
@@ -0,0 +1,215 @@
+// Retry policy for transient API failures
+
+use crate::types::*;
+use std::future::Future;
+use std::time::Duration;
+
+/// HTTP status codes that are retried by default
+///
+/// 408 (request timeout), 409 (conflict), 429 (rate limited), the 5xx gateway
+/// family and 529 (Anthropic's "overloaded" status).
+pub const DEFAULT_RETRY_STATUSES: &[u16] = &[408, 409, 429, 500, 502, 503, 504, 529];
+
+/// Configuration for automatically retrying failed requests
+///
+/// Retries use exponential backoff (`base_delay * 2^attempt`, capped at
+/// `max_delay`) with optional jitter. When the API supplies a `retry-after`
+/// header, that delay is used instead of the computed backoff, still capped
+/// at `max_delay`.
+///
+/// Only the initial request is retried. For streaming requests this means the
+/// connection is retried until the stream is established; errors that occur
+/// mid-stream are passed through to the caller.
+///
+/// ## Example
+///
+/// ```no_run
+/// # use claude_rs::{Claude, RetryPolicy};
+/// # use std::time::Duration;
+/// let claude = Claude::new("your_api_key_here")
+///     .with_retry_policy(
+///         RetryPolicy::default()
+///             .with_max_retries(5)
+///             .with_max_delay(Duration::from_secs(60))
+///     );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum number of retries after the initial attempt (0 disables retries)
+    pub max_retries: u32,
+    /// Delay before the first retry
+    pub base_delay: Duration,
+    /// Upper bound for the delay before a retry, including a server-provided `retry-after`
+    pub max_delay: Duration,
+    /// Whether to randomize delays to avoid synchronized retries
+    pub jitter: bool,
+    /// HTTP status codes that should be retried
+    pub retry_statuses: Vec<u16>,
+    /// Retry when the connection to the API could not be established
+    pub retry_on_connect: bool,
+    /// Retry when the request timed out
+    pub retry_on_timeout: bool,
+    /// Use the server-provided `retry-after` delay when present
+    pub respect_retry_after: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: true,
+            retry_statuses: DEFAULT_RETRY_STATUSES.to_vec(),
+            retry_on_connect: true,
+            retry_on_timeout: true,
+            respect_retry_after: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Create a retry policy with the default settings
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a policy that never retries
+    ///
+    /// This is the policy used by `Claude` unless another one is configured.
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Set the maximum number of retries after the initial attempt
+    pub fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Set the delay before the first retry
+    pub fn with_base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+
+    /// Set the upper bound for the computed backoff delay and the server's `retry-after` value
+    pub fn with_max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    /// Enable or disable jitter
+    pub fn with_jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Replace the set of HTTP status codes that should be retried
+    pub fn with_retry_statuses(mut self, statuses: impl Into<Vec<u16>>) -> Self {
+        self.retry_statuses = statuses.into();
+        self
+    }
+
+    /// Enable or disable retries on connection failures
+    pub fn with_retry_on_connect(mut self, retry: bool) -> Self {
+        self.retry_on_connect = retry;
+        self
+    }
+
+    /// Enable or disable retries on request timeouts
+    pub fn with_retry_on_timeout(mut self, retry: bool) -> Self {
+        self.retry_on_timeout = retry;
+        self
+    }
+
+    /// Enable or disable honoring the server's `retry-after` header
+    pub fn with_respect_retry_after(mut self, respect: bool) -> Self {
+        self.respect_retry_after = respect;
+        self
+    }
+
+    /// Check whether this policy performs any retries at all
+    pub fn is_enabled(&self) -> bool {
+        self.max_retries > 0
+    }
+
+    /// Decide whether an error is transient according to this policy
+    pub fn should_retry(&self, error: &ClaudeError) -> bool {
+        match error {
+            ClaudeError::RateLimited { .. } => self.retry_statuses.contains(&429),
+            ClaudeError::ApiError { status, .. } => self.retry_statuses.contains(status),
+            ClaudeError::RequestError { source: Some(source), .. } => {
+                if source.is::<tokio::time::error::Elapsed>() {
+                    return self.retry_on_timeout;
+                }
+                match source.downcast_ref::<reqwest::Error>() {
+                    Some(err) if err.is_timeout() => self.retry_on_timeout,
+                    Some(err) if err.is_connect() => self.retry_on_connect,
+                    _ => false,
+                }
+            }
+            ClaudeError::StreamTimeout { .. } => self.retry_on_timeout,
+            _ => false,
+        }
+    }
+
+    /// Compute the delay before retry number `attempt` (starting at 0)
+    ///
+    /// The server's `retry-after` value takes precedence when present and
+    /// `respect_retry_after` is enabled. It is capped at `max_delay`, like
+    /// the backoff delay.
+    pub fn retry_delay(&self, attempt: u32, error: &ClaudeError) -> Duration {
+        if self.respect_retry_after {
+            if let Some(retry_after) = error.retry_after() {
+                return retry_after.min(self.max_delay);
+            }
+        }
+
+        self.backoff_delay(attempt)
+    }
+
+    /// Compute the exponential backoff delay for retry number `attempt` (starting at 0)
+    pub fn backoff_delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt);
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+
+        if self.jitter {
+            // Equal jitter: keep half of the delay and randomize the other half
+            let half = delay / 2;
+            half + half.mul_f64(fastrand::f64())
+        } else {
+            delay
+        }
+    }
+
+    /// Run an operation, retrying transient failures according to this policy
+    pub(crate) async fn execute<T, F, Fut>(&self, mut operation: F) -> ClaudeResult<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = ClaudeResult<T>>,
+    {
+        let mut attempt = 0;
+
+        loop {
+            match operation().await {
+                Ok(value) => return Ok(value),
+                Err(error) if attempt < self.max_retries && self.should_retry(&error) => {
+                    let delay = self.retry_delay(attempt, &error);
+                    attempt += 1;
+
+                    log::warn!(
+                        "Retrying request in {:?} (retry {}/{}) after error: {}",
+                        delay, attempt, self.max_retries, error
+                    );
+
+                    tokio::time::sleep(delay).await;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+}
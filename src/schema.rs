@@ -0,0 +1,215 @@
+//! JSON Schema generation for tool inputs
+//!
+//! The [`JsonSchema`] trait describes how a Rust type looks as JSON, so a
+//! [`Tool`] definition can be built from the same type that its input is
+//! deserialized into. With the `derive` feature enabled, `#[derive(JsonSchema)]`
+//! implements the trait for structs and enums, using doc comments as
+//! descriptions and honoring the common serde attributes (`rename`,
+//! `rename_all`, `default`, `skip`, `tag`).
+//!
+//! ## Example
+//!
+//! ```
+//! use claude_rs::schema::{JsonSchema, parse_tool_input};
+//! use claude_rs::types::Tool;
+//! use serde::Deserialize;
+//! use serde_json::json;
+//!
+//! #[derive(Deserialize)]
+//! struct WeatherInput {
+//!     location: String,
+//!     days: Option<u8>,
+//! }
+//!
+//! // Implemented by hand here; `#[derive(JsonSchema)]` generates the same code
+//! impl JsonSchema for WeatherInput {
+//!     fn json_schema() -> serde_json::Value {
+//!         json!({
+//!             "type": "object",
+//!             "properties": {
+//!                 "location": String::json_schema(),
+//!                 "days": <Option<u8>>::json_schema(),
+//!             },
+//!             "required": ["location"]
+//!         })
+//!     }
+//! }
+//!
+//! let tool = Tool::from_type::<WeatherInput>("get_weather", "Get the forecast");
+//! assert_eq!(tool.input_schema["required"], json!(["location"]));
+//!
+//! let error = parse_tool_input::<WeatherInput>(&json!({"location": "Tokyo", "days": -1}))
+//!     .err()
+//!     .unwrap();
+//! assert!(error.to_string().contains("days"));
+//! ```
+
+use crate::types::*;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap, HashSet, BTreeSet};
+
+#[cfg(feature = "derive")]
+pub use claude_rs_derive::JsonSchema;
+
+/// A type that can describe its JSON representation as a JSON Schema
+pub trait JsonSchema {
+    /// Get the JSON Schema for this type
+    fn json_schema() -> Value;
+
+    /// Whether a struct field of this type may be omitted
+    ///
+    /// Only `Option<T>` overrides this; it keeps optional fields out of the
+    /// `required` list of the parent object.
+    fn is_optional() -> bool {
+        false
+    }
+}
+
+macro_rules! impl_json_schema {
+    ($($ty:ty),* => $schema:expr) => {
+        $(
+            impl JsonSchema for $ty {
+                fn json_schema() -> Value {
+                    $schema
+                }
+            }
+        )*
+    };
+}
+
+impl_json_schema!(String, str, char => json!({"type": "string"}));
+impl_json_schema!(bool => json!({"type": "boolean"}));
+impl_json_schema!(i8, i16, i32, i64, i128, isize => json!({"type": "integer"}));
+impl_json_schema!(u8, u16, u32, u64, u128, usize => json!({"type": "integer", "minimum": 0}));
+impl_json_schema!(f32, f64 => json!({"type": "number"}));
+impl_json_schema!(Value => json!({}));
+
+impl<T: JsonSchema + ?Sized> JsonSchema for &T {
+    fn json_schema() -> Value {
+        T::json_schema()
+    }
+}
+
+impl<T: JsonSchema + ?Sized> JsonSchema for Box<T> {
+    fn json_schema() -> Value {
+        T::json_schema()
+    }
+}
+
+impl<T: JsonSchema> JsonSchema for Option<T> {
+    fn json_schema() -> Value {
+        T::json_schema()
+    }
+
+    fn is_optional() -> bool {
+        true
+    }
+}
+
+impl<T: JsonSchema> JsonSchema for Vec<T> {
+    fn json_schema() -> Value {
+        json!({"type": "array", "items": T::json_schema()})
+    }
+}
+
+impl<T: JsonSchema> JsonSchema for [T] {
+    fn json_schema() -> Value {
+        json!({"type": "array", "items": T::json_schema()})
+    }
+}
+
+impl<T: JsonSchema> JsonSchema for HashSet<T> {
+    fn json_schema() -> Value {
+        json!({"type": "array", "items": T::json_schema(), "uniqueItems": true})
+    }
+}
+
+impl<T: JsonSchema> JsonSchema for BTreeSet<T> {
+    fn json_schema() -> Value {
+        json!({"type": "array", "items": T::json_schema(), "uniqueItems": true})
+    }
+}
+
+impl<V: JsonSchema> JsonSchema for HashMap<String, V> {
+    fn json_schema() -> Value {
+        json!({"type": "object", "additionalProperties": V::json_schema()})
+    }
+}
+
+impl<V: JsonSchema> JsonSchema for BTreeMap<String, V> {
+    fn json_schema() -> Value {
+        json!({"type": "object", "additionalProperties": V::json_schema()})
+    }
+}
+
+impl Tool {
+    /// Build a tool definition whose input schema is generated from `T`
+    pub fn from_type<T: JsonSchema>(name: impl Into<String>, description: impl Into<String>) -> Self {
+        Tool {
+            name: name.into(),
+            description: description.into(),
+            input_schema: T::json_schema(),
+            cache_control: None,
+        }
+    }
+}
+
+/// Deserialize a tool's JSON input into `T`
+///
+/// Errors name the path of the invalid field (for example `items[2].quantity`),
+/// so they can be reported back to the model as a useful tool error.
+pub fn parse_tool_input<T: DeserializeOwned>(input: &Value) -> ClaudeResult<T> {
+    serde_path_to_error::deserialize(input).map_err(|e| {
+        let path = e.path().to_string();
+        if path == "." {
+            ClaudeError::ValidationError(format!("Invalid tool input: {}", e.inner()))
+        } else {
+            ClaudeError::ValidationError(format!("Invalid tool input at `{}`: {}", path, e.inner()))
+        }
+    })
+}
+
+#[doc(hidden)]
+pub mod __private {
+    //! Re-exports and helpers used by code generated by `#[derive(JsonSchema)]`
+    pub use serde_json::{json, Map, Value};
+
+    /// Add a description to a schema, ahead of any description it already has
+    pub fn describe(mut schema: Value, description: &str) -> Value {
+        if let Value::Object(map) = &mut schema {
+            let description = match map.get("description").and_then(Value::as_str) {
+                Some(existing) => format!("{}\n{}", description, existing),
+                None => description.to_string(),
+            };
+            map.insert("description".to_string(), Value::String(description));
+        }
+        schema
+    }
+
+    /// Build an object schema from its properties and required property names
+    pub fn object(properties: Map<String, Value>, required: Vec<Value>) -> Value {
+        let mut schema = json!({"type": "object", "properties": properties});
+        if !required.is_empty() {
+            schema["required"] = Value::Array(required);
+        }
+        schema
+    }
+
+    /// Add a constant tag property to an object schema, as used by internally
+    /// and adjacently tagged enums
+    pub fn tagged(mut schema: Value, tag: &str, name: &str) -> Value {
+        if let Value::Object(map) = &mut schema {
+            if let Some(Value::Object(properties)) = map.get_mut("properties") {
+                properties.insert(tag.to_string(), json!({"type": "string", "const": name}));
+            }
+            match map.get_mut("required") {
+                Some(Value::Array(required)) => required.insert(0, Value::from(tag)),
+                _ => {
+                    map.insert("required".to_string(), json!([tag]));
+                }
+            }
+        }
+        schema
+    }
+}
@@ -0,0 +1,58 @@
+// Stall detection for streaming responses
+
+use crate::types::*;
+use futures::StreamExt;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Limits on how long a stream may go without events
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct StreamTimeouts {
+    /// Longest wait for the first event other than `ping`
+    pub first_event: Option<Duration>,
+    /// Longest wait between two events, `ping` events included
+    pub max_gap: Option<Duration>,
+}
+
+impl StreamTimeouts {
+    fn is_empty(&self) -> bool {
+        self.first_event.is_none() && self.max_gap.is_none()
+    }
+}
+
+/// End `stream` with a `ClaudeError::StreamTimeout` item once it stalls
+///
+/// Until the first event other than `ping` arrives, only the first event
+/// timeout applies, measured from when the stream is opened. After that, or
+/// when no first event timeout is set, every event, keep-alive pings
+/// included, restarts the gap timer. The inner stream is dropped on a stall,
+/// closing its connection.
+pub(crate) fn stall_guard(stream: MessageStream, timeouts: StreamTimeouts) -> MessageStream {
+    if timeouts.is_empty() {
+        return stream;
+    }
+
+    let first_event_deadline = timeouts.first_event.map(|timeout| (Instant::now() + timeout, timeout));
+    futures::stream::unfold(Some((stream, false)), move |state| async move {
+        let (mut stream, started) = state?;
+        let limit = match first_event_deadline {
+            Some((deadline, timeout)) if !started => Some((deadline, timeout, StreamStall::FirstEvent)),
+            _ => timeouts.max_gap.map(|gap| (Instant::now() + gap, gap, StreamStall::Idle)),
+        };
+        let Some((deadline, timeout, stall)) = limit else {
+            let item = stream.next().await?;
+            return Some((item, Some((stream, true))));
+        };
+
+        match tokio::time::timeout_at(deadline, stream.next()).await {
+            Ok(Some(item)) => {
+                let started = started || matches!(&item, Ok(event) if event.event_type != "ping");
+                Some((item, Some((stream, started))))
+            }
+            Ok(None) => None,
+            // End the stream after reporting the stall
+            Err(_) => Some((Err(ClaudeError::StreamTimeout { stall, timeout }), None)),
+        }
+    })
+    .boxed()
+}
@@ -0,0 +1,353 @@
+// Reassembly of streamed content blocks
+
+use crate::types::*;
+use futures::Stream;
+use std::collections::{BTreeMap, VecDeque};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// A change to the response content, derived from the raw stream events
+#[derive(Debug, Clone)]
+pub enum ContentEvent {
+    /// A content block was started at `index`
+    ///
+    /// For `tool_use` blocks, `block` carries the tool call id and name; its
+    /// input is filled in by later `InputJsonDelta` events.
+    BlockStart { index: u32, block: Content },
+    /// Text was appended to the text block at `index`
+    TextDelta { index: u32, text: String },
+    /// Reasoning was appended to the thinking block at `index`
+    ThinkingDelta { index: u32, thinking: String },
+    /// A fragment of JSON was appended to the input of the tool_use block at `index`
+    InputJsonDelta { index: u32, partial_json: String },
+    /// A citation was added to the text block at `index`
+    CitationDelta { index: u32, citation: Citation },
+    /// The block at `index` is complete
+    ///
+    /// For `tool_use` blocks, `block` carries the fully parsed input.
+    BlockComplete { index: u32, block: Content },
+}
+
+/// A content block that is still being received
+#[derive(Debug, Clone)]
+struct PartialBlock {
+    block: Content,
+    /// Accumulated `input_json_delta` fragments, for tool_use blocks
+    input_json: String,
+    complete: bool,
+}
+
+impl PartialBlock {
+    fn new(block: Content) -> Self {
+        Self { block, input_json: String::new(), complete: false }
+    }
+
+    /// Parse the accumulated JSON fragments into the tool_use input
+    fn finish(&mut self) -> ClaudeResult<()> {
+        if let Content::ToolUse { input, .. } = &mut self.block {
+            if !self.input_json.trim().is_empty() {
+                *input = serde_json::from_str(&self.input_json).map_err(|e| ClaudeError::parse_error(
+                    format!("Failed to parse streamed tool input: {}", e),
+                    Some(self.input_json.clone()),
+                    Some(e),
+                    Some(concat!(file!(), ":", line!()))
+                ))?;
+            }
+        }
+        self.complete = true;
+        Ok(())
+    }
+}
+
+/// Rebuilds a complete response from stream events
+///
+/// Content blocks are tracked by their `index`. Text deltas are appended to
+/// text blocks, and `input_json_delta` fragments are collected until the
+/// block's `content_block_stop` event, when they are parsed into the tool_use
+/// input. Thinking and signature deltas are appended to thinking blocks.
+/// Text deltas for an index without a `content_block_start` event start
+/// an implicit text block, so streams in the legacy format are handled too.
+///
+/// The message id, model and input and cache token counts come from
+/// `message_start`; the stop reason, stop sequence and output token count from
+/// `message_delta`, which can also update the input and cache token counts.
+/// The response is only complete once `message_stop` has arrived.
+#[derive(Debug, Clone, Default)]
+pub struct StreamAccumulator {
+    blocks: BTreeMap<u32, PartialBlock>,
+    stopped: bool,
+    id: String,
+    model: String,
+    role: Option<Role>,
+    stop_reason: Option<String>,
+    stop_sequence: Option<String>,
+    usage: Usage,
+}
+
+impl StreamAccumulator {
+    /// Create an empty accumulator
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply a stream event, returning the content changes it produced
+    ///
+    /// Fails if a completed tool_use block's input is not valid JSON.
+    pub fn apply(&mut self, event: &DeltaEvent) -> ClaudeResult<Vec<ContentEvent>> {
+        let index = event.index.unwrap_or(0);
+        let mut events = Vec::new();
+
+        match event.event_type.as_str() {
+            "message_start" => {
+                if let Some(message) = &event.message {
+                    self.id = message.id.clone();
+                    self.model = message.model.clone();
+                    self.role = message.role.clone();
+                    if let Some(usage) = &message.usage {
+                        self.usage = usage.clone();
+                    }
+                }
+            }
+            "message_delta" => {
+                if let Some(delta) = &event.delta {
+                    if delta.stop_reason.is_some() {
+                        self.stop_reason = delta.stop_reason.clone();
+                    }
+                    if delta.stop_sequence.is_some() {
+                        self.stop_sequence = delta.stop_sequence.clone();
+                    }
+                }
+                // Token counts in message_delta are cumulative; input and cache
+                // counts are only present when they changed since message_start
+                if let Some(usage) = &event.usage {
+                    self.usage.output_tokens = usage.output_tokens;
+                    if usage.input_tokens > 0 {
+                        self.usage.input_tokens = usage.input_tokens;
+                    }
+                    if usage.cache_creation_input_tokens > 0 {
+                        self.usage.cache_creation_input_tokens = usage.cache_creation_input_tokens;
+                    }
+                    if usage.cache_read_input_tokens > 0 {
+                        self.usage.cache_read_input_tokens = usage.cache_read_input_tokens;
+                    }
+                }
+            }
+            "message_stop" => {
+                self.stopped = true;
+            }
+            "content_block_start" => {
+                if let Some(block) = &event.content_block {
+                    self.blocks.insert(index, PartialBlock::new(block.clone()));
+                    events.push(ContentEvent::BlockStart { index, block: block.clone() });
+                }
+            }
+            "content_block_delta" => {
+                let Some(delta) = &event.delta else { return Ok(events) };
+
+                if let Some(thinking) = &delta.thinking {
+                    if let Some(Content::Thinking { thinking: existing, .. }) = self.blocks.get_mut(&index).map(|p| &mut p.block) {
+                        existing.push_str(thinking);
+                    }
+                    events.push(ContentEvent::ThinkingDelta { index, thinking: thinking.clone() });
+                } else if let Some(signature) = &delta.signature {
+                    if let Some(Content::Thinking { signature: existing, .. }) = self.blocks.get_mut(&index).map(|p| &mut p.block) {
+                        existing.push_str(signature);
+                    }
+                } else if let Some(partial_json) = &delta.partial_json {
+                    if let Some(partial) = self.blocks.get_mut(&index) {
+                        partial.input_json.push_str(partial_json);
+                    }
+                    events.push(ContentEvent::InputJsonDelta { index, partial_json: partial_json.clone() });
+                } else if let Some(text) = &delta.text {
+                    let partial = self.blocks.entry(index)
+                        .or_insert_with(|| PartialBlock::new(Content::text(String::new())));
+                    if let Content::Text { text: existing, .. } = &mut partial.block {
+                        existing.push_str(text);
+                    }
+                    events.push(ContentEvent::TextDelta { index, text: text.clone() });
+                } else if let Some(citation) = &delta.citation {
+                    if let Some(Content::Text { citations, .. }) = self.blocks.get_mut(&index).map(|p| &mut p.block) {
+                        citations.get_or_insert_with(Vec::new).push(citation.clone());
+                    }
+                    events.push(ContentEvent::CitationDelta { index, citation: citation.clone() });
+                }
+            }
+            "content_block_stop" => {
+                if let Some(partial) = self.blocks.get_mut(&index) {
+                    partial.finish()?;
+                    events.push(ContentEvent::BlockComplete { index, block: partial.block.clone() });
+                }
+            }
+            _ => {}
+        }
+
+        Ok(events)
+    }
+
+    /// Get the content blocks received so far, in index order
+    ///
+    /// Tool_use blocks that have not been completed yet keep the input they
+    /// were started with.
+    pub fn content(&self) -> Vec<Content> {
+        self.blocks.values().map(|partial| partial.block.clone()).collect()
+    }
+
+    /// Check whether the `message_stop` event has arrived
+    pub fn is_complete(&self) -> bool {
+        self.stopped
+    }
+
+    /// Get the stop reason, once the `message_delta` event has arrived
+    pub fn stop_reason(&self) -> Option<&str> {
+        self.stop_reason.as_deref()
+    }
+
+    /// Get the token usage reported so far
+    pub fn usage(&self) -> &Usage {
+        &self.usage
+    }
+
+    /// Finish any open blocks and return the assembled content
+    pub fn into_content(self) -> ClaudeResult<Vec<Content>> {
+        Self::finish_blocks(self.blocks)
+    }
+
+    /// Finish any open blocks and build the response `send()` would have returned
+    ///
+    /// Fails if the stream ended before `message_stop`, for example because
+    /// the connection was dropped, since the content would be incomplete.
+    pub fn into_response(self) -> ClaudeResult<MessageResponse> {
+        if !self.stopped {
+            return Err(ClaudeError::request_error(
+                "Stream ended before message_stop",
+                Some(format!("Received {} content blocks", self.blocks.len())),
+                None::<reqwest::Error>,
+                Some(concat!(file!(), ":", line!()))
+            ));
+        }
+
+        Ok(MessageResponse {
+            content: Self::finish_blocks(self.blocks)?,
+            id: self.id,
+            model: self.model,
+            r#type: "message".to_string(),
+            role: self.role.unwrap_or(Role::Assistant),
+            usage: self.usage,
+            stop_reason: self.stop_reason,
+            stop_sequence: self.stop_sequence,
+        })
+    }
+
+    fn finish_blocks(blocks: BTreeMap<u32, PartialBlock>) -> ClaudeResult<Vec<Content>> {
+        blocks.into_values()
+            .map(|mut partial| {
+                if !partial.complete {
+                    partial.finish()?;
+                }
+                Ok(partial.block)
+            })
+            .collect()
+    }
+}
+
+/// A stream of content changes built on top of a [`MessageStream`]
+///
+/// Yields a [`ContentEvent`] for each block start, text or JSON fragment and
+/// block completion, while keeping the assembled content available through
+/// [`ContentStream::content`].
+///
+/// ## Example
+///
+/// ```no_run
+/// # use claude_rs::{Claude, Content, ContentEvent};
+/// # use futures::StreamExt;
+/// # async fn example(claude: Claude) -> Result<(), claude_rs::ClaudeError> {
+/// let mut stream = claude.message()
+///     .user_content("What's the weather in Tokyo?")
+///     .stream_content()
+///     .await?;
+///
+/// while let Some(event) = stream.next().await {
+///     match event? {
+///         ContentEvent::TextDelta { text, .. } => print!("{}", text),
+///         ContentEvent::BlockComplete { block: Content::ToolUse { name, input, .. }, .. } => {
+///             println!("\nTool call: {} {}", name, input);
+///         }
+///         _ => {}
+///     }
+/// }
+///
+/// let content = stream.into_content()?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct ContentStream {
+    inner: MessageStream,
+    accumulator: StreamAccumulator,
+    pending: VecDeque<ContentEvent>,
+}
+
+impl ContentStream {
+    /// Wrap a raw event stream
+    pub fn new(inner: MessageStream) -> Self {
+        Self {
+            inner,
+            accumulator: StreamAccumulator::new(),
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// Get the content blocks received so far, in index order
+    pub fn content(&self) -> Vec<Content> {
+        self.accumulator.content()
+    }
+
+    /// Get the state accumulated from the events received so far
+    pub fn accumulator(&self) -> &StreamAccumulator {
+        &self.accumulator
+    }
+
+    /// Finish any open blocks and return the assembled content
+    pub fn into_content(self) -> ClaudeResult<Vec<Content>> {
+        self.accumulator.into_content()
+    }
+
+    /// Finish any open blocks and return the assembled response
+    ///
+    /// Call this after the stream has ended; events that have not been polled
+    /// are not included. Fails if the stream ended before `message_stop`.
+    pub fn into_response(self) -> ClaudeResult<MessageResponse> {
+        self.accumulator.into_response()
+    }
+}
+
+impl Stream for ContentStream {
+    type Item = ClaudeResult<ContentEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+
+            match self.inner.as_mut().poll_next(cx) {
+                Poll::Ready(Some(Ok(event))) => match self.accumulator.apply(&event) {
+                    Ok(events) => self.pending.extend(events),
+                    Err(e) => return Poll::Ready(Some(Err(e))),
+                },
+                Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for ContentStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ContentStream")
+            .field("accumulator", &self.accumulator)
+            .field("pending", &self.pending)
+            .finish()
+    }
+}
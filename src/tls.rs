@@ -0,0 +1,374 @@
+// TLS configuration
+
+use crate::types::*;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use lazy_static::lazy_static;
+use reqwest::tls::{Certificate, Identity, Version};
+use rustls::client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier};
+use rustls::{CertificateError, ClientConfig, RootCertStore, ServerName};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+lazy_static! {
+    static ref CLIENT_CONFIG: Mutex<TlsConfig> = Mutex::new(TlsConfig::default());
+}
+
+/// Configuration for TLS
+///
+/// Set it for one client with `ClaudeBuilder::tls_config`, or for all clients
+/// created afterwards with `set_tls_config`.
+///
+/// ## Example
+///
+/// ```no_run
+/// # use claude_rs::{Claude, TlsConfig};
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let tls = TlsConfig::new()
+///     .with_root_certificates_pem(&std::fs::read("corporate-ca.pem")?)?
+///     .with_identity_pem(&std::fs::read("client.pem")?, &std::fs::read("client.key")?)?;
+///
+/// let claude = Claude::builder("your_api_key_here")
+///     .tls_config(tls)
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct TlsConfig {
+    pub min_tls_version: Option<Version>,
+    pub cert_verification: bool,
+    /// DER encoded certificate authorities to trust in addition to the built-in ones
+    pub root_certificates: Vec<Vec<u8>>,
+    /// Whether the system's certificate authorities are trusted
+    pub built_in_root_certs: bool,
+    /// Client certificate and key for mutual TLS, unless `identity_pem` is set
+    pub identity: Option<Identity>,
+    /// DER encoded server certificates, one of which the server must present
+    pub pinned_certificates: Vec<Vec<u8>>,
+    /// Client certificate chain and key for mutual TLS
+    ///
+    /// Takes precedence over `identity`, and is the only identity pinned
+    /// connections can present.
+    pub identity_pem: Option<PemIdentity>,
+}
+
+/// A client certificate chain and private key, as loaded by `TlsConfig::with_identity_pem`
+#[derive(Clone)]
+pub struct PemIdentity {
+    /// DER encoded certificates, leaf first
+    pub certificates: Vec<Vec<u8>>,
+    /// DER encoded PKCS #8 private key
+    pub key: Vec<u8>,
+}
+
+impl PemIdentity {
+    /// Convert to an identity for the native TLS backend
+    fn to_identity(&self) -> ClaudeResult<Identity> {
+        let certificates: String = self.certificates.iter()
+            .map(|der| pem_block("CERTIFICATE", der))
+            .collect();
+        let key = pem_block("PRIVATE KEY", &self.key);
+        Identity::from_pkcs8_pem(certificates.as_bytes(), key.as_bytes()).map_err(|e| {
+            ClaudeError::InvalidParameter(format!("Invalid client identity: {}", e))
+        })
+    }
+}
+
+/// Encode DER bytes as a PEM block with the given label
+fn pem_block(label: &str, der: &[u8]) -> String {
+    let encoded = BASE64.encode(der);
+    let mut pem = format!("-----BEGIN {}-----\n", label);
+    for line in encoded.as_bytes().chunks(64) {
+        // Base64 output is ASCII, so every chunk is valid UTF-8
+        pem.push_str(std::str::from_utf8(line).unwrap_or_default());
+        pem.push('\n');
+    }
+    pem.push_str(&format!("-----END {}-----\n", label));
+    pem
+}
+
+impl std::fmt::Debug for PemIdentity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print the private key
+        f.debug_struct("PemIdentity")
+            .field("certificates", &self.certificates.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            min_tls_version: Some(Version::TLS_1_2),
+            cert_verification: true,
+            root_certificates: Vec::new(),
+            built_in_root_certs: true,
+            identity: None,
+            pinned_certificates: Vec::new(),
+            identity_pem: None,
+        }
+    }
+}
+
+impl TlsConfig {
+    /// Create the default configuration: TLS 1.2 or later, verified against the system's roots
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the minimum TLS version
+    pub fn with_min_tls_version(mut self, version: Version) -> Self {
+        self.min_tls_version = Some(version);
+        self
+    }
+
+    /// Trust an additional DER encoded certificate authority
+    pub fn with_root_certificate(mut self, der: impl Into<Vec<u8>>) -> ClaudeResult<Self> {
+        let der = der.into();
+        Certificate::from_der(&der).map_err(|e| {
+            ClaudeError::InvalidParameter(format!("Invalid root certificate: {}", e))
+        })?;
+        self.root_certificates.push(der);
+        Ok(self)
+    }
+
+    /// Trust the certificate authorities in a PEM bundle
+    pub fn with_root_certificates_pem(mut self, pem: &[u8]) -> ClaudeResult<Self> {
+        let certificates = parse_pem_certificates(pem, "root")?;
+        for der in &certificates {
+            Certificate::from_der(der).map_err(|e| {
+                ClaudeError::InvalidParameter(format!("Invalid root certificate: {}", e))
+            })?;
+        }
+        self.root_certificates.extend(certificates);
+        Ok(self)
+    }
+
+    /// Trust only the certificate authorities added to this configuration
+    pub fn without_built_in_roots(mut self) -> Self {
+        self.built_in_root_certs = false;
+        self
+    }
+
+    /// Present a client certificate for mutual TLS
+    ///
+    /// Connections with pinned certificates can only present identities
+    /// loaded with `with_identity_pem`.
+    pub fn with_identity(mut self, identity: Identity) -> Self {
+        self.identity = Some(identity);
+        self.identity_pem = None;
+        self
+    }
+
+    /// Present a client certificate from a PEM certificate chain and PKCS #8 PEM key
+    pub fn with_identity_pem(self, certificate_pem: &[u8], key_pem: &[u8]) -> ClaudeResult<Self> {
+        let identity = Identity::from_pkcs8_pem(certificate_pem, key_pem).map_err(|e| {
+            ClaudeError::InvalidParameter(format!("Invalid client identity: {}", e))
+        })?;
+        let key = rustls_pemfile::pkcs8_private_keys(&mut &key_pem[..])
+            .ok()
+            .and_then(|keys| keys.into_iter().next())
+            .ok_or_else(|| ClaudeError::InvalidParameter("Invalid client identity: no PKCS #8 key found".into()))?;
+        let pem = PemIdentity {
+            certificates: parse_pem_certificates(certificate_pem, "client")?,
+            key,
+        };
+
+        let mut config = self.with_identity(identity);
+        config.identity_pem = Some(pem);
+        Ok(config)
+    }
+
+    /// Present a client certificate from a PKCS #12 archive
+    pub fn with_identity_pkcs12(self, der: &[u8], password: &str) -> ClaudeResult<Self> {
+        let identity = Identity::from_pkcs12_der(der, password).map_err(|e| {
+            ClaudeError::InvalidParameter(format!("Invalid client identity: {}", e))
+        })?;
+        Ok(self.with_identity(identity))
+    }
+
+    /// Only accept a server that presents this DER encoded certificate
+    ///
+    /// The pin is checked during the TLS handshake, in addition to the usual
+    /// certificate verification, so nothing is sent to a server that presents
+    /// another certificate. Pin several certificates to rotate them without
+    /// downtime. Pinned clients only connect over HTTPS.
+    pub fn with_pinned_certificate(mut self, der: impl Into<Vec<u8>>) -> ClaudeResult<Self> {
+        let der = der.into();
+        Certificate::from_der(&der).map_err(|e| {
+            ClaudeError::InvalidParameter(format!("Invalid pinned certificate: {}", e))
+        })?;
+        self.pinned_certificates.push(der);
+        Ok(self)
+    }
+
+    /// Only accept a server that presents one of the certificates in a PEM bundle
+    pub fn with_pinned_certificates_pem(mut self, pem: &[u8]) -> ClaudeResult<Self> {
+        let certificates = parse_pem_certificates(pem, "pinned")?;
+        for der in &certificates {
+            Certificate::from_der(der).map_err(|e| {
+                ClaudeError::InvalidParameter(format!("Invalid pinned certificate: {}", e))
+            })?;
+        }
+        self.pinned_certificates.extend(certificates);
+        Ok(self)
+    }
+
+    /// Apply this configuration to an HTTP client builder
+    pub(crate) fn apply(&self, mut builder: reqwest::ClientBuilder) -> ClaudeResult<reqwest::ClientBuilder> {
+        if !self.pinned_certificates.is_empty() {
+            // The native TLS backend cannot check pins during the handshake, so
+            // pinned clients use rustls with a verifier that does
+            return Ok(builder
+                .use_preconfigured_tls(self.pinned_rustls_config()?)
+                .https_only(true));
+        }
+
+        builder = builder
+            .danger_accept_invalid_certs(!self.cert_verification)
+            .tls_built_in_root_certs(self.built_in_root_certs);
+        if let Some(version) = self.min_tls_version {
+            builder = builder.min_tls_version(version);
+        }
+        for der in &self.root_certificates {
+            let certificate = Certificate::from_der(der).map_err(|e| {
+                ClaudeError::InvalidParameter(format!("Invalid root certificate: {}", e))
+            })?;
+            builder = builder.add_root_certificate(certificate);
+        }
+        if let Some(pem) = &self.identity_pem {
+            builder = builder.identity(pem.to_identity()?);
+        } else if let Some(identity) = &self.identity {
+            builder = builder.identity(identity.clone());
+        }
+        Ok(builder)
+    }
+
+    /// Build the rustls configuration of a client with pinned certificates
+    fn pinned_rustls_config(&self) -> ClaudeResult<ClientConfig> {
+        let invalid = |e: rustls::Error| ClaudeError::InvalidParameter(format!("Invalid TLS configuration: {}", e));
+
+        let mut roots = RootCertStore::empty();
+        roots.add_parsable_certificates(&self.root_certificates);
+        if self.built_in_root_certs {
+            let native = rustls_native_certs::load_native_certs().map_err(|e| {
+                ClaudeError::InvalidParameter(format!("Failed to load the system's root certificates: {}", e))
+            })?;
+            let native: Vec<Vec<u8>> = native.into_iter().map(|certificate| certificate.0).collect();
+            roots.add_parsable_certificates(&native);
+        }
+        let verifier = PinnedCertVerifier {
+            chain: self.cert_verification.then(|| WebPkiVerifier::new(roots, None)),
+            pins: self.pinned_certificates.clone(),
+        };
+
+        // rustls only speaks TLS 1.2 and 1.3
+        let versions: &[&rustls::SupportedProtocolVersion] = match self.min_tls_version {
+            Some(Version::TLS_1_3) => &[&rustls::version::TLS13],
+            _ => &[&rustls::version::TLS12, &rustls::version::TLS13],
+        };
+        let builder = ClientConfig::builder()
+            .with_safe_default_cipher_suites()
+            .with_safe_default_kx_groups()
+            .with_protocol_versions(versions)
+            .map_err(invalid)?
+            .with_custom_certificate_verifier(Arc::new(verifier));
+
+        let mut config = match (&self.identity_pem, &self.identity) {
+            (Some(pem), _) => builder
+                .with_client_auth_cert(
+                    pem.certificates.iter().cloned().map(rustls::Certificate).collect(),
+                    rustls::PrivateKey(pem.key.clone()),
+                )
+                .map_err(invalid)?,
+            (None, Some(_)) => {
+                return Err(ClaudeError::InvalidParameter(
+                    "Certificate pinning with a client certificate requires with_identity_pem".into()
+                ));
+            }
+            (None, None) => builder.with_no_client_auth(),
+        };
+        config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+        Ok(config)
+    }
+}
+
+/// Parse the certificates of a PEM bundle, which must contain at least one
+fn parse_pem_certificates(pem: &[u8], kind: &str) -> ClaudeResult<Vec<Vec<u8>>> {
+    let certificates = rustls_pemfile::certs(&mut &pem[..]).map_err(|e| {
+        ClaudeError::InvalidParameter(format!("Invalid {} certificate: {}", kind, e))
+    })?;
+    if certificates.is_empty() {
+        return Err(ClaudeError::InvalidParameter(format!("No certificates found in {} certificate PEM", kind)));
+    }
+    Ok(certificates)
+}
+
+/// Accepts a server only if it presents a pinned certificate
+///
+/// Runs during the handshake, so a request is never sent to a server with
+/// another certificate. The certificate chain is verified first, unless
+/// certificate verification is disabled.
+struct PinnedCertVerifier {
+    chain: Option<WebPkiVerifier>,
+    pins: Vec<Vec<u8>>,
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &rustls::Certificate,
+        intermediates: &[rustls::Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if let Some(chain) = &self.chain {
+            chain.verify_server_cert(end_entity, intermediates, server_name, scts, ocsp_response, now)?;
+        }
+        if self.pins.contains(&end_entity.0) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::InvalidCertificate(CertificateError::Other(Arc::new(PinMismatch))))
+        }
+    }
+}
+
+/// The server presented a certificate that is not pinned
+struct PinMismatch;
+
+impl std::fmt::Debug for PinMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Server certificate does not match any pinned certificate")
+    }
+}
+
+impl std::fmt::Display for PinMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(self, f)
+    }
+}
+
+impl std::error::Error for PinMismatch {}
+
+/// Set global TLS configuration for all Claude clients
+///
+/// Applies to clients created afterwards, unless they set their own with
+/// `ClaudeBuilder::tls_config`.
+pub fn set_tls_config(config: TlsConfig) {
+    if let Ok(mut cfg) = CLIENT_CONFIG.lock() {
+        *cfg = config;
+    }
+}
+
+/// Get the global TLS configuration used by new clients
+pub(crate) fn global_tls_config() -> TlsConfig {
+    match CLIENT_CONFIG.lock() {
+        // Clone the config so we don't hold the lock
+        Ok(guard) => guard.clone(),
+        // If lock is poisoned, use the default config
+        Err(_) => TlsConfig::default(),
+    }
+}
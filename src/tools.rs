@@ -0,0 +1,214 @@
+// Tool registry and automatic tool execution
+
+use crate::schema::{parse_tool_input, JsonSchema};
+use crate::types::*;
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+/// Maximum number of model calls made by `MessageBuilder::run_tools` unless overridden
+pub const DEFAULT_MAX_TOOL_ITERATIONS: u32 = 10;
+
+/// Future returned by a tool handler
+type ToolHandlerFuture = Pin<Box<dyn Future<Output = ClaudeResult<ToolResultContent>> + Send>>;
+
+/// Function that executes a tool call from its JSON input
+type ToolHandlerFn = dyn Fn(serde_json::Value) -> ToolHandlerFuture + Send + Sync + 'static;
+
+/// A set of tools the model may call, each paired with an async handler
+///
+/// The registry supplies the tool definitions sent with each request and
+/// executes `tool_use` blocks returned by the model. Handler errors and calls
+/// to unknown tools are reported back to the model as `tool_result` blocks
+/// with `is_error` set, so the model can recover instead of the run failing.
+///
+/// ## Example
+///
+/// ```no_run
+/// # use claude_rs::{Claude, ToolRegistry};
+/// # use claude_rs::types::Tool;
+/// # use serde_json::json;
+/// # async fn example() -> Result<(), claude_rs::ClaudeError> {
+/// let registry = ToolRegistry::new().with_tool(
+///     Tool {
+///         name: "get_weather".to_string(),
+///         description: "Get the current weather for a location".to_string(),
+///         input_schema: json!({
+///             "type": "object",
+///             "properties": { "location": { "type": "string" } },
+///             "required": ["location"]
+///         }),
+///         cache_control: None,
+///     },
+///     |input| async move {
+///         let location = input["location"].as_str().unwrap_or("unknown");
+///         Ok(format!("It is sunny in {}", location))
+///     },
+/// );
+///
+/// let claude = Claude::new("your_api_key_here");
+/// let run = claude.message()
+///     .user_content("What's the weather in Tokyo?")
+///     .run_tools(&registry)
+///     .await?;
+///
+/// println!("{} model calls, {} output tokens", run.iterations, run.usage.output_tokens);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    tools: Vec<Tool>,
+    handlers: HashMap<String, Arc<ToolHandlerFn>>,
+}
+
+impl ToolRegistry {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a tool and its handler
+    ///
+    /// Registering a tool with the same name as an existing one replaces it.
+    pub fn register<F, Fut, R>(&mut self, tool: Tool, handler: F)
+    where
+        F: Fn(serde_json::Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ClaudeResult<R>> + Send + 'static,
+        R: Into<ToolResultContent>,
+    {
+        let handler: Arc<ToolHandlerFn> = Arc::new(move |input| {
+            let future = handler(input);
+            Box::pin(async move { future.await.map(Into::into) })
+        });
+
+        self.tools.retain(|existing| existing.name != tool.name);
+        self.handlers.insert(tool.name.clone(), handler);
+        self.tools.push(tool);
+    }
+
+    /// Register a tool and its handler, returning the registry
+    pub fn with_tool<F, Fut, R>(mut self, tool: Tool, handler: F) -> Self
+    where
+        F: Fn(serde_json::Value) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ClaudeResult<R>> + Send + 'static,
+        R: Into<ToolResultContent>,
+    {
+        self.register(tool, handler);
+        self
+    }
+
+    /// Register a tool whose input schema is generated from `T`
+    ///
+    /// The model's input is deserialized into `T` before the handler runs. If it
+    /// does not match, the handler is skipped and the error, naming the invalid
+    /// field, is sent back to the model.
+    pub fn register_typed<T, F, Fut, R>(&mut self, name: impl Into<String>, description: impl Into<String>, handler: F)
+    where
+        T: JsonSchema + DeserializeOwned + Send + 'static,
+        F: Fn(T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ClaudeResult<R>> + Send + 'static,
+        R: Into<ToolResultContent>,
+    {
+        let handler = Arc::new(handler);
+        self.register(Tool::from_type::<T>(name, description), move |input| {
+            let handler = handler.clone();
+            async move { handler(parse_tool_input::<T>(&input)?).await }
+        });
+    }
+
+    /// Register a typed tool and its handler, returning the registry
+    pub fn with_typed_tool<T, F, Fut, R>(mut self, name: impl Into<String>, description: impl Into<String>, handler: F) -> Self
+    where
+        T: JsonSchema + DeserializeOwned + Send + 'static,
+        F: Fn(T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ClaudeResult<R>> + Send + 'static,
+        R: Into<ToolResultContent>,
+    {
+        self.register_typed(name, description, handler);
+        self
+    }
+
+    /// Get the definitions of all registered tools, in registration order
+    pub fn tools(&self) -> &[Tool] {
+        &self.tools
+    }
+
+    /// Check whether a tool with the given name is registered
+    pub fn contains(&self, name: &str) -> bool {
+        self.handlers.contains_key(name)
+    }
+
+    /// Get the number of registered tools
+    pub fn len(&self) -> usize {
+        self.tools.len()
+    }
+
+    /// Check whether the registry has no tools
+    pub fn is_empty(&self) -> bool {
+        self.tools.is_empty()
+    }
+
+    /// Execute a single tool call and build the matching `tool_result` block
+    pub async fn execute(&self, tool_use_id: &str, name: &str, input: serde_json::Value) -> Content {
+        let Some(handler) = self.handlers.get(name).cloned() else {
+            log::warn!("Model called unknown tool '{}'", name);
+            return Content::tool_error(tool_use_id, format!("Unknown tool: {}", name));
+        };
+
+        match handler(input).await {
+            Ok(content) => Content::ToolResult {
+                tool_use_id: tool_use_id.to_string(),
+                content: Some(content),
+                is_error: false,
+                cache_control: None,
+            },
+            Err(error) => {
+                log::warn!("Tool '{}' failed: {}", name, error);
+                Content::tool_error(tool_use_id, error.to_string())
+            }
+        }
+    }
+
+    /// Execute every `tool_use` block in `content` concurrently
+    ///
+    /// Results are returned in the same order as the tool calls.
+    pub async fn execute_all(&self, content: &[Content]) -> Vec<Content> {
+        let calls = content.iter().filter_map(|block| match block {
+            Content::ToolUse { id, name, input, .. } => Some(self.execute(id, name, input.clone())),
+            _ => None,
+        });
+
+        futures::future::join_all(calls).await
+    }
+}
+
+impl std::fmt::Debug for ToolRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToolRegistry")
+            .field("tools", &self.tools.iter().map(|t| &t.name).collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// The outcome of an automatic tool-execution loop
+#[derive(Debug, Clone)]
+pub struct ToolRun {
+    /// The full conversation, including the original messages, every assistant
+    /// turn and every tool result
+    pub messages: Vec<Message>,
+    /// The last response received from the model
+    pub response: MessageResponse,
+    /// Token usage summed over every model call
+    pub usage: Usage,
+    /// Number of model calls made
+    pub iterations: u32,
+    /// Whether the loop stopped because it hit the iteration cap while the
+    /// model was still requesting tools
+    ///
+    /// When set, the last message is an assistant turn whose `tool_use` blocks
+    /// have not been executed.
+    pub max_iterations_reached: bool,
+}
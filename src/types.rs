@@ -1,6 +1,8 @@
 // Core types and errors
 
 use serde::{Deserialize, Serialize};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 use thiserror::Error;
 use std::time::Duration;
 use std::pin::Pin;
@@ -119,6 +121,7 @@ pub enum ClaudeError {
         retry_after: Option<Duration>,
         details: Option<String>,
         location: Option<String>,  // New field for call location
+        request_id: Option<String>,  // Value of the `request-id` response header
     },
     
     #[error("API key not provided")]
@@ -132,6 +135,9 @@ pub enum ClaudeError {
         message: String,
         response_body: Option<String>,
         location: Option<String>,  // New field for call location
+        retry_after: Option<Duration>,  // Server-provided retry delay, if any
+        kind: ApiErrorKind,  // Typed error category from the response body
+        request_id: Option<String>,  // Value of the `request-id` response header
     },
     
     #[error("Context window exceeded")]
@@ -161,6 +167,167 @@ pub enum ClaudeError {
     
     #[error("Conversion error: {0}")]
     ConversionError(String),
+    
+    #[error("Budget exceeded for {scope}: spent ${spent:.4} of ${limit:.4}")]
+    BudgetExceeded {
+        scope: String,  // The budget that was exceeded, e.g. `total` or `tag 'search'`
+        spent: f64,  // Spend in USD when the request was rejected
+        limit: f64,  // The budget's limit in USD
+    },
+    
+    #[error("Request was cancelled")]
+    Cancelled,
+    
+    #[error("Stream stalled: {stall} within {timeout:?}")]
+    StreamTimeout {
+        stall: StreamStall,  // Which stream timeout was exceeded
+        timeout: Duration,  // The configured timeout
+    },
+}
+
+/// The kind of stall that ended a stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StreamStall {
+    /// No event other than `ping` arrived within the first event timeout
+    FirstEvent,
+    /// No event, not even a `ping`, arrived within the maximum gap
+    Idle,
+}
+
+impl std::fmt::Display for StreamStall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamStall::FirstEvent => write!(f, "no first event"),
+            StreamStall::Idle => write!(f, "no event"),
+        }
+    }
+}
+
+/// Error categories returned by the Anthropic API
+///
+/// Parsed from the `error.type` field of an error response body. When the body
+/// cannot be parsed, the kind is derived from the HTTP status code instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum ApiErrorKind {
+    /// 400: There was an issue with the format or content of the request
+    InvalidRequestError,
+    /// 401: There's an issue with the API key
+    AuthenticationError,
+    /// 403: The API key does not have permission to use the resource
+    PermissionError,
+    /// 404: The requested resource was not found
+    NotFoundError,
+    /// 413: The request exceeds the maximum allowed size
+    RequestTooLarge,
+    /// 429: The account has hit a rate limit
+    RateLimitError,
+    /// 500: An unexpected error occurred inside Anthropic's systems
+    ApiError,
+    /// 529: The API is temporarily overloaded
+    OverloadedError,
+    /// The error type is not known to this version of the SDK or could not be
+    /// determined from the response
+    Unknown,
+}
+
+impl ApiErrorKind {
+    /// Get the wire name of this error type
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiErrorKind::InvalidRequestError => "invalid_request_error",
+            ApiErrorKind::AuthenticationError => "authentication_error",
+            ApiErrorKind::PermissionError => "permission_error",
+            ApiErrorKind::NotFoundError => "not_found_error",
+            ApiErrorKind::RequestTooLarge => "request_too_large",
+            ApiErrorKind::RateLimitError => "rate_limit_error",
+            ApiErrorKind::ApiError => "api_error",
+            ApiErrorKind::OverloadedError => "overloaded_error",
+            ApiErrorKind::Unknown => "unknown",
+        }
+    }
+    
+    /// Derive the error kind from an HTTP status code
+    pub fn from_status(status: u16) -> Self {
+        match status {
+            400 => ApiErrorKind::InvalidRequestError,
+            401 => ApiErrorKind::AuthenticationError,
+            403 => ApiErrorKind::PermissionError,
+            404 => ApiErrorKind::NotFoundError,
+            413 => ApiErrorKind::RequestTooLarge,
+            429 => ApiErrorKind::RateLimitError,
+            529 => ApiErrorKind::OverloadedError,
+            500..=599 => ApiErrorKind::ApiError,
+            _ => ApiErrorKind::Unknown,
+        }
+    }
+    
+    /// The HTTP status code the API uses for this error kind
+    ///
+    /// Useful for errors delivered inside a stream, which have no status of their own.
+    pub fn status_code(&self) -> Option<u16> {
+        match self {
+            ApiErrorKind::InvalidRequestError => Some(400),
+            ApiErrorKind::AuthenticationError => Some(401),
+            ApiErrorKind::PermissionError => Some(403),
+            ApiErrorKind::NotFoundError => Some(404),
+            ApiErrorKind::RequestTooLarge => Some(413),
+            ApiErrorKind::RateLimitError => Some(429),
+            ApiErrorKind::ApiError => Some(500),
+            ApiErrorKind::OverloadedError => Some(529),
+            ApiErrorKind::Unknown => None,
+        }
+    }
+}
+
+impl From<&str> for ApiErrorKind {
+    fn from(kind: &str) -> Self {
+        match kind {
+            "invalid_request_error" => ApiErrorKind::InvalidRequestError,
+            "authentication_error" => ApiErrorKind::AuthenticationError,
+            "permission_error" => ApiErrorKind::PermissionError,
+            "not_found_error" => ApiErrorKind::NotFoundError,
+            "request_too_large" => ApiErrorKind::RequestTooLarge,
+            "rate_limit_error" => ApiErrorKind::RateLimitError,
+            "api_error" => ApiErrorKind::ApiError,
+            "overloaded_error" => ApiErrorKind::OverloadedError,
+            _ => ApiErrorKind::Unknown,
+        }
+    }
+}
+
+impl From<String> for ApiErrorKind {
+    fn from(kind: String) -> Self {
+        ApiErrorKind::from(kind.as_str())
+    }
+}
+
+impl From<ApiErrorKind> for String {
+    fn from(kind: ApiErrorKind) -> Self {
+        kind.as_str().to_string()
+    }
+}
+
+impl fmt::Display for ApiErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Error envelope returned by the API: `{"type": "error", "error": {...}}`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiErrorResponse {
+    #[serde(rename = "type")]
+    pub response_type: String,
+    pub error: ApiErrorDetail,
+}
+
+/// The `error` object inside an API error response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiErrorDetail {
+    #[serde(rename = "type")]
+    pub kind: ApiErrorKind,
+    pub message: String,
 }
 
 /// Claude model identifiers
@@ -211,30 +378,537 @@ pub enum Role {
 #[serde(tag = "type")]
 #[serde(rename_all = "snake_case")]
 pub enum Content {
-    Text { text: String },
-    Image { source: ImageSource },
-    Tool { tool_use: ToolUse },
-    ToolResult { tool_result: ToolResult, tool_call_id: String },
+    Text {
+        text: String,
+        /// Marks the end of a cacheable prompt prefix
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
+        /// Passages of the request's documents that support this text
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        citations: Option<Vec<Citation>>,
+    },
+    Image {
+        source: ImageSource,
+        /// Marks the end of a cacheable prompt prefix
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
+    },
+    /// A PDF or text document for the model to read, and optionally cite
+    Document {
+        source: DocumentSource,
+        /// Title of the document, passed to the model and returned in citations
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        title: Option<String>,
+        /// Information about the document that is passed to the model but never cited
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        context: Option<String>,
+        /// Whether the model can cite passages of the document
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        citations: Option<CitationsConfig>,
+        /// Marks the end of a cacheable prompt prefix
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
+    },
+    /// A request from the model to call a tool
+    ToolUse {
+        /// Unique identifier for this tool call, referenced by the matching result
+        id: String,
+        /// Name of the tool to call
+        name: String,
+        /// Tool input, matching the tool's input schema
+        input: serde_json::Value,
+        /// Marks the end of a cacheable prompt prefix
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
+    },
+    /// The result of a tool call, sent back to the model in a user message
+    ToolResult {
+        /// The `id` of the `tool_use` block this result answers
+        tool_use_id: String,
+        /// Output of the tool, either a string or a list of text and image blocks
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        content: Option<ToolResultContent>,
+        /// Whether the tool call failed
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        is_error: bool,
+        /// Marks the end of a cacheable prompt prefix
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        cache_control: Option<CacheControl>,
+    },
+    /// The model's reasoning before its answer, when extended thinking is enabled
+    ///
+    /// Thinking blocks must be sent back unmodified, signature included, when
+    /// the assistant turn is part of a later request, such as in a tool loop.
+    Thinking {
+        thinking: String,
+        /// Verifies that the block was produced by the model
+        #[serde(default)]
+        signature: String,
+    },
+    /// Reasoning that was encrypted by the safety systems
+    ///
+    /// The data is opaque, but must be sent back like a thinking block.
+    RedactedThinking {
+        data: String,
+    },
+}
+
+impl Content {
+    /// Create a `text` block
+    pub fn text(text: impl Into<String>) -> Self {
+        Content::Text {
+            text: text.into(),
+            cache_control: None,
+            citations: None,
+        }
+    }
+
+    /// Create an `image` block
+    pub fn image(source: ImageSource) -> Self {
+        Content::Image {
+            source,
+            cache_control: None,
+        }
+    }
+
+    /// Create an `image` block for an image the API fetches from `url`
+    pub fn image_url(url: impl Into<String>) -> Self {
+        Content::image(ImageSource::url(url))
+    }
+
+    /// Create a `document` block
+    ///
+    /// Use `with_title`, `with_context` and `with_citations` to describe it.
+    pub fn document(source: DocumentSource) -> Self {
+        Content::Document {
+            source,
+            title: None,
+            context: None,
+            citations: None,
+            cache_control: None,
+        }
+    }
+
+    /// Create a `tool_use` block
+    pub fn tool_use(id: impl Into<String>, name: impl Into<String>, input: serde_json::Value) -> Self {
+        Content::ToolUse {
+            id: id.into(),
+            name: name.into(),
+            input,
+            cache_control: None,
+        }
+    }
+
+    /// Create a successful `tool_result` block for the given tool call
+    pub fn tool_result(tool_use_id: impl Into<String>, content: impl Into<ToolResultContent>) -> Self {
+        Content::ToolResult {
+            tool_use_id: tool_use_id.into(),
+            content: Some(content.into()),
+            is_error: false,
+            cache_control: None,
+        }
+    }
+
+    /// Create a `tool_result` block reporting that the tool call failed
+    pub fn tool_error(tool_use_id: impl Into<String>, message: impl Into<String>) -> Self {
+        Content::ToolResult {
+            tool_use_id: tool_use_id.into(),
+            content: Some(ToolResultContent::Text(message.into())),
+            is_error: true,
+            cache_control: None,
+        }
+    }
+
+    /// Mark this block as the end of a cacheable prompt prefix
+    ///
+    /// Everything in the request up to and including this block (tools, system
+    /// prompt and earlier messages) is cached for reuse by later requests.
+    /// Thinking blocks cannot carry a breakpoint and are returned unchanged.
+    pub fn with_cache_control(mut self, cache_control: CacheControl) -> Self {
+        match &mut self {
+            Content::Text { cache_control: slot, .. }
+            | Content::Image { cache_control: slot, .. }
+            | Content::Document { cache_control: slot, .. }
+            | Content::ToolUse { cache_control: slot, .. }
+            | Content::ToolResult { cache_control: slot, .. } => *slot = Some(cache_control),
+            Content::Thinking { .. } | Content::RedactedThinking { .. } => {}
+        }
+        self
+    }
+
+    /// Set the title of a document block
+    ///
+    /// Other blocks are returned unchanged.
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        if let Content::Document { title: slot, .. } = &mut self {
+            *slot = Some(title.into());
+        }
+        self
+    }
+
+    /// Set context for a document block, which the model reads but does not cite
+    ///
+    /// Other blocks are returned unchanged.
+    pub fn with_context(mut self, context: impl Into<String>) -> Self {
+        if let Content::Document { context: slot, .. } = &mut self {
+            *slot = Some(context.into());
+        }
+        self
+    }
+
+    /// Allow the model to cite a document block
+    ///
+    /// Cited passages are returned in the `citations` of the response's text
+    /// blocks. Other blocks are returned unchanged.
+    pub fn with_citations(mut self, enabled: bool) -> Self {
+        if let Content::Document { citations, .. } = &mut self {
+            *citations = Some(CitationsConfig { enabled });
+        }
+        self
+    }
+
+    /// Get the citations of a text block, if any
+    pub fn citations(&self) -> &[Citation] {
+        match self {
+            Content::Text { citations: Some(citations), .. } => citations,
+            _ => &[],
+        }
+    }
+
+    /// Get the cache breakpoint set on this block, if any
+    pub fn cache_control(&self) -> Option<&CacheControl> {
+        match self {
+            Content::Text { cache_control, .. }
+            | Content::Image { cache_control, .. }
+            | Content::Document { cache_control, .. }
+            | Content::ToolUse { cache_control, .. }
+            | Content::ToolResult { cache_control, .. } => cache_control.as_ref(),
+            Content::Thinking { .. } | Content::RedactedThinking { .. } => None,
+        }
+    }
+
+    /// Check whether this is a `thinking` or `redacted_thinking` block
+    pub fn is_thinking(&self) -> bool {
+        matches!(self, Content::Thinking { .. } | Content::RedactedThinking { .. })
+    }
+
+    /// Check whether this is an image, or a tool result that contains one
+    pub fn has_image(&self) -> bool {
+        match self {
+            Content::Image { .. } => true,
+            Content::ToolResult { content: Some(ToolResultContent::Blocks(blocks)), .. } => {
+                blocks.iter().any(|block| matches!(block, ToolResultBlock::Image { .. }))
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Smallest thinking budget the API accepts
+pub const MIN_THINKING_BUDGET_TOKENS: u32 = 1024;
+
+/// Extended thinking configuration for a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ThinkingConfig {
+    /// Let the model reason before answering, using up to `budget_tokens`
+    /// of the request's `max_tokens` for thinking
+    Enabled { budget_tokens: u32 },
+    Disabled,
+}
+
+impl ThinkingConfig {
+    /// Enable thinking with the given token budget
+    pub fn enabled(budget_tokens: u32) -> Self {
+        ThinkingConfig::Enabled { budget_tokens }
+    }
+
+    /// Get the thinking budget, if thinking is enabled
+    pub fn budget_tokens(&self) -> Option<u32> {
+        match self {
+            ThinkingConfig::Enabled { budget_tokens } => Some(*budget_tokens),
+            ThinkingConfig::Disabled => None,
+        }
+    }
+}
+
+/// A prompt caching breakpoint
+///
+/// Placed on a tool definition, system block or content block, it marks the
+/// end of a prompt prefix that the API caches and reuses for later requests
+/// starting with the same prefix.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CacheControl {
+    /// A short-lived cache entry, refreshed each time it is read
+    Ephemeral {
+        /// How long the entry lives; the API default is five minutes
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        ttl: Option<CacheTtl>,
+    },
+}
+
+impl CacheControl {
+    /// An ephemeral breakpoint with the default five minute lifetime
+    pub fn ephemeral() -> Self {
+        CacheControl::Ephemeral { ttl: None }
+    }
+
+    /// An ephemeral breakpoint with an explicit lifetime
+    pub fn ephemeral_with_ttl(ttl: CacheTtl) -> Self {
+        CacheControl::Ephemeral { ttl: Some(ttl) }
+    }
 }
 
+/// Lifetime of an ephemeral cache entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CacheTtl {
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "1h")]
+    OneHour,
+}
+
+/// A block of the system prompt
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename = "text")]
+pub struct SystemBlock {
+    pub text: String,
+    /// Marks the end of a cacheable prompt prefix
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<CacheControl>,
+}
+
+impl SystemBlock {
+    /// Create a system block without a cache breakpoint
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            cache_control: None,
+        }
+    }
+
+    /// Mark this block as the end of a cacheable prompt prefix
+    pub fn with_cache_control(mut self, cache_control: CacheControl) -> Self {
+        self.cache_control = Some(cache_control);
+        self
+    }
+}
+
+/// The system prompt of a request
+///
+/// Sent as a plain string, or as a list of text blocks when the prompt has
+/// several parts or a block needs a cache breakpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SystemPrompt {
+    Text(String),
+    Blocks(Vec<SystemBlock>),
+}
+
+impl SystemPrompt {
+    /// Get the full text of the system prompt, with blocks joined by blank lines
+    pub fn text(&self) -> String {
+        match self {
+            SystemPrompt::Text(text) => text.clone(),
+            SystemPrompt::Blocks(blocks) => blocks.iter()
+                .map(|block| block.text.as_str())
+                .collect::<Vec<_>>()
+                .join("\n\n"),
+        }
+    }
+
+    /// Convert the system prompt into blocks, turning a plain string into a single block
+    pub fn into_blocks(self) -> Vec<SystemBlock> {
+        match self {
+            SystemPrompt::Text(text) => vec![SystemBlock::new(text)],
+            SystemPrompt::Blocks(blocks) => blocks,
+        }
+    }
+}
+
+impl fmt::Display for SystemPrompt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text())
+    }
+}
+
+impl From<String> for SystemPrompt {
+    fn from(text: String) -> Self {
+        SystemPrompt::Text(text)
+    }
+}
+
+impl From<&str> for SystemPrompt {
+    fn from(text: &str) -> Self {
+        SystemPrompt::Text(text.to_string())
+    }
+}
+
+impl From<Vec<SystemBlock>> for SystemPrompt {
+    fn from(blocks: Vec<SystemBlock>) -> Self {
+        SystemPrompt::Blocks(blocks)
+    }
+}
+
+/// Source of an image content block
+///
+/// Use `Image` to build a base64 source from image data, with the media type
+/// detected and the API's limits checked.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ImageSource {
-    #[serde(rename = "type")]
-    pub source_type: String,
-    pub media_type: String,
-    pub data: String,
+#[serde(tag = "type")]
+#[serde(rename_all = "snake_case")]
+pub enum ImageSource {
+    /// Base64 encoded image data
+    Base64 {
+        /// MIME type of the image, such as `image/png`
+        media_type: String,
+        data: String,
+    },
+    /// An image the API fetches from a URL
+    Url { url: String },
+}
+
+impl ImageSource {
+    /// Create a source from base64 encoded image data
+    pub fn base64(media_type: impl Into<String>, data: impl Into<String>) -> Self {
+        ImageSource::Base64 {
+            media_type: media_type.into(),
+            data: data.into(),
+        }
+    }
+
+    /// Create a source for an image the API fetches from `url`
+    pub fn url(url: impl Into<String>) -> Self {
+        ImageSource::Url { url: url.into() }
+    }
 }
 
+/// Source of a document content block
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ToolUse {
-    pub id: String,
-    pub name: String,
-    pub parameters: serde_json::Value,
+#[serde(tag = "type")]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentSource {
+    /// Base64 encoded PDF data
+    Base64 {
+        /// Always `application/pdf`
+        media_type: String,
+        data: String,
+    },
+    /// Plain text, cited by character ranges
+    Text {
+        /// Always `text/plain`
+        media_type: String,
+        data: String,
+    },
+    /// Custom chunks of content, cited by block ranges
+    Content { content: Vec<Content> },
+}
+
+impl DocumentSource {
+    /// Create a source from the bytes of a PDF file
+    pub fn pdf(data: &[u8]) -> Self {
+        Self::pdf_base64(BASE64.encode(data))
+    }
+
+    /// Create a source from base64 encoded PDF data
+    pub fn pdf_base64(data: impl Into<String>) -> Self {
+        DocumentSource::Base64 {
+            media_type: "application/pdf".to_string(),
+            data: data.into(),
+        }
+    }
+
+    /// Create a source from plain text
+    pub fn text(text: impl Into<String>) -> Self {
+        DocumentSource::Text {
+            media_type: "text/plain".to_string(),
+            data: text.into(),
+        }
+    }
+
+    /// Create a source from chunks of text, which citations refer to by index
+    pub fn content(chunks: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        DocumentSource::Content {
+            content: chunks.into_iter().map(Content::text).collect(),
+        }
+    }
+}
+
+/// Citation settings of a document content block
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CitationsConfig {
+    pub enabled: bool,
 }
 
+/// A passage of a document that supports part of the model's answer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Citation {
+    /// The text of the cited passage
+    #[serde(default)]
+    pub cited_text: String,
+    /// Position of the cited document among the request's documents, starting at 0
+    #[serde(default)]
+    pub document_index: u32,
+    /// Title of the cited document, if it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document_title: Option<String>,
+    /// Where the passage is in the document
+    #[serde(flatten)]
+    pub location: CitationLocation,
+}
+
+/// Location of a cited passage, depending on the kind of document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+#[serde(rename_all = "snake_case")]
+pub enum CitationLocation {
+    /// Character range of a plain text document, end exclusive, starting at 0
+    CharLocation { start_char_index: u32, end_char_index: u32 },
+    /// Page range of a PDF document, end exclusive, starting at 1
+    PageLocation { start_page_number: u32, end_page_number: u32 },
+    /// Range of blocks of a custom content document, end exclusive, starting at 0
+    ContentBlockLocation { start_block_index: u32, end_block_index: u32 },
+    /// A location type this version of the SDK does not know
+    #[serde(other)]
+    Unknown,
+}
+
+/// Content of a `tool_result` block
+///
+/// The API accepts either a plain string or a list of content blocks.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ToolResult {
-    pub content: String,
+#[serde(untagged)]
+pub enum ToolResultContent {
+    Text(String),
+    Blocks(Vec<ToolResultBlock>),
+}
+
+/// A content block allowed inside a `tool_result`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+#[serde(rename_all = "snake_case")]
+pub enum ToolResultBlock {
+    Text { text: String },
+    Image { source: ImageSource },
+}
+
+impl From<String> for ToolResultContent {
+    fn from(text: String) -> Self {
+        ToolResultContent::Text(text)
+    }
+}
+
+impl From<&str> for ToolResultContent {
+    fn from(text: &str) -> Self {
+        ToolResultContent::Text(text.to_string())
+    }
+}
+
+impl From<Vec<ToolResultBlock>> for ToolResultContent {
+    fn from(blocks: Vec<ToolResultBlock>) -> Self {
+        ToolResultContent::Blocks(blocks)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -242,6 +916,98 @@ pub struct Tool {
     pub name: String,
     pub description: String,
     pub input_schema: serde_json::Value,
+    /// Marks the end of a cacheable prompt prefix
+    ///
+    /// Tools come first in the prompt, so a breakpoint on the last tool caches
+    /// every tool definition.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_control: Option<CacheControl>,
+}
+
+impl Tool {
+    /// Mark this tool as the end of a cacheable prompt prefix
+    pub fn with_cache_control(mut self, cache_control: CacheControl) -> Self {
+        self.cache_control = Some(cache_control);
+        self
+    }
+}
+
+/// How the model should use the tools provided in a request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+#[serde(rename_all = "snake_case")]
+pub enum ToolChoice {
+    /// The model decides whether to call any tools (the API default)
+    Auto {
+        /// Allow at most one tool call per response
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        disable_parallel_tool_use: bool,
+    },
+    /// The model must call at least one of the provided tools
+    Any {
+        /// Allow exactly one tool call per response
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        disable_parallel_tool_use: bool,
+    },
+    /// The model must call the named tool
+    Tool {
+        name: String,
+        /// Allow exactly one tool call per response
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        disable_parallel_tool_use: bool,
+    },
+    /// The model must not call any tools
+    None,
+}
+
+impl ToolChoice {
+    /// Let the model decide whether to call tools
+    pub fn auto() -> Self {
+        ToolChoice::Auto { disable_parallel_tool_use: false }
+    }
+
+    /// Require the model to call at least one tool
+    pub fn any() -> Self {
+        ToolChoice::Any { disable_parallel_tool_use: false }
+    }
+
+    /// Require the model to call the named tool
+    pub fn tool(name: impl Into<String>) -> Self {
+        ToolChoice::Tool { name: name.into(), disable_parallel_tool_use: false }
+    }
+
+    /// Prevent the model from calling tools
+    pub fn none() -> Self {
+        ToolChoice::None
+    }
+
+    /// Limit the model to a single tool call per response
+    ///
+    /// Has no effect on `ToolChoice::None`.
+    pub fn disable_parallel_tool_use(mut self) -> Self {
+        match &mut self {
+            ToolChoice::Auto { disable_parallel_tool_use }
+            | ToolChoice::Any { disable_parallel_tool_use }
+            | ToolChoice::Tool { disable_parallel_tool_use, .. } => *disable_parallel_tool_use = true,
+            ToolChoice::None => {}
+        }
+        self
+    }
+
+    /// Check whether the model is limited to a single tool call per response
+    pub fn parallel_tool_use_disabled(&self) -> bool {
+        match self {
+            ToolChoice::Auto { disable_parallel_tool_use }
+            | ToolChoice::Any { disable_parallel_tool_use }
+            | ToolChoice::Tool { disable_parallel_tool_use, .. } => *disable_parallel_tool_use,
+            ToolChoice::None => false,
+        }
+    }
+
+    /// Check whether this choice forces the model to call a tool
+    pub fn is_forced(&self) -> bool {
+        matches!(self, ToolChoice::Any { .. } | ToolChoice::Tool { .. })
+    }
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -249,7 +1015,7 @@ pub struct MessageRequest {
     pub model: String,
     pub messages: Vec<Message>,
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub system: Option<String>,
+    pub system: Option<SystemPrompt>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -257,6 +1023,10 @@ pub struct MessageRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<Vec<Tool>>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub thinking: Option<ThinkingConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub top_p: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub top_k: Option<u32>,
@@ -264,6 +1034,40 @@ pub struct MessageRequest {
     pub stop_sequences: Vec<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stream: Option<bool>,
+    /// Tag the request's spend is recorded under by a `CostTracker`; not sent to the API
+    #[serde(skip)]
+    pub cost_tag: Option<String>,
+}
+
+/// Maximum number of `cache_control` breakpoints the API accepts in one request
+pub const MAX_CACHE_BREAKPOINTS: usize = 4;
+
+impl MessageRequest {
+    /// Count the `cache_control` breakpoints on tools, system blocks and content blocks
+    pub fn cache_breakpoints(&self) -> usize {
+        let tools = self.tools.iter().flatten()
+            .filter(|tool| tool.cache_control.is_some())
+            .count();
+        let system = match &self.system {
+            Some(SystemPrompt::Blocks(blocks)) => blocks.iter()
+                .filter(|block| block.cache_control.is_some())
+                .count(),
+            _ => 0,
+        };
+        let content = self.messages.iter()
+            .flat_map(|message| &message.content)
+            .filter(|block| block.cache_control().is_some())
+            .count();
+        
+        tools + system + content
+    }
+    
+    /// Check whether any message contains an image
+    pub fn has_images(&self) -> bool {
+        self.messages.iter()
+            .flat_map(|message| &message.content)
+            .any(Content::has_image)
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -278,7 +1082,16 @@ pub struct MessageResponse {
     pub stop_sequence: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+impl MessageResponse {
+    /// Check whether the answer was cut off by the `max_tokens` limit
+    ///
+    /// See `MessageBuilder::auto_continue` to continue such answers automatically.
+    pub fn is_truncated(&self) -> bool {
+        self.stop_reason.as_deref() == Some("max_tokens")
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
 pub struct Usage {
     /// Number of input tokens - Optional in streaming final events
     #[serde(default)]
@@ -286,6 +1099,35 @@ pub struct Usage {
     /// Number of output tokens - Optional in streaming final events
     #[serde(default)]
     pub output_tokens: u32,
+    /// Number of input tokens written to the prompt cache
+    #[serde(default, deserialize_with = "null_as_zero")]
+    pub cache_creation_input_tokens: u32,
+    /// Number of input tokens read from the prompt cache
+    #[serde(default, deserialize_with = "null_as_zero")]
+    pub cache_read_input_tokens: u32,
+}
+
+/// Deserialize a token count that the API may send as `null`
+fn null_as_zero<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    Ok(Option::<u32>::deserialize(deserializer)?.unwrap_or(0))
+}
+
+impl Usage {
+    /// Get the total number of input tokens, including cache writes and reads
+    ///
+    /// `input_tokens` only counts the tokens after the last cache breakpoint.
+    pub fn total_input_tokens(&self) -> u32 {
+        self.input_tokens + self.cache_creation_input_tokens + self.cache_read_input_tokens
+    }
+}
+
+impl std::ops::AddAssign<&Usage> for Usage {
+    fn add_assign(&mut self, other: &Usage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_input_tokens += other.cache_creation_input_tokens;
+        self.cache_read_input_tokens += other.cache_read_input_tokens;
+    }
 }
 
 // Delta content for streaming response
@@ -298,6 +1140,20 @@ pub struct DeltaEvent {
     pub usage: Option<Usage>,
     // New field
     pub delta: Option<Delta>,
+    /// The block being started, for `content_block_start` events
+    ///
+    /// Block types this crate does not model are skipped rather than failing
+    /// the whole event.
+    #[serde(default, deserialize_with = "deserialize_known_content")]
+    pub content_block: Option<Content>,
+}
+
+fn deserialize_known_content<'de, D>(deserializer: D) -> Result<Option<Content>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = Option::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(value.and_then(|value| serde_json::from_value(value).ok()))
 }
 
 impl DeltaEvent {
@@ -331,7 +1187,7 @@ impl DeltaEvent {
         if let Some(msg) = &self.message {
             if let Some(contents) = &msg.content {
                 for content in contents {
-                    if let Content::Text { text } = content {
+                    if let Content::Text { text, .. } = content {
                         if !text.is_empty() {
                             return Some(text.clone());
                         }
@@ -343,6 +1199,17 @@ impl DeltaEvent {
         None
     }
     
+    /// Extract the model's reasoning from a `thinking_delta` event
+    ///
+    /// Returns None for every other event, so thinking never mixes with the
+    /// answer text returned by `to_text()`.
+    pub fn to_thinking(&self) -> Option<String> {
+        self.delta.as_ref()
+            .and_then(|delta| delta.thinking.as_ref())
+            .filter(|thinking| !thinking.is_empty())
+            .cloned()
+    }
+    
     /// Check if this is a final event (with stop_reason)
     pub fn is_final(&self) -> bool {
         // Event type-based detection (most reliable)
@@ -374,6 +1241,17 @@ pub struct Delta {
     pub stop_reason: Option<String>,
     pub stop_sequence: Option<String>,
     pub text: Option<String>,
+    /// The delta kind, such as `text_delta` or `input_json_delta`
+    #[serde(rename = "type")]
+    pub delta_type: Option<String>,
+    /// A fragment of a tool_use block's JSON input, for `input_json_delta` events
+    pub partial_json: Option<String>,
+    /// A fragment of the model's reasoning, for `thinking_delta` events
+    pub thinking: Option<String>,
+    /// The signature of a thinking block, for `signature_delta` events
+    pub signature: Option<String>,
+    /// A citation added to a text block, for `citations_delta` events
+    pub citation: Option<Citation>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -387,6 +1265,8 @@ pub struct DeltaMessage {
     pub role: Option<Role>,
     #[serde(rename = "type")]
     pub type_field: Option<String>,
+    /// Token usage so far, sent with `message_start` events
+    pub usage: Option<Usage>,
 }
 
 // Implementation of helper methods for ClaudeError
@@ -474,6 +1354,9 @@ impl ClaudeError {
             status: status.unwrap_or(500),
             response_body,
             location: location.map(String::from),
+            retry_after: None,
+            kind: ApiErrorKind::from_status(status.unwrap_or(500)),
+            request_id: None,
         };
         
         // Optional logging integration
@@ -526,6 +1409,15 @@ impl ClaudeError {
         }
     }
     
+    /// Get the server-provided retry delay, if the error carries one
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RateLimited { retry_after, .. } => *retry_after,
+            Self::ApiError { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+    
     // Legacy compat methods
     pub fn request_error_with_details<S1: Into<String>, S2: Into<String>>(
         message: S1,
@@ -585,6 +1477,7 @@ impl ClaudeError {
             retry_after,
             details: None,
             location: None,
+            request_id: None,
         }
     }
     
@@ -596,7 +1489,152 @@ impl ClaudeError {
             retry_after,
             details: Some(details.into()),
             location: None,
+            request_id: None,
+        }
+    }
+    
+    /// Build an error from an API error response
+    ///
+    /// Parses the `{"type":"error","error":{"type":...,"message":...}}` envelope
+    /// into a typed `ApiErrorKind`. If the body is not a valid envelope, the kind
+    /// is derived from the status code and the sanitized body is used as message.
+    /// A 429 status produces `RateLimited`, everything else `ApiError`.
+    pub fn from_api_response(
+        status: u16,
+        body: &str,
+        request_id: Option<String>,
+        retry_after: Option<Duration>,
+        location: Option<&str>,
+    ) -> Self {
+        let parsed = serde_json::from_str::<ApiErrorResponse>(body).ok();
+        
+        if status == 429 {
+            return Self::RateLimited {
+                retry_after,
+                details: parsed.map(|p| sanitize_error_message(&p.error.message)),
+                location: location.map(String::from),
+                request_id,
+            };
         }
+        
+        let (kind, message) = match parsed {
+            Some(response) => (response.error.kind, sanitize_error_message(&response.error.message)),
+            None => (ApiErrorKind::from_status(status), sanitize_error_message(body)),
+        };
+        
+        let error = Self::ApiError {
+            status,
+            message,
+            response_body: Some(sanitize_error_message(body)),
+            location: location.map(String::from),
+            retry_after,
+            kind,
+            request_id,
+        };
+        
+        if let Some(loc) = &error.location() {
+            log::error!("{} at {}", error, loc);
+        } else {
+            log::error!("{}", error);
+        }
+        
+        error
+    }
+    
+    /// Build an error from an `error` event received in the middle of a stream
+    ///
+    /// Streamed errors have no HTTP status of their own, so the status is
+    /// derived from the error kind.
+    pub fn from_stream_error(detail: ApiErrorDetail, location: Option<&str>) -> Self {
+        let status = detail.kind.status_code().unwrap_or(500);
+        
+        if detail.kind == ApiErrorKind::RateLimitError {
+            return Self::RateLimited {
+                retry_after: None,
+                details: Some(sanitize_error_message(&detail.message)),
+                location: location.map(String::from),
+                request_id: None,
+            };
+        }
+        
+        Self::ApiError {
+            status,
+            message: sanitize_error_message(&detail.message),
+            response_body: None,
+            location: location.map(String::from),
+            retry_after: None,
+            kind: detail.kind,
+            request_id: None,
+        }
+    }
+    
+    /// Get the typed API error kind, if this error came from the API
+    pub fn api_error_kind(&self) -> Option<ApiErrorKind> {
+        match self {
+            Self::ApiError { kind, .. } => Some(*kind),
+            Self::RateLimited { .. } => Some(ApiErrorKind::RateLimitError),
+            _ => None,
+        }
+    }
+    
+    /// Get the HTTP status code, if this error came from an API response
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::ApiError { status, .. } => Some(*status),
+            Self::RateLimited { .. } => Some(429),
+            _ => None,
+        }
+    }
+    
+    /// Get the `request-id` reported by the API, useful when contacting support
+    pub fn request_id(&self) -> Option<&str> {
+        match self {
+            Self::ApiError { request_id, .. } => request_id.as_deref(),
+            Self::RateLimited { request_id, .. } => request_id.as_deref(),
+            _ => None,
+        }
+    }
+    
+    /// Check whether the error is transient and the request may succeed if retried
+    ///
+    /// This is the decision of the default [`RetryPolicy`](crate::RetryPolicy):
+    /// rate limits, the statuses in
+    /// [`DEFAULT_RETRY_STATUSES`](crate::DEFAULT_RETRY_STATUSES), request
+    /// timeouts and connection failures are considered retryable.
+    pub fn is_retryable(&self) -> bool {
+        crate::RetryPolicy::default().should_retry(self)
+    }
+    
+    /// Check whether the error is caused by a missing, invalid or unauthorized API key
+    pub fn is_auth_failure(&self) -> bool {
+        match self {
+            Self::MissingApiKey { .. } => true,
+            Self::ApiError { status, kind, .. } => {
+                matches!(kind, ApiErrorKind::AuthenticationError | ApiErrorKind::PermissionError)
+                    || matches!(status, 401 | 403)
+            }
+            _ => false,
+        }
+    }
+    
+    /// Check whether the request was stopped by its cancellation token
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, Self::Cancelled)
+    }
+    
+    /// Check whether a stream ended because it stopped sending events
+    pub fn is_stream_timeout(&self) -> bool {
+        matches!(self, Self::StreamTimeout { .. })
+    }
+    
+    /// Check whether the error is a rate limit
+    pub fn is_rate_limited(&self) -> bool {
+        self.api_error_kind() == Some(ApiErrorKind::RateLimitError)
+    }
+    
+    /// Check whether the API reported that it is overloaded
+    pub fn is_overloaded(&self) -> bool {
+        self.api_error_kind() == Some(ApiErrorKind::OverloadedError)
     }
 }
 
@@ -70,7 +70,7 @@ pub fn extract_from_response(response: &MessageResponse) -> Result<String, Claud
 /// and extracts the content inside.
 fn extract_from_code_block(response: &MessageResponse) -> Result<String, ClaudeError> {
     for content in &response.content {
-        if let Content::Text { text } = content {
+        if let Content::Text { text, .. } = content {
             if let Some(captures) = CODE_BLOCK_REGEX.captures(text) {
                 return Ok(captures[1].to_string());
             }
@@ -90,7 +90,7 @@ fn extract_from_code_block(response: &MessageResponse) -> Result<String, ClaudeE
 /// 2. If not found, tries to find patterns that match JSON arrays: [...]
 fn extract_from_json_object(response: &MessageResponse) -> Result<String, ClaudeError> {
     for content in &response.content {
-        if let Content::Text { text } = content {
+        if let Content::Text { text, .. } = content {
             // Try to find JSON objects first (more common)
             if let Some(json_match) = JSON_OBJECT_REGEX.find(text) {
                 return Ok(json_match.as_str().to_string());
@@ -116,7 +116,7 @@ fn extract_from_json_object(response: &MessageResponse) -> Result<String, Claude
 /// This is useful when Claude returns clean JSON without any preamble or code blocks.
 fn extract_raw_text(response: &MessageResponse) -> Result<String, ClaudeError> {
     for content in &response.content {
-        if let Content::Text { text } = content {
+        if let Content::Text { text, .. } = content {
             let trimmed = text.trim();
             // Simple heuristic to check if the entire content might be JSON
             if trimmed.starts_with('{') || trimmed.starts_with('[') {
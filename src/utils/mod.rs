@@ -1,6 +1,7 @@
 // Utility functions
 
 pub mod json_extractor;
+pub mod sse;
 pub mod token_counter;
 
 use crate::types::*;
@@ -0,0 +1,325 @@
+//! Incremental Server-Sent Events decoding
+//!
+//! This module provides a stateful decoder for `text/event-stream` bodies as
+//! described in the WHATWG HTML specification. Bytes can be fed in arbitrary
+//! chunks: partial lines, events split across chunks and multi-byte UTF-8
+//! characters split across chunks are all buffered until they are complete.
+//!
+//! The decoder handles:
+//! 1. `LF`, `CRLF` and bare `CR` line endings (including a `CRLF` split across chunks)
+//! 2. `event`, `data`, `id` and `retry` fields, with multi-line `data`
+//! 3. Comment lines starting with `:` (used by servers as keep-alives)
+//! 4. A leading UTF-8 byte order mark
+
+use std::time::Duration;
+
+/// Default event type used when an event has no `event:` field
+pub const DEFAULT_EVENT_TYPE: &str = "message";
+
+/// A single decoded Server-Sent Event
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    /// The event type (`message` if the stream did not specify one)
+    pub event: String,
+    /// The event data, with multiple `data:` lines joined by `\n`
+    pub data: String,
+    /// The last event ID seen on the stream when this event was dispatched
+    pub id: Option<String>,
+}
+
+/// Stateful decoder that turns a byte stream into Server-Sent Events
+///
+/// ## Example
+///
+/// ```
+/// use claude_rs::utils::sse::SseDecoder;
+///
+/// let mut decoder = SseDecoder::new();
+///
+/// // The event is split across two chunks
+/// assert!(decoder.decode(b"event: ping\nda").is_empty());
+/// let events = decoder.decode(b"ta: {}\n\n");
+///
+/// assert_eq!(events.len(), 1);
+/// assert_eq!(events[0].event, "ping");
+/// assert_eq!(events[0].data, "{}");
+/// ```
+#[derive(Debug, Default)]
+pub struct SseDecoder {
+    /// Bytes of the current, not yet terminated line
+    buffer: Vec<u8>,
+    /// Event type for the event being assembled
+    event_type: Option<String>,
+    /// Data for the event being assembled
+    data: String,
+    /// Whether any `data` field was seen for the event being assembled
+    has_data: bool,
+    /// Last event ID seen on the stream
+    last_event_id: Option<String>,
+    /// Reconnection time requested by the server
+    retry: Option<Duration>,
+    /// Whether the previous chunk ended in `\r`, so a leading `\n` must be skipped
+    pending_cr: bool,
+    /// Whether the first line has been processed (for BOM stripping)
+    started: bool,
+}
+
+impl SseDecoder {
+    /// Create a new decoder with empty state
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed a chunk of bytes and return all events completed by it
+    pub fn decode(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+        let mut bytes = chunk;
+
+        // Finish a CRLF whose CR arrived at the end of the previous chunk; an
+        // empty chunk leaves it pending
+        if self.pending_cr && !bytes.is_empty() {
+            self.pending_cr = false;
+            if let Some(rest) = bytes.strip_prefix(b"\n") {
+                bytes = rest;
+            }
+        }
+
+        while let Some(pos) = bytes.iter().position(|&b| b == b'\n' || b == b'\r') {
+            self.buffer.extend_from_slice(&bytes[..pos]);
+
+            let terminator_len = if bytes[pos] == b'\r' {
+                match bytes.get(pos + 1) {
+                    Some(b'\n') => 2,
+                    Some(_) => 1,
+                    None => {
+                        self.pending_cr = true;
+                        1
+                    }
+                }
+            } else {
+                1
+            };
+            bytes = &bytes[pos + terminator_len..];
+
+            let line = std::mem::take(&mut self.buffer);
+            if let Some(event) = self.process_line(&line) {
+                events.push(event);
+            }
+        }
+
+        self.buffer.extend_from_slice(bytes);
+        events
+    }
+
+    /// The last event ID received from the server, if any
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.last_event_id.as_deref()
+    }
+
+    /// The reconnection time requested by the server via a `retry` field, if any
+    pub fn retry(&self) -> Option<Duration> {
+        self.retry
+    }
+
+    /// Check whether the decoder holds an incomplete line or event
+    ///
+    /// Per the specification, an event that is not terminated by a blank line
+    /// when the stream ends is discarded.
+    pub fn has_pending(&self) -> bool {
+        !self.buffer.is_empty() || self.has_data || self.event_type.is_some()
+    }
+
+    /// Process one complete line, returning an event if the line dispatched one
+    fn process_line(&mut self, line: &[u8]) -> Option<SseEvent> {
+        // Lines are split on ASCII terminators, so a complete line never ends
+        // inside a multi-byte character
+        let decoded = String::from_utf8_lossy(line);
+        let mut line: &str = &decoded;
+
+        if !self.started {
+            self.started = true;
+            line = line.strip_prefix('\u{feff}').unwrap_or(line);
+        }
+
+        if line.is_empty() {
+            return self.dispatch();
+        }
+
+        if line.starts_with(':') {
+            // Comment line
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (line, ""),
+        };
+
+        match field {
+            "event" => self.event_type = Some(value.to_string()),
+            "data" => {
+                if self.has_data {
+                    self.data.push('\n');
+                }
+                self.data.push_str(value);
+                self.has_data = true;
+            }
+            "id" if !value.contains('\0') => self.last_event_id = Some(value.to_string()),
+            "retry" if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) => {
+                if let Ok(millis) = value.parse::<u64>() {
+                    self.retry = Some(Duration::from_millis(millis));
+                }
+            }
+            _ => {
+                // Unknown fields and invalid id/retry values are ignored
+            }
+        }
+
+        None
+    }
+
+    /// Dispatch the event being assembled, if it has any data
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event_type = self.event_type.take();
+
+        if !self.has_data {
+            return None;
+        }
+
+        self.has_data = false;
+        Some(SseEvent {
+            event: event_type
+                .filter(|t| !t.is_empty())
+                .unwrap_or_else(|| DEFAULT_EVENT_TYPE.to_string()),
+            data: std::mem::take(&mut self.data),
+            id: self.last_event_id.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_all(decoder: &mut SseDecoder, chunks: &[&[u8]]) -> Vec<SseEvent> {
+        chunks.iter().flat_map(|chunk| decoder.decode(chunk)).collect()
+    }
+
+    #[test]
+    fn test_single_event() {
+        let mut decoder = SseDecoder::new();
+        let events = decoder.decode(b"event: message_start\ndata: {\"a\":1}\n\n");
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, "message_start");
+        assert_eq!(events[0].data, "{\"a\":1}");
+        assert!(!decoder.has_pending());
+    }
+
+    #[test]
+    fn test_event_split_across_chunks() {
+        let mut decoder = SseDecoder::new();
+        let events = decode_all(&mut decoder, &[b"eve", b"nt: ping\nda", b"ta: {}", b"\n", b"\n"]);
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].event, "ping");
+        assert_eq!(events[0].data, "{}");
+    }
+
+    #[test]
+    fn test_multibyte_character_split_across_chunks() {
+        let text = "data: こんにちは\n\n".as_bytes();
+        // Split inside the first three-byte character
+        let (first, second) = text.split_at(7);
+
+        let mut decoder = SseDecoder::new();
+        let events = decode_all(&mut decoder, &[first, second]);
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "こんにちは");
+    }
+
+    #[test]
+    fn test_multi_line_data() {
+        let mut decoder = SseDecoder::new();
+        let events = decoder.decode(b"data: first\ndata: second\ndata\n\n");
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "first\nsecond\n");
+        assert_eq!(events[0].event, DEFAULT_EVENT_TYPE);
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let mut decoder = SseDecoder::new();
+        let events = decode_all(&mut decoder, &[b"data: crlf\r\n\r\ndata: cr\r\rdata: split\r", b"\n\r", b"\n"]);
+
+        let data: Vec<&str> = events.iter().map(|e| e.data.as_str()).collect();
+        assert_eq!(data, vec!["crlf", "cr", "split"]);
+    }
+
+    #[test]
+    fn test_empty_chunk_after_trailing_cr() {
+        let mut decoder = SseDecoder::new();
+        let events = decode_all(&mut decoder, &[b"data: x\r", b"", b"\n\n"]);
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "x");
+
+        // The LF completing the CRLF is not read as a blank line
+        let mut decoder = SseDecoder::new();
+        assert!(decode_all(&mut decoder, &[b"data: x\r", b"", b"\n"]).is_empty());
+        assert_eq!(decoder.decode(b"\n").len(), 1);
+    }
+
+    #[test]
+    fn test_comments_and_unknown_fields_are_ignored() {
+        let mut decoder = SseDecoder::new();
+        let events = decoder.decode(b": keep-alive\nfoo: bar\ndata: value\n\n");
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "value");
+    }
+
+    #[test]
+    fn test_id_and_retry_fields() {
+        let mut decoder = SseDecoder::new();
+        let events = decoder.decode(b"id: 42\nretry: 1500\ndata: one\n\ndata: two\n\nretry: soon\n\n");
+
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].id.as_deref(), Some("42"));
+        // The last event ID persists across events
+        assert_eq!(events[1].id.as_deref(), Some("42"));
+        assert_eq!(decoder.last_event_id(), Some("42"));
+        // Non-numeric retry values are ignored
+        assert_eq!(decoder.retry(), Some(Duration::from_millis(1500)));
+    }
+
+    #[test]
+    fn test_event_without_data_is_not_dispatched() {
+        let mut decoder = SseDecoder::new();
+        let events = decoder.decode(b"event: empty\n\ndata: x\n\n");
+
+        assert_eq!(events.len(), 1);
+        // The event type is reset after the blank line
+        assert_eq!(events[0].event, DEFAULT_EVENT_TYPE);
+    }
+
+    #[test]
+    fn test_value_without_space_and_bom() {
+        let mut decoder = SseDecoder::new();
+        let events = decoder.decode("\u{feff}data:no-space\n\n".as_bytes());
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].data, "no-space");
+    }
+
+    #[test]
+    fn test_unterminated_event_is_pending() {
+        let mut decoder = SseDecoder::new();
+        let events = decoder.decode(b"data: partial\n");
+
+        assert!(events.is_empty());
+        assert!(decoder.has_pending());
+    }
+}
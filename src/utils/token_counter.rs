@@ -5,6 +5,10 @@
 //! usage by providing accurate token counts for messages.
 
 use crate::types::*;
+use crate::images::estimate_source_tokens;
+use async_trait::async_trait;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
 use lazy_static::lazy_static;
 use std::sync::Arc;
 use tiktoken_rs::{CoreBPE, cl100k_base, r50k_base};
@@ -14,6 +18,21 @@ lazy_static! {
     static ref R50K_BPE: CoreBPE = r50k_base().unwrap();
 }
 
+/// Estimated tokens of one PDF page, which is read both as text and as an image
+const PDF_PAGE_TOKENS: u32 = 2500;
+
+lazy_static! {
+    static ref PDF_PAGE: regex::bytes::Regex = regex::bytes::Regex::new(r"/Type\s*/Page\b").unwrap();
+}
+
+/// Estimate the tokens of a base64 encoded PDF from the number of pages it declares
+fn estimate_pdf_tokens(data: &str) -> u32 {
+    let pages = BASE64.decode(data)
+        .map(|pdf| PDF_PAGE.find_iter(&pdf).count() as u32)
+        .unwrap_or(0);
+    pages.max(1) * PDF_PAGE_TOKENS
+}
+
 /// Trait for token counting strategies
 pub trait TokenCounter: Send + Sync {
     /// Count tokens in a text string
@@ -25,24 +44,59 @@ pub trait TokenCounter: Send + Sync {
         
         for content in &message.content {
             match content {
-                Content::Text { text } => {
+                Content::Text { text, .. } => {
                     total += self.count_tokens(text);
                 }
-                Content::Image { .. } => {
-                    // For images, use a conservative estimate based on Claude's image token pricing
-                    // This is a placeholder and should be refined based on actual usage patterns
-                    total += 1024; // Conservative estimate for typical image
+                Content::Image { source, .. } => {
+                    // Estimated from the image's dimensions, or the maximum when they are unknown
+                    total += estimate_source_tokens(source);
                 }
-                Content::Tool { tool_use } => {
-                    // Count tokens in the JSON representation of the tool use
-                    if let Ok(json) = serde_json::to_string(&tool_use) {
-                        total += self.count_tokens(&json);
+                Content::Document { source, title, context, .. } => {
+                    // Count the document's text, with PDFs estimated from their page count
+                    for text in title.iter().chain(context) {
+                        total += self.count_tokens(text);
                     }
+                    total += match source {
+                        DocumentSource::Base64 { data, .. } => estimate_pdf_tokens(data),
+                        DocumentSource::Text { data, .. } => self.count_tokens(data),
+                        DocumentSource::Content { content } => content.iter()
+                            .map(|block| match block {
+                                Content::Text { text, .. } => self.count_tokens(text),
+                                Content::Image { source, .. } => estimate_source_tokens(source),
+                                _ => 0,
+                            })
+                            .sum(),
+                    };
+                }
+                Content::ToolUse { id, name, input, .. } => {
+                    // Count tokens in the tool call ID, name and JSON input
+                    total += self.count_tokens(id);
+                    total += self.count_tokens(name);
+                    total += self.count_tokens(&input.to_string());
+                }
+                Content::ToolResult { tool_use_id, content, .. } => {
+                    // Count tokens in the call ID and each part of the result
+                    total += self.count_tokens(tool_use_id);
+                    match content {
+                        Some(ToolResultContent::Text(text)) => {
+                            total += self.count_tokens(text);
+                        }
+                        Some(ToolResultContent::Blocks(blocks)) => {
+                            for block in blocks {
+                                total += match block {
+                                    ToolResultBlock::Text { text } => self.count_tokens(text),
+                                    ToolResultBlock::Image { source } => estimate_source_tokens(source),
+                                };
+                            }
+                        }
+                        None => {}
+                    }
+                }
+                Content::Thinking { thinking, .. } => {
+                    total += self.count_tokens(thinking);
                 }
-                Content::ToolResult { tool_result, tool_call_id } => {
-                    // Count tokens in tool results and call ID
-                    total += self.count_tokens(&tool_result.content);
-                    total += self.count_tokens(tool_call_id);
+                Content::RedactedThinking { .. } => {
+                    // Encrypted reasoning has no readable text to count
                 }
             }
         }
@@ -57,6 +111,53 @@ pub trait TokenCounter: Send + Sync {
     fn count_messages_tokens(&self, messages: &[Message]) -> u32 {
         messages.iter().map(|msg| self.count_message_tokens(msg)).sum()
     }
+    
+    /// Count tokens in a system prompt
+    fn count_system_tokens(&self, system: &SystemPrompt) -> u32 {
+        match system {
+            SystemPrompt::Text(text) => self.count_tokens(text),
+            SystemPrompt::Blocks(blocks) => blocks.iter()
+                .map(|block| self.count_tokens(&block.text))
+                .sum(),
+        }
+    }
+}
+
+impl<T: TokenCounter + ?Sized> TokenCounter for Arc<T> {
+    fn count_tokens(&self, text: &str) -> u32 {
+        (**self).count_tokens(text)
+    }
+    
+    fn count_message_tokens(&self, message: &Message) -> u32 {
+        (**self).count_message_tokens(message)
+    }
+    
+    fn count_system_tokens(&self, system: &SystemPrompt) -> u32 {
+        (**self).count_system_tokens(system)
+    }
+}
+
+/// Trait for token counting backends that may need to wait, such as the API
+///
+/// Every `TokenCounter` is also an `AsyncTokenCounter` that never fails.
+#[async_trait]
+pub trait AsyncTokenCounter: Send + Sync {
+    /// Count tokens in a message
+    async fn message_tokens(&self, message: &Message) -> ClaudeResult<u32>;
+    
+    /// Count tokens in a system prompt
+    async fn system_tokens(&self, system: &SystemPrompt) -> ClaudeResult<u32>;
+}
+
+#[async_trait]
+impl<T: TokenCounter + ?Sized> AsyncTokenCounter for T {
+    async fn message_tokens(&self, message: &Message) -> ClaudeResult<u32> {
+        Ok(self.count_message_tokens(message))
+    }
+    
+    async fn system_tokens(&self, system: &SystemPrompt) -> ClaudeResult<u32> {
+        Ok(self.count_system_tokens(system))
+    }
 }
 
 /// Claude 3 token counter using cl100k_base tokenizer
@@ -83,19 +184,18 @@ pub struct SimpleTokenCounter;
 impl TokenCounter for SimpleTokenCounter {
     fn count_tokens(&self, text: &str) -> u32 {
         // Approximate tokens as 4 characters per token
-        (text.len() as u32 + 3) / 4
+        (text.len() as u32).div_ceil(4)
     }
 }
 
 /// Get an appropriate token counter for the specified model
+///
+/// Models in the `ModelRegistry`, including `Custom` ids of known models, use
+/// the Claude 3 tokenizer; unknown models fall back to an approximation.
 pub fn get_token_counter(model: &ClaudeModel) -> Arc<dyn TokenCounter> {
-    match model {
-        ClaudeModel::Opus | 
-        ClaudeModel::Sonnet |
-        ClaudeModel::Haiku |
-        ClaudeModel::Sonnet35 |
-        ClaudeModel::Sonnet37 => Arc::new(Claude3TokenCounter),
-        ClaudeModel::Custom(_) => Arc::new(SimpleTokenCounter),
+    match model.info() {
+        Some(_) => Arc::new(Claude3TokenCounter),
+        None => Arc::new(SimpleTokenCounter),
     }
 }
 
@@ -127,10 +227,29 @@ mod tests {
         let counter = Claude3TokenCounter;
         let message = Message {
             role: Role::User,
-            content: vec![Content::Text { text: "Hello, world!".to_string() }],
+            content: vec![Content::text("Hello, world!".to_string())],
         };
         
         let tokens = counter.count_message_tokens(&message);
         assert!(tokens > 0);
     }
-}
\ No newline at end of file
+    
+    #[test]
+    fn test_tool_block_token_counting() {
+        let counter = SimpleTokenCounter;
+        let message = Message {
+            role: Role::User,
+            content: vec![Content::ToolResult {
+                tool_use_id: "toolu_01".to_string(),
+                content: Some(ToolResultContent::Blocks(vec![
+                    ToolResultBlock::Text { text: "22 degrees and sunny".to_string() },
+                ])),
+                is_error: false,
+                cache_control: None,
+            }],
+        };
+        
+        // 2 tokens for the ID, 5 for the text and 4 for message overhead
+        assert_eq!(counter.count_message_tokens(&message), 11);
+    }
+}
@@ -28,12 +28,12 @@ impl TestMockApiHandler {
                 model: "claude-3-sonnet-20240229".to_string(),
                 r#type: "message".to_string(),
                 role: Role::Assistant,
-                content: vec![Content::Text { 
-                    text: "This is a test mock response.".to_string() 
-                }],
+                content: vec![Content::text("This is a test mock response.".to_string())],
                 usage: Usage {
                     input_tokens: 10,
                     output_tokens: 5,
+                    cache_creation_input_tokens: 0,
+                    cache_read_input_tokens: 0,
                 },
                 stop_reason: Some("end_turn".to_string()),
                 stop_sequence: None,
@@ -43,7 +43,7 @@ impl TestMockApiHandler {
     
     #[allow(dead_code)]
     fn with_text(mut self, text: &str) -> Self {
-        self.response.content = vec![Content::Text { text: text.to_string() }];
+        self.response.content = vec![Content::text(text.to_string())];
         self
     }
 }
@@ -64,17 +64,17 @@ impl MockApiHandler for TestMockApiHandler {
             message: Some(DeltaMessage {
                 id: "msg_mock123".to_string(),
                 model: "claude-3-sonnet-20240229".to_string(),
-                content: Some(vec![Content::Text {
-                    text: "Streaming test response".to_string(),
-                }]),
+                content: Some(vec![Content::text("Streaming test response".to_string())]),
                 stop_reason: None,
                 stop_sequence: None,
                 role: Some(Role::Assistant),
                 type_field: Some("message".to_string()),
+                usage: None,
             }),
             usage: None,
             index: Some(0),
             delta: None,
+            content_block: None,
         };
         
         // Create a stream that just returns this event
@@ -136,7 +136,7 @@ async fn test_with_improved_helpers() {
     let requests = mock_api.get_request_history();
     assert_eq!(requests.len(), 1);
     match &requests[0].messages[0].content[0] {
-        Content::Text { text } => assert!(text.contains("This is a great product!")),
+        Content::Text { text, .. } => assert!(text.contains("This is a great product!")),
         _ => panic!("Expected Content::Text"),
     }
 }
@@ -0,0 +1,328 @@
+use claude_rs::{BatchOutcome, BatchRequest, BatchResult, BatchStatus, CancellationToken, Claude, ClaudeModel, EntityType};
+use claude_rs::types::*;
+use futures::StreamExt;
+use mockito::Matcher;
+use serde_json::{json, Value};
+use std::time::{Duration, Instant};
+
+fn batch_json(id: &str, status: &str, results_url: Option<String>) -> Value {
+    json!({
+        "id": id,
+        "type": "message_batch",
+        "processing_status": status,
+        "request_counts": {"processing": 0, "succeeded": 2, "errored": 1, "canceled": 0, "expired": 1},
+        "created_at": "2025-01-01T00:00:00Z",
+        "expires_at": "2025-01-02T00:00:00Z",
+        "ended_at": null,
+        "cancel_initiated_at": null,
+        "archived_at": null,
+        "results_url": results_url,
+    })
+}
+
+fn simple_request(text: &str) -> MessageRequest {
+    MessageRequest {
+        model: "claude-3-sonnet-20240229".to_string(),
+        messages: vec![Message { role: Role::User, content: vec![Content::text(text)] }],
+        system: None,
+        temperature: None,
+        max_tokens: Some(100),
+        tools: None,
+        tool_choice: None,
+        thinking: None,
+        top_p: None,
+        top_k: None,
+        stop_sequences: Vec::new(),
+        stream: Some(true),
+        cost_tag: None,
+    }
+}
+
+fn succeeded(custom_id: &str, content: Value) -> Value {
+    json!({"custom_id": custom_id, "result": {"type": "succeeded", "message": {
+        "id": "msg_1",
+        "type": "message",
+        "role": "assistant",
+        "model": "claude-3-sonnet-20240229",
+        "content": content,
+        "stop_reason": "end_turn",
+        "stop_sequence": null,
+        "usage": {"input_tokens": 10, "output_tokens": 5}
+    }}})
+}
+
+#[tokio::test]
+async fn test_submit_batch() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server.mock("POST", "/messages/batches")
+        .match_header("x-api-key", "test-api-key")
+        .match_body(Matcher::PartialJson(json!({"requests": [
+            {"custom_id": "first", "params": {"model": "claude-3-sonnet-20240229", "max_tokens": 100}},
+            {"custom_id": "second"},
+        ]})))
+        .with_status(200)
+        .with_body(batch_json("msgbatch_1", "in_progress", None).to_string())
+        .create_async()
+        .await;
+
+    let client = Claude::new("test-api-key").with_base_url(server.url());
+    let batch = client.batches()
+        .submit(vec![("first", simple_request("One")), ("second", simple_request("Two"))])
+        .await
+        .unwrap();
+
+    mock.assert_async().await;
+    assert_eq!(batch.id, "msgbatch_1");
+    assert_eq!(batch.processing_status, BatchStatus::InProgress);
+    assert_eq!(batch.request_counts.total(), 4);
+    assert!(!batch.is_ended());
+}
+
+#[test]
+fn test_batch_request_wire_format() {
+    let request = BatchRequest::from(("first", simple_request("One")));
+    let value = serde_json::to_value(&request).unwrap();
+    assert_eq!(value["custom_id"], json!("first"));
+    assert_eq!(value["params"]["messages"][0]["content"][0]["text"], json!("One"));
+}
+
+#[tokio::test]
+async fn test_invalid_batches_are_rejected() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server.mock("POST", "/messages/batches").expect(0).create_async().await;
+    let client = Claude::new("test-api-key").with_base_url(server.url());
+    let batches = client.batches();
+
+    let result = batches.submit(Vec::<BatchRequest>::new()).await;
+    assert!(matches!(result, Err(ClaudeError::ValidationError(_))));
+
+    let result = batches.submit(vec![("same", simple_request("One")), ("same", simple_request("Two"))]).await;
+    assert!(matches!(result, Err(ClaudeError::ValidationError(message)) if message.contains("Duplicate")));
+
+    let result = batches.submit(vec![("not valid!", simple_request("One"))]).await;
+    assert!(matches!(result, Err(ClaudeError::ValidationError(message)) if message.contains("custom_id")));
+
+    let result = batches.submit(vec![("x".repeat(65), simple_request("One"))]).await;
+    assert!(matches!(result, Err(ClaudeError::ValidationError(_))));
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_retrieve_wait_and_cancel() {
+    let mut server = mockito::Server::new_async().await;
+    let _retrieve = server.mock("GET", "/messages/batches/msgbatch_1")
+        .with_status(200)
+        .with_body(batch_json("msgbatch_1", "ended", None).to_string())
+        .create_async()
+        .await;
+    let _cancel = server.mock("POST", "/messages/batches/msgbatch_2/cancel")
+        .with_status(200)
+        .with_body(batch_json("msgbatch_2", "canceling", None).to_string())
+        .create_async()
+        .await;
+    let _missing = server.mock("GET", "/messages/batches/msgbatch_3")
+        .with_status(404)
+        .with_body(json!({"type": "error", "error": {"type": "not_found_error", "message": "No such batch"}}).to_string())
+        .create_async()
+        .await;
+
+    let client = Claude::new("test-api-key").with_base_url(server.url());
+    let batches = client.batches();
+
+    let batch = batches.wait_until_ended("msgbatch_1", Duration::from_millis(10), None).await.unwrap();
+    assert!(batch.is_ended());
+
+    let batch = batches.cancel("msgbatch_2").await.unwrap();
+    assert_eq!(batch.processing_status, BatchStatus::Canceling);
+
+    let error = batches.retrieve("msgbatch_3").await.unwrap_err();
+    assert!(matches!(error, ClaudeError::ApiError { kind: ApiErrorKind::NotFoundError, .. }));
+}
+
+#[tokio::test]
+async fn test_results_are_streamed_as_typed_outcomes() {
+    let mut server = mockito::Server::new_async().await;
+    let results_url = format!("{}/results/msgbatch_1", server.url());
+    let _retrieve = server.mock("GET", "/messages/batches/msgbatch_1")
+        .with_status(200)
+        .with_body(batch_json("msgbatch_1", "ended", Some(results_url)).to_string())
+        .create_async()
+        .await;
+
+    let lines = [
+        succeeded("first", json!([{"type": "text", "text": "Hello"}])),
+        json!({"custom_id": "second", "result": {"type": "errored", "error": {"type": "error", "error": {"type": "invalid_request_error", "message": "max_tokens: Field required"}}}}),
+        json!({"custom_id": "third", "result": {"type": "expired"}}),
+        json!({"custom_id": "fourth", "result": {"type": "canceled"}}),
+    ];
+    // The final line has no trailing newline
+    let body = lines.iter().map(Value::to_string).collect::<Vec<_>>().join("\n");
+    let _results = server.mock("GET", "/results/msgbatch_1")
+        .match_header("x-api-key", "test-api-key")
+        .with_status(200)
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let client = Claude::new("test-api-key").with_base_url(server.url());
+    let results: Vec<BatchResult> = client.batches().results("msgbatch_1").await.unwrap()
+        .map(|result| result.unwrap())
+        .collect()
+        .await;
+
+    assert_eq!(results.len(), 4);
+    assert!(results[0].is_success());
+    assert!(matches!(&results[0].message().unwrap().content[0], Content::Text { text, .. } if text == "Hello"));
+
+    assert!(matches!(&results[1].result, BatchOutcome::Errored { .. }));
+    assert!(matches!(
+        results[1].message(),
+        Err(ClaudeError::ApiError { kind: ApiErrorKind::InvalidRequestError, .. })
+    ));
+
+    assert!(matches!(results[2].result, BatchOutcome::Expired));
+    assert!(matches!(results[2].message(), Err(ClaudeError::RequestError { message, .. }) if message.contains("third")));
+    assert!(matches!(results[3].result, BatchOutcome::Canceled));
+}
+
+#[tokio::test]
+async fn test_results_require_an_ended_batch() {
+    let mut server = mockito::Server::new_async().await;
+    let _retrieve = server.mock("GET", "/messages/batches/msgbatch_1")
+        .with_status(200)
+        .with_body(batch_json("msgbatch_1", "in_progress", None).to_string())
+        .create_async()
+        .await;
+
+    let client = Claude::new("test-api-key").with_base_url(server.url());
+    let result = client.batches().results("msgbatch_1").await;
+    assert!(matches!(result, Err(ClaudeError::ValidationError(_))));
+}
+
+#[tokio::test]
+async fn test_waiting_gives_up_after_max_wait() {
+    let mut server = mockito::Server::new_async().await;
+    let _retrieve = server.mock("GET", "/messages/batches/msgbatch_1")
+        .with_status(200)
+        .with_body(batch_json("msgbatch_1", "in_progress", None).to_string())
+        .create_async()
+        .await;
+
+    let client = Claude::new("test-api-key").with_base_url(server.url());
+    let start = Instant::now();
+    let error = client.batches()
+        .wait_until_ended("msgbatch_1", Duration::from_secs(60), Some(Duration::from_millis(100)))
+        .await
+        .unwrap_err();
+
+    assert!(matches!(&error, ClaudeError::RequestError { message, .. } if message.contains("did not end")));
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn test_waiting_and_results_observe_the_client_token() {
+    let mut server = mockito::Server::new_async().await;
+    let _retrieve = server.mock("GET", "/messages/batches/msgbatch_1")
+        .with_status(200)
+        .with_body(batch_json("msgbatch_1", "in_progress", None).to_string())
+        .create_async()
+        .await;
+
+    let token = CancellationToken::new();
+    let client = Claude::new("test-api-key")
+        .with_base_url(server.url())
+        .with_cancellation_token(token.clone());
+    let canceller = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        canceller.cancel();
+    });
+
+    let start = Instant::now();
+    let result = client.batches().wait_until_ended("msgbatch_1", Duration::from_secs(60), None).await;
+    assert!(matches!(result, Err(ClaudeError::Cancelled)));
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn test_results_stop_when_the_client_token_is_cancelled() {
+    let mut server = mockito::Server::new_async().await;
+    let results_url = format!("{}/results/msgbatch_1", server.url());
+    let _retrieve = server.mock("GET", "/messages/batches/msgbatch_1")
+        .with_status(200)
+        .with_body(batch_json("msgbatch_1", "ended", Some(results_url)).to_string())
+        .create_async()
+        .await;
+    // One result, then the download stalls
+    let first = format!("{}\n", succeeded("first", json!([{"type": "text", "text": "Hello"}])));
+    let _results = server.mock("GET", "/results/msgbatch_1")
+        .with_status(200)
+        .with_chunked_body(move |w| {
+            w.write_all(first.as_bytes())?;
+            w.flush()?;
+            std::thread::sleep(Duration::from_secs(2));
+            Ok(())
+        })
+        .create_async()
+        .await;
+
+    let token = CancellationToken::new();
+    let client = Claude::new("test-api-key")
+        .with_base_url(server.url())
+        .with_cancellation_token(token.clone());
+    let mut results = client.batches().results("msgbatch_1").await.unwrap();
+    assert_eq!(results.next().await.unwrap().unwrap().custom_id, "first");
+
+    let start = Instant::now();
+    token.cancel();
+    assert!(matches!(results.next().await, Some(Err(ClaudeError::Cancelled))));
+    assert!(results.next().await.is_none());
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn test_sentiment_batch_round_trip() {
+    let client = Claude::new("test-api-key").with_model(ClaudeModel::Sonnet);
+    let sentiment = client.sentiment();
+
+    let request = sentiment.batch_request("review-1", "I love it").await.unwrap();
+    assert_eq!(request.custom_id, "review-1");
+    assert_eq!(request.params.tool_choice, Some(ToolChoice::tool("report_sentiment")));
+    assert_eq!(request.params.tools.as_ref().unwrap()[0].name, "report_sentiment");
+    assert!(matches!(&request.params.messages[0].content[0], Content::Text { text, .. } if text.contains("I love it")));
+
+    let result: BatchResult = serde_json::from_value(succeeded("review-1", json!([{
+        "type": "tool_use",
+        "id": "toolu_1",
+        "name": "report_sentiment",
+        "input": {"score": 0.8, "sentiment": "Positive", "aspects": {}}
+    }]))).unwrap();
+    let analysis = sentiment.parse_batch_result(&result).await.unwrap();
+    assert_eq!(analysis.sentiment, claude_rs::Sentiment::Positive);
+
+    let expired: BatchResult = serde_json::from_value(json!({"custom_id": "review-2", "result": {"type": "expired"}})).unwrap();
+    assert!(sentiment.parse_batch_result(&expired).await.is_err());
+
+    // Invalid input is rejected before anything is built
+    assert!(matches!(sentiment.batch_request("review-3", "").await, Err(ClaudeError::DomainError { .. })));
+}
+
+#[tokio::test]
+async fn test_entity_batch_round_trip() {
+    let client = Claude::new("test-api-key").with_model(ClaudeModel::Sonnet);
+    let entity = client.entity();
+
+    let request = entity.batch_request_with_types("doc-1", "Tim Cook runs Apple", vec![EntityType::Person]).await.unwrap();
+    assert!(request.params.tools.is_none());
+    assert!(matches!(&request.params.messages[0].content[0], Content::Text { text, .. } if text.contains("[\"Person\"]")));
+
+    let result: BatchResult = serde_json::from_value(succeeded("doc-1", json!([{
+        "type": "text",
+        "text": "```json\n[{\"text\": \"Tim Cook\", \"entity_type\": \"Person\", \"start_idx\": 0, \"end_idx\": 8, \"confidence\": 0.99}]\n```"
+    }]))).unwrap();
+
+    let entities = entity.parse_batch_result(&result).await.unwrap();
+    assert_eq!(entities.len(), 1);
+    assert_eq!(entities[0].entity_type, EntityType::Person);
+}
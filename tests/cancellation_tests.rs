@@ -0,0 +1,173 @@
+use claude_rs::{Claude, ClaudeError, CancellationToken, CostTracker};
+use claude_rs::types::*;
+use claude_rs::domains::base::BaseDomainClient;
+use futures::StreamExt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+mod mock_api_client;
+use mock_api_client::{create_text_response, stalling_client, text_delta};
+
+/// A client whose streams send one delta and then wait forever
+fn stalled_stream_client() -> Claude {
+    stalling_client(Claude::new("test-api-key"), vec![(0, text_delta("Once upon"))])
+}
+
+#[tokio::test]
+async fn test_cancelling_aborts_http_request() {
+    let mut server = mockito::Server::new_async().await;
+    let _mock = server.mock("POST", "/messages")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_chunked_body(|w| {
+            std::thread::sleep(Duration::from_secs(2));
+            w.write_all(b"{}")
+        })
+        .create_async()
+        .await;
+
+    let tracker = CostTracker::default();
+    let client = Claude::new("test-api-key")
+        .with_base_url(server.url())
+        .with_cost_tracker(tracker.clone());
+    let token = CancellationToken::new();
+    let canceller = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        canceller.cancel();
+    });
+
+    let start = Instant::now();
+    let result = client.message()
+        .user_content("Write a long story")
+        .cancellation_token(token)
+        .send()
+        .await;
+
+    assert!(matches!(result, Err(ClaudeError::Cancelled)));
+    assert!(start.elapsed() < Duration::from_secs(1));
+    // Response middleware never saw a partial response
+    assert_eq!(tracker.report().total.responses, 0);
+}
+
+#[tokio::test]
+async fn test_cancelled_token_stops_request_before_sending() {
+    let client = Claude::new("test-api-key");
+    let calls = Arc::new(AtomicUsize::new(0));
+    let handler_calls = calls.clone();
+    client.set_request_handler(Box::new(move |_request: MessageRequest| {
+        handler_calls.fetch_add(1, Ordering::SeqCst);
+        Box::pin(async move { Ok(create_text_response("Hello")) })
+            as std::pin::Pin<Box<dyn std::future::Future<Output = ClaudeResult<MessageResponse>> + Send>>
+    }));
+
+    let token = CancellationToken::new();
+    token.cancel();
+    let error = client.message()
+        .user_content("Hello")
+        .cancellation_token(token)
+        .send()
+        .await
+        .unwrap_err();
+
+    assert!(error.is_cancelled());
+    assert!(!error.is_retryable());
+    assert_eq!(calls.load(Ordering::SeqCst), 0);
+}
+
+#[tokio::test]
+async fn test_cancelled_stream_ends_with_cancelled_item() {
+    let client = stalled_stream_client();
+    let token = CancellationToken::new();
+    let mut stream = client.message()
+        .user_content("Tell me a story")
+        .cancellation_token(token.clone())
+        .stream()
+        .await
+        .unwrap();
+
+    assert_eq!(stream.next().await.unwrap().unwrap().to_text().as_deref(), Some("Once upon"));
+    token.cancel();
+    assert!(matches!(stream.next().await, Some(Err(ClaudeError::Cancelled))));
+    assert!(stream.next().await.is_none());
+}
+
+#[tokio::test]
+async fn test_client_token_cancels_domain_operations() {
+    let client = Claude::new("test-api-key");
+    client.set_request_handler(Box::new(|_request: MessageRequest| {
+        Box::pin(async move {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(create_text_response(r#"{"sentiment": "Positive", "score": 0.9}"#))
+        }) as std::pin::Pin<Box<dyn std::future::Future<Output = ClaudeResult<MessageResponse>> + Send>>
+    }));
+
+    let token = CancellationToken::new();
+    let client = client.with_cancellation_token(token.clone());
+    assert!(client.cancellation_token().is_some());
+
+    let canceller = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        canceller.cancel();
+    });
+
+    let start = Instant::now();
+    let result = client.sentiment().analyze_text("What a great product!").await;
+    assert!(matches!(result, Err(ClaudeError::Cancelled)));
+    assert!(start.elapsed() < Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn test_domain_client_token_cancels_one_operation() {
+    let client = Arc::new(Claude::new("test-api-key"));
+    client.set_request_handler(Box::new(|_request: MessageRequest| {
+        Box::pin(async move {
+            tokio::time::sleep(Duration::from_secs(5)).await;
+            Ok(create_text_response(r#"{"sentiment": "Positive", "score": 0.9}"#))
+        }) as std::pin::Pin<Box<dyn std::future::Future<Output = ClaudeResult<MessageResponse>> + Send>>
+    }));
+    client.register_domain("support", BaseDomainClient::new(client.clone(), "support"));
+
+    let token = CancellationToken::new();
+    let canceller = token.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        canceller.cancel();
+    });
+
+    let start = Instant::now();
+    let result = client.sentiment()
+        .with_cancellation(token)
+        .analyze_text("What a great product!")
+        .await;
+    assert!(matches!(result, Err(ClaudeError::Cancelled)));
+    assert!(start.elapsed() < Duration::from_secs(1));
+    // The client itself is untouched
+    assert!(client.cancellation_token().is_none());
+    assert!(client.get_domain("support").is_some());
+}
+
+#[cfg(feature = "reactive")]
+#[tokio::test]
+async fn test_reactive_response_reports_cancellation() {
+    use claude_rs::reactive::ReactiveResponseStatus;
+
+    let client = stalled_stream_client();
+    let token = CancellationToken::new();
+    let mut response = client.send_reactive(client.message().user_content("Tell me a story"))
+        .await
+        .unwrap()
+        .with_cancellation(token.clone());
+
+    response.next().await.unwrap().unwrap();
+    token.cancel();
+    assert!(matches!(response.next().await, Some(Err(ClaudeError::Cancelled))));
+    assert!(response.next().await.is_none());
+
+    assert_eq!(response.status(), ReactiveResponseStatus::Cancelled);
+    assert!(response.is_cancelled());
+    assert!(!response.has_error());
+    assert_eq!(response.current_text(), "Once upon");
+}
@@ -0,0 +1,139 @@
+use claude_rs::{Claude, ClaudeError, ClaudeModel, RetryPolicy, DEFAULT_API_VERSION, DEFAULT_BASE_URL};
+use futures::StreamExt;
+use mockito::Matcher;
+use serde_json::json;
+use std::time::Duration;
+
+mod test_helpers;
+use test_helpers::response_body;
+
+#[test]
+fn test_builder_defaults_match_new() {
+    let claude = Claude::builder("test-api-key").build().unwrap();
+    let default = Claude::new("test-api-key");
+    let fallible = Claude::try_new("test-api-key").unwrap();
+
+    assert_eq!(claude.base_url, DEFAULT_BASE_URL);
+    assert_eq!(claude.base_url, default.base_url);
+    assert_eq!(claude.default_model, default.default_model);
+    assert_eq!(fallible.base_url, default.base_url);
+    assert_eq!(claude.default_max_tokens, None);
+}
+
+#[tokio::test]
+async fn test_builder_sends_configured_headers() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server.mock("POST", "/messages")
+        .match_header("x-api-key", "test-api-key")
+        .match_header("anthropic-version", "2024-01-01")
+        .match_header("anthropic-beta", "beta-one,beta-two")
+        .match_header("user-agent", "my-app/1.2")
+        .match_header("x-team", "search")
+        .match_body(Matcher::PartialJson(json!({"model": "claude-3-haiku-20240307", "max_tokens": 200})))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(response_body())
+        .expect(1)
+        .create_async()
+        .await;
+
+    let claude = Claude::builder("test-api-key")
+        .base_url(server.url())
+        .model(ClaudeModel::Haiku)
+        .default_max_tokens(200)
+        .api_version("2024-01-01")
+        .beta("beta-one")
+        .beta("beta-two")
+        .beta("beta-one")
+        .user_agent("my-app/1.2")
+        .header("x-team", "search")
+        .connect_timeout(Duration::from_secs(5))
+        .pool_max_idle_per_host(4)
+        .build()
+        .unwrap();
+    claude.message().user_content("Hello").send().await.unwrap();
+
+    mock.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_default_headers_apply_to_other_endpoints() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server.mock("POST", "/messages/count_tokens")
+        .match_header("anthropic-version", DEFAULT_API_VERSION)
+        .match_header("user-agent", Matcher::Regex("^claude-rs/".to_string()))
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"input_tokens": 12}"#)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let claude = Claude::new("test-api-key").with_base_url(server.url());
+    let request = claude.message().user_content("Hello").build_request().await.unwrap();
+    assert_eq!(claude.count_tokens(&request).await.unwrap(), 12);
+
+    mock.assert_async().await;
+}
+
+#[test]
+fn test_invalid_settings_are_build_errors() {
+    let reserved = Claude::builder("test-api-key").header("anthropic-version", "2024-01-01").build();
+    assert!(matches!(reserved, Err(ClaudeError::InvalidParameter(message)) if message.contains("anthropic-version")));
+
+    let invalid_name = Claude::builder("test-api-key").header("bad header", "value").build();
+    assert!(matches!(invalid_name, Err(ClaudeError::InvalidParameter(_))));
+
+    let invalid_agent = Claude::builder("test-api-key").user_agent("line\nbreak").build();
+    assert!(matches!(invalid_agent, Err(ClaudeError::InvalidParameter(_))));
+
+    let zero_tokens = Claude::builder("test-api-key").default_max_tokens(0).build();
+    assert!(matches!(zero_tokens, Err(ClaudeError::ValidationError(_))));
+}
+
+#[test]
+fn test_external_client_cannot_be_combined_with_transport_settings() {
+    let external = reqwest::Client::new();
+    let claude = Claude::builder("test-api-key")
+        .http_client(external.clone())
+        .read_timeout(Duration::from_secs(30))
+        .header("x-team", "search")
+        .build();
+    assert!(claude.is_ok());
+
+    let conflicting = Claude::builder("test-api-key")
+        .http_client(external)
+        .timeout(Duration::from_secs(30))
+        .build();
+    assert!(matches!(conflicting, Err(ClaudeError::InvalidParameter(_))));
+}
+
+#[tokio::test]
+async fn test_read_timeout_fails_stalled_response() {
+    let mut server = mockito::Server::new_async().await;
+    let _mock = server.mock("POST", "/messages")
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_chunked_body(|w| {
+            w.write_all(b"event: ping\ndata: {\"type\":\"ping\"}\n\n")?;
+            w.flush()?;
+            std::thread::sleep(Duration::from_millis(500));
+            w.write_all(b"event: message_stop\ndata: {\"type\":\"message_stop\"}\n\n")
+        })
+        .create_async()
+        .await;
+
+    let claude = Claude::builder("test-api-key")
+        .base_url(server.url())
+        .read_timeout(Duration::from_millis(100))
+        .build()
+        .unwrap();
+    let mut stream = claude.message().user_content("Hello").stream().await.unwrap();
+
+    assert_eq!(stream.next().await.unwrap().unwrap().event_type, "ping");
+    let error = stream.next().await.unwrap().unwrap_err();
+    assert!(error.to_string().contains("No response data received"));
+    assert!(error.is_retryable());
+    assert!(RetryPolicy::default().should_retry(&error));
+    assert!(stream.next().await.is_none());
+}
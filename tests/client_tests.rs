@@ -26,6 +26,9 @@ async fn test_client_domain_methods() {
 mod mock_api_client;
 mod test_helpers;
 
+#[cfg(feature = "reactive")]
+use test_helpers::setup_mock_with_streaming_text;
+
 use mock_api_client::{
     MockApiClient, 
     mock_api_to_handler, 
@@ -39,10 +39,10 @@ impl ContextManager for TestContextManager {
         
         // Extract assistant message from response and add to context
         // This assumes the first content block is what we want to use
-        if let Some(Content::Text { text }) = response.content.first() {
+        if let Some(Content::Text { text, .. }) = response.content.first() {
             messages.push(Message {
                 role: Role::Assistant,
-                content: vec![Content::Text { text: text.clone() }],
+                content: vec![Content::text(text.clone())],
             });
         }
         
@@ -57,7 +57,7 @@ async fn test_context_manager() {
     // Test processing messages
     let new_messages = vec![Message {
         role: Role::User,
-        content: vec![Content::Text { text: "Hello".to_string() }],
+        content: vec![Content::text("Hello".to_string())],
     }];
     
     let processed = context_manager.process_messages(new_messages).await.unwrap();
@@ -69,12 +69,12 @@ async fn test_context_manager() {
         model: "claude-3-sonnet-20240229".to_string(),
         r#type: "message".to_string(),
         role: Role::Assistant,
-        content: vec![Content::Text {
-            text: "Hi there".to_string()
-        }],
+        content: vec![Content::text("Hi there".to_string())],
         usage: Usage {
             input_tokens: 10,
             output_tokens: 5,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
         },
         stop_reason: None,
         stop_sequence: None,
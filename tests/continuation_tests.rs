@@ -0,0 +1,147 @@
+use async_trait::async_trait;
+use claude_rs::{AdaptiveContextManager, Claude, Content, Message, RequestMiddleware, Role, SimpleImportanceScorer};
+use claude_rs::types::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+mod mock_api_client;
+use mock_api_client::{create_text_response, recording_client, RequestLog};
+
+fn truncated(text: &str) -> MessageResponse {
+    let mut response = create_text_response(text);
+    response.stop_reason = Some("max_tokens".to_string());
+    response
+}
+
+/// A client that answers with `responses` in order, repeating the last one, and records the requests it receives
+fn scripted_client(responses: Vec<MessageResponse>) -> (Claude, RequestLog) {
+    recording_client(Claude::new("test-api-key"), move |_, index| {
+        Ok(responses[index.min(responses.len() - 1)].clone())
+    })
+}
+
+fn last_message(request: &MessageRequest) -> &Message {
+    request.messages.last().unwrap()
+}
+
+fn text_of(content: &[Content]) -> &str {
+    match content {
+        [Content::Text { text, .. }] => text,
+        other => panic!("expected a single text block, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_continuation_joins_text_without_repeating_it() {
+    let (client, requests) = scripted_client(vec![
+        truncated("The quick brown fox jumps over the lazy dog. "),
+        create_text_response("brown fox jumps over the lazy dog. Then it ran off."),
+    ]);
+
+    let response = client.message()
+        .user_content("Tell me about the fox")
+        .auto_continue(3)
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(text_of(&response.content), "The quick brown fox jumps over the lazy dog. Then it ran off.");
+    assert!(!response.is_truncated());
+    assert_eq!(response.stop_reason.as_deref(), Some("end_turn"));
+    assert_eq!(response.usage.input_tokens, 20);
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    // The answer so far is sent back as a prefill, without trailing whitespace
+    let prefill = last_message(&requests[1]);
+    assert_eq!(prefill.role, Role::Assistant);
+    assert_eq!(text_of(&prefill.content), "The quick brown fox jumps over the lazy dog.");
+}
+
+#[tokio::test]
+async fn test_continuation_is_capped_and_extends_existing_prefill() {
+    let (client, requests) = scripted_client(vec![
+        truncated(" It was"),
+        truncated(" a dark"),
+        truncated(" and stormy"),
+        create_text_response(" night."),
+    ]);
+
+    let response = client.message()
+        .user_content("Write a novel")
+        .assistant_content("Chapter 1:")
+        .auto_continue(2)
+        .send()
+        .await
+        .unwrap();
+
+    assert!(response.is_truncated());
+    assert_eq!(text_of(&response.content), " It was a dark and stormy");
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 3);
+    assert_eq!(requests[2].messages.len(), 2);
+    assert_eq!(text_of(&last_message(&requests[2]).content), "Chapter 1: It was a dark");
+}
+
+#[tokio::test]
+async fn test_truncation_is_reported_when_continuation_is_off() {
+    let (client, requests) = scripted_client(vec![truncated("The beginning of")]);
+
+    let response = client.message().user_content("Hello").send().await.unwrap();
+
+    assert!(response.is_truncated());
+    assert_eq!(requests.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_domain_operations_continue_when_enabled() {
+    let (client, requests) = scripted_client(vec![
+        truncated("# Rust\n\nRust is a systems language"),
+        create_text_response(" focused on safety."),
+    ]);
+    let client = client.with_domain_continuation(1);
+
+    let post = client.content().blog_post("Rust", None, None).await.unwrap();
+
+    assert_eq!(post, "# Rust\n\nRust is a systems language focused on safety.");
+    assert_eq!(requests.lock().unwrap().len(), 2);
+}
+
+/// Counts the requests that pass through the request middleware
+struct CountingMiddleware(Arc<AtomicUsize>);
+
+#[async_trait]
+impl RequestMiddleware for CountingMiddleware {
+    async fn process_request(&self, request: MessageRequest) -> ClaudeResult<MessageRequest> {
+        self.0.fetch_add(1, Ordering::SeqCst);
+        Ok(request)
+    }
+}
+
+#[tokio::test]
+async fn test_continuations_reuse_the_prepared_request() {
+    let (client, requests) = scripted_client(vec![
+        create_text_response("Earlier answer"),
+        truncated("Once upon"),
+        create_text_response(" a time."),
+    ]);
+    let prepared = Arc::new(AtomicUsize::new(0));
+    let client = client
+        .with_context_manager(AdaptiveContextManager::new(100_000, SimpleImportanceScorer))
+        .add_request_middleware(CountingMiddleware(prepared.clone()));
+
+    client.message().user_content("Hello").send().await.unwrap();
+    let response = client.message().user_content("Tell me a story").auto_continue(1).send().await.unwrap();
+
+    assert_eq!(text_of(&response.content), "Once upon a time.");
+    // The continuation is not prepared again
+    assert_eq!(prepared.load(Ordering::SeqCst), 2);
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 3);
+    // The history from the context manager is kept, and the partial answer is only the prefill
+    let roles: Vec<Role> = requests[2].messages.iter().map(|message| message.role.clone()).collect();
+    assert_eq!(roles, vec![Role::Assistant, Role::User, Role::Assistant]);
+    assert_eq!(text_of(&last_message(&requests[2]).content), "Once upon");
+}
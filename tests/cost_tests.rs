@@ -0,0 +1,212 @@
+use claude_rs::{Budget, Claude, ClaudeModel, CostTracker, ModelPricing, PriceTable};
+use claude_rs::types::*;
+
+mod mock_api_client;
+use mock_api_client::{create_text_response, RequestLog};
+
+/// Creates a client that answers every request with `usage` and records it
+fn recording_client(usage: Usage) -> (Claude, RequestLog) {
+    let client = Claude::new("test-api-key").with_model(ClaudeModel::Sonnet);
+    mock_api_client::recording_client(client, move |request, _| {
+        let mut response = create_text_response("ok");
+        response.model = request.model.clone();
+        response.usage = usage.clone();
+        Ok(response)
+    })
+}
+
+fn usage(input_tokens: u32, output_tokens: u32) -> Usage {
+    Usage { input_tokens, output_tokens, ..Usage::default() }
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!((actual - expected).abs() < 1e-9, "expected {}, got {}", expected, actual);
+}
+
+#[test]
+fn test_prices_include_cache_and_batch_discounts() {
+    let prices = PriceTable::default();
+
+    // Sonnet: $3 input, $15 output, $3.75 cache write, $0.30 cache read per million tokens
+    let usage = Usage {
+        input_tokens: 1_000_000,
+        output_tokens: 1_000_000,
+        cache_creation_input_tokens: 1_000_000,
+        cache_read_input_tokens: 1_000_000,
+    };
+    assert_close(prices.cost("claude-3-sonnet-20240229", &usage, false).unwrap(), 3.0 + 15.0 + 3.75 + 0.30);
+    assert_close(prices.cost("claude-3-sonnet-20240229", &usage, true).unwrap(), (3.0 + 15.0 + 3.75 + 0.30) / 2.0);
+
+    // Snapshots and aliases are priced by family
+    assert_eq!(prices.get("claude-sonnet-4-0"), prices.get("claude-sonnet-4-20250514"));
+    assert!(prices.cost("not-a-model", &usage, false).is_none());
+
+    let custom = PriceTable::new()
+        .with_price("my-model", ModelPricing::new(1.0, 2.0).with_cache_prices(0.0, 0.0))
+        .with_batch_discount(0.25);
+    assert_close(custom.cost("my-model", &usage, true).unwrap(), 3.0 * 0.75);
+}
+
+#[tokio::test]
+async fn test_spend_is_recorded_by_client_model_and_tag() {
+    let tracker = CostTracker::default();
+    let (client, _) = recording_client(usage(1_000, 500));
+    let client = client.with_cost_tracker(tracker.for_client("backend"));
+    let (tagged, _) = recording_client(usage(2_000, 0));
+    let tagged = tagged.with_cost_tracker(tracker.tagged("search"));
+
+    client.message().user_content("Hello").send().await.unwrap();
+    client.message().model(ClaudeModel::Haiku).user_content("Hello").send().await.unwrap();
+    tagged.message().user_content("Hello").send().await.unwrap();
+
+    let report = tracker.report();
+    let sonnet = (1_000.0 * 3.0 + 500.0 * 15.0) / 1_000_000.0;
+    let haiku = (1_000.0 * 0.25 + 500.0 * 1.25) / 1_000_000.0;
+    let search = 2_000.0 * 3.0 / 1_000_000.0;
+
+    assert_eq!(report.total.responses, 3);
+    assert_eq!(report.total.usage.input_tokens, 4_000);
+    assert_close(report.total.cost, sonnet + haiku + search);
+    assert_close(report.by_client["backend"].cost, sonnet + haiku);
+    assert_close(report.by_model["claude-3-haiku-20240307"].cost, haiku);
+    assert_close(report.by_tag["search"].cost, search);
+    assert!(!report.by_client.contains_key("search"));
+    assert_eq!(report.unpriced_responses, 0);
+
+    tracker.reset();
+    assert_eq!(tracker.report().total.responses, 0);
+}
+
+#[tokio::test]
+async fn test_soft_budget_rejects_requests_once_spent() {
+    // Each response costs $0.015
+    let tracker = CostTracker::default().with_budget(Budget::soft(0.02));
+    let (client, requests) = recording_client(usage(0, 1_000));
+    let client = client.with_cost_tracker(tracker.clone());
+
+    client.message().user_content("One").send().await.unwrap();
+    // Under the limit, so the request goes out and overshoots it
+    client.message().user_content("Two").send().await.unwrap();
+
+    let result = client.message().user_content("Three").send().await;
+    match result {
+        Err(ClaudeError::BudgetExceeded { scope, spent, limit }) => {
+            assert_eq!(scope, "total");
+            assert_close(spent, 0.03);
+            assert_close(limit, 0.02);
+        }
+        other => panic!("Expected BudgetExceeded, got {:?}", other),
+    }
+    assert_eq!(requests.lock().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_hard_budget_rejects_requests_that_could_exceed_it() {
+    let tracker = CostTracker::default().with_budget(Budget::hard(0.01));
+    let (client, requests) = recording_client(usage(10, 10));
+    let client = client.with_cost_tracker(tracker);
+
+    // 1000 output tokens of Sonnet may cost $0.015
+    let result = client.message().user_content("Hello").max_tokens(1_000).unwrap().send().await;
+    assert!(matches!(result, Err(ClaudeError::BudgetExceeded { .. })));
+
+    client.message().user_content("Hello").max_tokens(100).unwrap().send().await.unwrap();
+    assert_eq!(requests.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_scoped_budgets_only_apply_to_their_scope() {
+    let tracker = CostTracker::default()
+        .with_budget(Budget::soft(0.0).for_tag("experiments"))
+        .with_budget(Budget::soft(0.0).for_model(ClaudeModel::Opus));
+    let (client, _) = recording_client(usage(10, 10));
+    let experiments = client.clone().with_cost_tracker(tracker.tagged("experiments"));
+    let client = client.with_cost_tracker(tracker.for_client("backend"));
+
+    client.message().user_content("Hello").send().await.unwrap();
+
+    let result = experiments.message().user_content("Hello").send().await;
+    assert!(matches!(result, Err(ClaudeError::BudgetExceeded { scope, .. }) if scope == "tag 'experiments'"));
+
+    let result = client.message().model(ClaudeModel::Opus).user_content("Hello").send().await;
+    assert!(matches!(result, Err(ClaudeError::BudgetExceeded { scope, .. }) if scope.contains("claude-3-opus")));
+}
+
+#[test]
+fn test_batch_and_unpriced_responses() {
+    let tracker = CostTracker::default();
+
+    let mut response = create_text_response("ok");
+    response.usage = usage(1_000_000, 0);
+    assert_close(tracker.record_batch(&response), 1.5);
+
+    response.model = "not-a-model".to_string();
+    assert_eq!(tracker.record(&response), 0.0);
+
+    let report = tracker.report();
+    assert_eq!(report.total.responses, 2);
+    assert_eq!(report.unpriced_responses, 1);
+    assert_close(report.total.cost, 1.5);
+}
+
+#[tokio::test]
+async fn test_model_budgets_cover_snapshots_of_requested_aliases() {
+    let tracker = CostTracker::default().with_budget(
+        Budget::hard(0.02).for_model(ClaudeModel::Custom("claude-sonnet-4-0".to_string())),
+    );
+    // The alias is answered by a dated snapshot, as the API does
+    let client = Claude::new("test-api-key").with_model(ClaudeModel::Custom("claude-sonnet-4-0".to_string()));
+    let (client, requests) = mock_api_client::recording_client(client, |_, _| {
+        let mut response = create_text_response("ok");
+        response.model = "claude-sonnet-4-20250514".to_string();
+        response.usage = usage(0, 1_000);
+        Ok(response)
+    });
+    let client = client.with_cost_tracker(tracker.clone());
+
+    // Each response costs $0.015, and each request may cost up to that much
+    client.message().user_content("One").max_tokens(1_000).unwrap().send().await.unwrap();
+    let result = client.message().user_content("Two").max_tokens(1_000).unwrap().send().await;
+    assert!(matches!(result, Err(ClaudeError::BudgetExceeded { scope, .. }) if scope.contains("claude-sonnet-4-0")));
+    assert_eq!(requests.lock().unwrap().len(), 1);
+
+    // Spend is still reported under the model that answered
+    assert!(tracker.report().by_model.contains_key("claude-sonnet-4-20250514"));
+}
+
+#[tokio::test]
+async fn test_cost_tags_can_be_set_per_request() {
+    let tracker = CostTracker::default().with_budget(Budget::soft(0.0).for_tag("search"));
+    let (client, _) = recording_client(usage(1_000, 0));
+    let client = client.with_cost_tracker(tracker.tagged("default"));
+
+    client.message().cost_tag("summaries").user_content("Hello").send().await.unwrap();
+    client.message().user_content("Hello").send().await.unwrap();
+
+    let report = tracker.report();
+    assert_eq!(report.by_tag["summaries"].responses, 1);
+    assert_eq!(report.by_tag["default"].responses, 1);
+
+    // Budgets of the request's tag apply, without another client
+    let result = client.message().cost_tag("search").user_content("Hello").send().await;
+    assert!(matches!(result, Err(ClaudeError::BudgetExceeded { scope, .. }) if scope == "tag 'search'"));
+}
+
+#[tokio::test]
+async fn test_cost_tracker_applies_to_existing_domain_clients() {
+    let client = Claude::new("test-api-key").with_model(ClaudeModel::Sonnet);
+    let (client, _requests) = mock_api_client::recording_client(client, |request, _| {
+        let mut response = create_text_response(r#"{"score": 0.8, "sentiment": "Positive", "aspects": {}}"#);
+        response.model = request.model.clone();
+        response.usage = usage(1_000, 500);
+        Ok(response)
+    });
+
+    // Domain clients taken before the tracker is added do not bypass it
+    client.sentiment();
+    let tracker = CostTracker::default();
+    let tracked = client.with_cost_tracker(tracker.clone());
+    tracked.sentiment().analyze_text("I love it").await.unwrap();
+
+    assert_eq!(tracker.report().total.responses, 1);
+}
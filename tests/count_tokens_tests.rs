@@ -0,0 +1,247 @@
+use claude_rs::{AdaptiveContextManager, ApiTokenCounter, AsyncTokenCounter, Claude, ClaudeModel, ClaudeResult, ContextManager, SimpleImportanceScorer};
+use claude_rs::types::*;
+use async_trait::async_trait;
+use mockito::Matcher;
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+mod mock_api_client;
+use mock_api_client::create_text_response;
+
+fn user_message(text: &str) -> Message {
+    Message {
+        role: Role::User,
+        content: vec![Content::text(text)],
+    }
+}
+
+fn count_body(tokens: u32) -> String {
+    json!({"input_tokens": tokens}).to_string()
+}
+
+#[tokio::test]
+async fn test_builder_counts_request_tokens() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server.mock("POST", "/messages/count_tokens")
+        .match_header("x-api-key", "test-api-key")
+        .match_header("anthropic-version", "2023-06-01")
+        .match_body(Matcher::PartialJson(json!({
+            "model": "claude-3-sonnet-20240229",
+            "system": "Be brief.",
+            "messages": [{"role": "user", "content": [{"type": "text", "text": "Hello"}]}],
+        })))
+        .with_status(200)
+        .with_body(count_body(14))
+        .create_async()
+        .await;
+
+    let client = Claude::new("test-api-key").with_base_url(server.url()).with_model(ClaudeModel::Sonnet);
+    let tokens = client.message()
+        .system("Be brief.").unwrap()
+        .user_content("Hello")
+        .count_tokens()
+        .await
+        .unwrap();
+
+    mock.assert_async().await;
+    assert_eq!(tokens, 14);
+}
+
+#[tokio::test]
+async fn test_count_tokens_sends_no_generation_parameters() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server.mock("POST", "/messages/count_tokens")
+        .match_body(Matcher::Json(json!({
+            "model": "claude-3-sonnet-20240229",
+            "messages": [{"role": "user", "content": [{"type": "text", "text": "Hello"}]}],
+        })))
+        .with_status(200)
+        .with_body(count_body(7))
+        .create_async()
+        .await;
+
+    let client = Claude::new("test-api-key").with_base_url(server.url()).with_model(ClaudeModel::Sonnet);
+    let request = client.message()
+        .user_content("Hello")
+        .max_tokens(500).unwrap()
+        .temperature(0.3).unwrap()
+        .build_request()
+        .await
+        .unwrap();
+    assert_eq!(client.count_tokens(&request).await.unwrap(), 7);
+    mock.assert_async().await;
+
+    // An empty request is rejected before anything is sent
+    let result = client.message().count_tokens().await;
+    assert!(matches!(result, Err(ClaudeError::ValidationError(_))));
+}
+
+#[tokio::test]
+async fn test_count_tokens_surfaces_api_errors() {
+    let mut server = mockito::Server::new_async().await;
+    let _mock = server.mock("POST", "/messages/count_tokens")
+        .with_status(400)
+        .with_body(json!({"type": "error", "error": {"type": "invalid_request_error", "message": "model: not found"}}).to_string())
+        .create_async()
+        .await;
+
+    let client = Claude::new("test-api-key").with_base_url(server.url());
+    let result = client.message().user_content("Hello").count_tokens().await;
+    assert!(matches!(result, Err(ClaudeError::ApiError { kind: ApiErrorKind::InvalidRequestError, .. })));
+}
+
+#[tokio::test]
+async fn test_api_token_counter_caches_counts() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server.mock("POST", "/messages/count_tokens")
+        .match_body(Matcher::PartialJson(json!({
+            "model": "claude-3-haiku-20240307",
+            "messages": [{"role": "user", "content": [{"type": "text", "text": "Hello"}]}],
+        })))
+        .with_status(200)
+        .with_body(count_body(9))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = Claude::new("test-api-key").with_base_url(server.url());
+    let counter = ApiTokenCounter::new(&client).with_model(ClaudeModel::Haiku);
+
+    assert_eq!(counter.message_tokens(&user_message("Hello")).await.unwrap(), 9);
+    assert_eq!(counter.message_tokens(&user_message("Hello")).await.unwrap(), 9);
+    assert_eq!(counter.cached_counts(), 1);
+    mock.assert_async().await;
+
+    counter.clear_cache();
+    assert_eq!(counter.cached_counts(), 0);
+}
+
+#[tokio::test]
+async fn test_api_token_counter_counts_assistant_turns_as_user_text() {
+    let mut server = mockito::Server::new_async().await;
+    let mock = server.mock("POST", "/messages/count_tokens")
+        .match_body(Matcher::PartialJson(json!({
+            "messages": [{"role": "user", "content": [
+                {"type": "text", "text": "Let me check."},
+                {"type": "text", "text": "toolu_1 lookup {\"query\":\"rust\"}"},
+            ]}],
+        })))
+        .with_status(200)
+        .with_body(count_body(21))
+        .create_async()
+        .await;
+
+    let client = Claude::new("test-api-key").with_base_url(server.url());
+    let counter = ApiTokenCounter::new(&client);
+    let message = Message {
+        role: Role::Assistant,
+        content: vec![
+            Content::text("Let me check."),
+            Content::tool_use("toolu_1", "lookup", json!({"query": "rust"})),
+            Content::RedactedThinking { data: "encrypted".to_string() },
+        ],
+    };
+
+    assert_eq!(counter.message_tokens(&message).await.unwrap(), 21);
+    mock.assert_async().await;
+
+    // Nothing countable means no request at all
+    let redacted = Message {
+        role: Role::Assistant,
+        content: vec![Content::RedactedThinking { data: "encrypted".to_string() }],
+    };
+    assert_eq!(counter.message_tokens(&redacted).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_api_token_counter_measures_system_prompt() {
+    let mut server = mockito::Server::new_async().await;
+    let with_system = server.mock("POST", "/messages/count_tokens")
+        .match_body(Matcher::PartialJson(json!({"system": "You are a meticulous librarian."})))
+        .with_status(200)
+        .with_body(count_body(18))
+        .create_async()
+        .await;
+    let baseline = server.mock("POST", "/messages/count_tokens")
+        .match_body(Matcher::Json(json!({
+            "model": "claude-3-sonnet-20240229",
+            "messages": [{"role": "user", "content": [{"type": "text", "text": "."}]}],
+        })))
+        .with_status(200)
+        .with_body(count_body(8))
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = Claude::new("test-api-key").with_base_url(server.url()).with_model(ClaudeModel::Sonnet);
+    let counter = ApiTokenCounter::new(&client);
+
+    let system = SystemPrompt::from("You are a meticulous librarian.");
+    assert_eq!(counter.system_tokens(&system).await.unwrap(), 10);
+    assert_eq!(counter.system_tokens(&system).await.unwrap(), 10);
+
+    with_system.assert_async().await;
+    baseline.assert_async().await;
+}
+
+#[tokio::test]
+async fn test_context_manager_uses_api_counts() {
+    let mut server = mockito::Server::new_async().await;
+    let _earlier = server.mock("POST", "/messages/count_tokens")
+        .match_body(Matcher::PartialJson(json!({"messages": [{"content": [{"text": "An earlier, very long reply"}]}]})))
+        .with_status(200)
+        .with_body(count_body(80))
+        .create_async()
+        .await;
+    let _latest = server.mock("POST", "/messages/count_tokens")
+        .match_body(Matcher::PartialJson(json!({"messages": [{"content": [{"text": "Follow-up"}]}]})))
+        .with_status(200)
+        .with_body(count_body(60))
+        .create_async()
+        .await;
+
+    let client = Claude::new("test-api-key").with_base_url(server.url());
+    let manager = AdaptiveContextManager::with_token_counter(100, SimpleImportanceScorer, ApiTokenCounter::new(&client));
+    manager.update_with_response(&create_text_response("An earlier, very long reply")).await.unwrap();
+
+    // 80 + 60 tokens exceed the limit, so only the latest message is kept
+    let messages = manager.process_messages(vec![user_message("Follow-up")]).await.unwrap();
+    assert_eq!(messages.len(), 1);
+    assert!(matches!(&messages[0].content[0], Content::Text { text, .. } if text == "Follow-up"));
+}
+
+/// Counts every message as 50 tokens and records how often it was asked
+struct FixedTokenCounter {
+    calls: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl AsyncTokenCounter for FixedTokenCounter {
+    async fn message_tokens(&self, _message: &Message) -> ClaudeResult<u32> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        Ok(50)
+    }
+
+    async fn system_tokens(&self, _system: &SystemPrompt) -> ClaudeResult<u32> {
+        Ok(0)
+    }
+}
+
+#[tokio::test]
+async fn test_context_manager_counts_only_what_it_needs() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counter = FixedTokenCounter { calls: calls.clone() };
+    let manager = AdaptiveContextManager::with_token_counter(100, SimpleImportanceScorer, counter);
+    for i in 0..10 {
+        manager.update_with_response(&create_text_response(&format!("Reply {}", i))).await.unwrap();
+    }
+
+    let messages = manager.process_messages(vec![user_message("Follow-up")]).await.unwrap();
+
+    // The latest message and one reply fit; the latest is not repeated
+    assert_eq!(messages.len(), 2);
+    assert!(matches!(&messages[0].content[0], Content::Text { text, .. } if text == "Follow-up"));
+    // Three counts (newest first) find the overflow, one more picks the reply
+    assert_eq!(calls.load(Ordering::SeqCst), 4);
+}
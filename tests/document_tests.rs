@@ -0,0 +1,145 @@
+use claude_rs::{Citation, CitationLocation, Claude, Content, ContentEvent, DocumentSource, Message, Role, SimpleTokenCounter, TokenCounter};
+use claude_rs::types::*;
+use futures::StreamExt;
+use serde_json::json;
+
+mod mock_api_client;
+use mock_api_client::{delta_event as event, streaming_client};
+
+#[test]
+fn test_document_sources_serialize_for_the_api() {
+    let pdf = Content::document(DocumentSource::pdf(b"%PDF-1.7"))
+        .with_title("Report")
+        .with_citations(true);
+    assert_eq!(serde_json::to_value(pdf).unwrap(), json!({
+        "type": "document",
+        "source": {"type": "base64", "media_type": "application/pdf", "data": "JVBERi0xLjc="},
+        "title": "Report",
+        "citations": {"enabled": true}
+    }));
+
+    let text = Content::document(DocumentSource::text("The grass is green."))
+        .with_context("Written by a gardener");
+    assert_eq!(serde_json::to_value(text).unwrap(), json!({
+        "type": "document",
+        "source": {"type": "text", "media_type": "text/plain", "data": "The grass is green."},
+        "context": "Written by a gardener"
+    }));
+
+    let chunks = Content::document(DocumentSource::content(["First chunk", "Second chunk"]));
+    assert_eq!(serde_json::to_value(chunks).unwrap(), json!({
+        "type": "document",
+        "source": {"type": "content", "content": [
+            {"type": "text", "text": "First chunk"},
+            {"type": "text", "text": "Second chunk"}
+        ]}
+    }));
+
+    // Document settings do not apply to other blocks
+    let plain = Content::text("Hello").with_title("Ignored").with_citations(true);
+    assert_eq!(serde_json::to_value(plain).unwrap(), json!({"type": "text", "text": "Hello"}));
+}
+
+#[test]
+fn test_response_citations_are_parsed() {
+    let response: MessageResponse = serde_json::from_value(json!({
+        "id": "msg_1",
+        "model": "claude-3-7-sonnet-20250219",
+        "type": "message",
+        "role": "assistant",
+        "content": [
+            {"type": "text", "text": "According to the documents, "},
+            {"type": "text", "text": "the grass is green", "citations": [
+                {"type": "char_location", "cited_text": "The grass is green.", "document_index": 0,
+                 "document_title": "Garden", "start_char_index": 0, "end_char_index": 20},
+                {"type": "page_location", "cited_text": "Grass is green", "document_index": 1,
+                 "document_title": null, "start_page_number": 2, "end_page_number": 3},
+                {"type": "content_block_location", "cited_text": "Second chunk", "document_index": 2,
+                 "start_block_index": 1, "end_block_index": 2},
+                {"type": "web_search_result_location", "cited_text": "Green grass", "url": "https://example.com"}
+            ]}
+        ],
+        "stop_reason": "end_turn",
+        "usage": {"input_tokens": 100, "output_tokens": 10}
+    })).unwrap();
+
+    assert!(response.content[0].citations().is_empty());
+    let citations = response.content[1].citations();
+    assert_eq!(citations.len(), 4);
+    assert_eq!(citations[0], Citation {
+        cited_text: "The grass is green.".to_string(),
+        document_index: 0,
+        document_title: Some("Garden".to_string()),
+        location: CitationLocation::CharLocation { start_char_index: 0, end_char_index: 20 },
+    });
+    assert_eq!(citations[1].location, CitationLocation::PageLocation { start_page_number: 2, end_page_number: 3 });
+    assert_eq!(citations[1].document_title, None);
+    assert_eq!(citations[2].location, CitationLocation::ContentBlockLocation { start_block_index: 1, end_block_index: 2 });
+    assert_eq!(citations[3].location, CitationLocation::Unknown);
+    assert_eq!(citations[3].cited_text, "Green grass");
+
+    // Cited answers can be sent back as conversation history
+    let history = serde_json::to_value(&response.content[1]).unwrap();
+    assert_eq!(history["citations"][0]["type"], "char_location");
+    assert_eq!(history["citations"][1]["start_page_number"], 2);
+}
+
+#[tokio::test]
+async fn test_streamed_citations_are_accumulated() {
+    let events = vec![
+        event(json!({"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": "", "citations": []}})),
+        event(json!({"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "The grass is green"}})),
+        event(json!({"type": "content_block_delta", "index": 0, "delta": {"type": "citations_delta", "citation": {
+            "type": "char_location", "cited_text": "The grass is green.", "document_index": 0,
+            "document_title": "Garden", "start_char_index": 0, "end_char_index": 20
+        }}})),
+        event(json!({"type": "content_block_stop", "index": 0})),
+    ];
+    let client = streaming_client(Claude::new("test-api-key"), events);
+
+    let mut stream = client.message()
+        .add_message(Message {
+            role: Role::User,
+            content: vec![
+                Content::document(DocumentSource::text("The grass is green.")).with_title("Garden").with_citations(true),
+                Content::text("What color is the grass?"),
+            ],
+        })
+        .stream_content()
+        .await
+        .unwrap();
+
+    let mut cited = Vec::new();
+    let mut completed = Vec::new();
+    while let Some(event) = stream.next().await {
+        match event.unwrap() {
+            ContentEvent::CitationDelta { index, citation } => cited.push((index, citation)),
+            ContentEvent::BlockComplete { block, .. } => completed.push(block),
+            _ => {}
+        }
+    }
+
+    assert_eq!(cited.len(), 1);
+    assert_eq!(cited[0].1.cited_text, "The grass is green.");
+    assert_eq!(completed.len(), 1);
+    assert_eq!(completed[0].citations(), &[cited[0].1.clone()]);
+}
+
+#[test]
+fn test_document_tokens_are_estimated() {
+    let counter = SimpleTokenCounter;
+    let message = |content| Message { role: Role::User, content: vec![content] };
+    let overhead = counter.count_message_tokens(&Message { role: Role::User, content: vec![] });
+
+    let text = "The grass is green. The sky is blue.";
+    let document = Content::document(DocumentSource::text(text));
+    assert_eq!(counter.count_message_tokens(&message(document)) - overhead, counter.count_tokens(text));
+
+    // PDFs are estimated per page
+    let one_page = counter.count_message_tokens(&message(Content::document(DocumentSource::pdf(b"%PDF /Type /Pages /Type /Page"))));
+    let three_pages = counter.count_message_tokens(&message(Content::document(DocumentSource::pdf(
+        b"%PDF /Type /Pages /Type /Page /Type/Page /Type /Page",
+    ))));
+    assert!(one_page > overhead);
+    assert_eq!(three_pages - overhead, 3 * (one_page - overhead));
+}
@@ -62,7 +62,7 @@ fn assert_request_contains(mock_api: &MockApiClient, expected_content: &str) {
     assert!(!user_messages.is_empty(), "No user messages in the request");
     
     let content_text = match &user_messages[0].content[0] {
-        Content::Text { text } => text,
+        Content::Text { text, .. } => text,
         _ => panic!("Expected text content"),
     };
     
@@ -159,9 +159,7 @@ async fn test_content_generation_client() {
     
     // Configure a sample response for blog post generation
     let mut response = create_sample_message_response();
-    response.content = vec![Content::Text { 
-        text: "# The Future of AI\n\nArtificial intelligence has come a long way in recent years...".to_string()
-    }];
+    response.content = vec![Content::text("# The Future of AI\n\nArtificial intelligence has come a long way in recent years...".to_string())];
     mock_api.add_mock(ClaudeModel::Sonnet, response.clone());
     
     // Create a Claude client with the mock API
@@ -185,7 +183,7 @@ async fn test_content_generation_client() {
     // Verify that the user message contains blog post related text
     let user_message = &requests[0].messages[0];
     assert_eq!(user_message.role, Role::User);
-    if let Some(Content::Text { text }) = user_message.content.first() {
+    if let Some(Content::Text { text, .. }) = user_message.content.first() {
         assert!(text.contains("The Future of AI"));
     } else {
         panic!("User message doesn't contain text content");
@@ -196,9 +194,7 @@ async fn test_content_generation_client() {
     
     // Configure a sample response for product description
     let mut product_response = create_sample_message_response();
-    product_response.content = vec![Content::Text { 
-        text: "Introducing the AirFlow Pro, the revolutionary air purifier that transforms your living space...".to_string()
-    }];
+    product_response.content = vec![Content::text("Introducing the AirFlow Pro, the revolutionary air purifier that transforms your living space...".to_string())];
     mock_api.add_mock(ClaudeModel::Sonnet, product_response);
     
     // Test product_description method
@@ -226,7 +222,7 @@ async fn test_content_generation_client() {
     // Verify that the user message contains product description related text
     let user_message = &requests[0].messages[0];
     assert_eq!(user_message.role, Role::User);
-    if let Some(Content::Text { text }) = user_message.content.first() {
+    if let Some(Content::Text { text, .. }) = user_message.content.first() {
         assert!(text.contains("AirFlow Pro Air Purifier"));
         assert!(text.to_lowercase().contains("hepa filtration"));
     } else {
@@ -284,7 +280,7 @@ async fn test_code_assistance_client() {
     // Verify that the user message contains code analysis related text
     let user_message = &requests[0].messages[0];
     assert_eq!(user_message.role, Role::User);
-    if let Some(Content::Text { text }) = user_message.content.first() {
+    if let Some(Content::Text { text, .. }) = user_message.content.first() {
         assert!(text.contains("javascript"));
         assert!(text.contains("function processUser"));
     } else {
@@ -296,9 +292,7 @@ async fn test_code_assistance_client() {
     
     // Configure a sample response for code documentation
     let mut doc_response = create_sample_message_response();
-    doc_response.content = vec![Content::Text { 
-        text: "/**\n * Processes a user object\n * @param {User} user - The user to process\n * @returns {boolean} - Processing success status\n */".to_string()
-    }];
+    doc_response.content = vec![Content::text("/**\n * Processes a user object\n * @param {User} user - The user to process\n * @returns {boolean} - Processing success status\n */".to_string())];
     mock_api.add_mock(ClaudeModel::Sonnet, doc_response);
     
     // Test generate_docs method
@@ -315,7 +309,7 @@ async fn test_code_assistance_client() {
     // Verify that the user message contains documentation generation related text
     let user_message = &requests[0].messages[0];
     assert_eq!(user_message.role, Role::User);
-    if let Some(Content::Text { text }) = user_message.content.first() {
+    if let Some(Content::Text { text, .. }) = user_message.content.first() {
         assert!(text.contains("JSDoc"));
         assert!(text.contains("function processUser"));
     } else {
@@ -411,7 +405,7 @@ async fn test_domain_client_streaming() {
     
     // Start a streaming operation directly with the client
     let builder = client.message().user_content(
-        &format!("Analyze this code: {}", code_sample)
+        format!("Analyze this code: {}", code_sample)
     );
     
     let mut stream = builder.stream().await.unwrap();
@@ -0,0 +1,136 @@
+use claude_rs::{ApiErrorKind, Claude, ClaudeError};
+use futures::StreamExt;
+use std::time::Duration;
+
+#[test]
+fn test_parse_error_envelope() {
+    let body = r#"{"type":"error","error":{"type":"invalid_request_error","message":"max_tokens: field required"}}"#;
+    let error = ClaudeError::from_api_response(400, body, Some("req_123".to_string()), None, None);
+
+    match &error {
+        ClaudeError::ApiError { status, message, kind, request_id, .. } => {
+            assert_eq!(*status, 400);
+            assert_eq!(message, "max_tokens: field required");
+            assert_eq!(*kind, ApiErrorKind::InvalidRequestError);
+            assert_eq!(request_id.as_deref(), Some("req_123"));
+        }
+        other => panic!("Expected ApiError, got {:?}", other),
+    }
+
+    assert_eq!(error.request_id(), Some("req_123"));
+    assert!(!error.is_retryable());
+    assert!(!error.is_auth_failure());
+}
+
+#[test]
+fn test_all_error_kinds_parse() {
+    let cases = [
+        ("invalid_request_error", ApiErrorKind::InvalidRequestError),
+        ("authentication_error", ApiErrorKind::AuthenticationError),
+        ("permission_error", ApiErrorKind::PermissionError),
+        ("not_found_error", ApiErrorKind::NotFoundError),
+        ("request_too_large", ApiErrorKind::RequestTooLarge),
+        ("rate_limit_error", ApiErrorKind::RateLimitError),
+        ("api_error", ApiErrorKind::ApiError),
+        ("overloaded_error", ApiErrorKind::OverloadedError),
+    ];
+
+    for (wire, expected) in cases {
+        let parsed: ApiErrorKind = serde_json::from_str(&format!("\"{}\"", wire)).unwrap();
+        assert_eq!(parsed, expected);
+        assert_eq!(parsed.as_str(), wire);
+    }
+
+    let parsed: ApiErrorKind = serde_json::from_str("\"brand_new_error\"").unwrap();
+    assert_eq!(parsed, ApiErrorKind::Unknown);
+}
+
+#[test]
+fn test_unparseable_body_falls_back_to_status() {
+    let error = ClaudeError::from_api_response(529, "<html>Overloaded</html>", None, None, None);
+
+    assert_eq!(error.api_error_kind(), Some(ApiErrorKind::OverloadedError));
+    assert!(error.is_overloaded());
+    assert!(error.is_retryable());
+    assert_eq!(error.status(), Some(529));
+}
+
+#[test]
+fn test_rate_limit_response() {
+    let body = r#"{"type":"error","error":{"type":"rate_limit_error","message":"Too many requests"}}"#;
+    let error = ClaudeError::from_api_response(
+        429, body, Some("req_abc".to_string()), Some(Duration::from_secs(3)), None
+    );
+
+    assert!(matches!(error, ClaudeError::RateLimited { .. }));
+    assert!(error.is_rate_limited());
+    assert!(error.is_retryable());
+    assert_eq!(error.retry_after(), Some(Duration::from_secs(3)));
+    assert_eq!(error.request_id(), Some("req_abc"));
+}
+
+#[test]
+fn test_auth_failures() {
+    let body = r#"{"type":"error","error":{"type":"authentication_error","message":"invalid x-api-key"}}"#;
+    let error = ClaudeError::from_api_response(401, body, None, None, None);
+    assert!(error.is_auth_failure());
+    assert!(!error.is_retryable());
+
+    let error = ClaudeError::from_api_response(403, "", None, None, None);
+    assert_eq!(error.api_error_kind(), Some(ApiErrorKind::PermissionError));
+    assert!(error.is_auth_failure());
+
+    assert!(ClaudeError::MissingApiKey { location: None }.is_auth_failure());
+}
+
+#[test]
+fn test_non_api_errors_have_no_kind() {
+    let error = ClaudeError::ValidationError("bad".to_string());
+
+    assert_eq!(error.api_error_kind(), None);
+    assert_eq!(error.status(), None);
+    assert!(!error.is_retryable());
+    assert!(!error.is_auth_failure());
+}
+
+#[tokio::test]
+async fn test_http_error_captures_kind_and_request_id() {
+    let mut server = mockito::Server::new_async().await;
+    let _mock = server.mock("POST", "/messages")
+        .with_status(404)
+        .with_header("request-id", "req_011CSHoEeqs5C35K2UUqR7Fy")
+        .with_body(r#"{"type":"error","error":{"type":"not_found_error","message":"model: claude-unknown"}}"#)
+        .create_async()
+        .await;
+
+    let client = Claude::new("test-api-key").with_base_url(server.url());
+    let error = client.message().user_content("Hello").send().await.unwrap_err();
+
+    assert_eq!(error.api_error_kind(), Some(ApiErrorKind::NotFoundError));
+    assert_eq!(error.status(), Some(404));
+    assert_eq!(error.request_id(), Some("req_011CSHoEeqs5C35K2UUqR7Fy"));
+}
+
+#[tokio::test]
+async fn test_stream_error_event_is_typed() {
+    let mut server = mockito::Server::new_async().await;
+    let _mock = server.mock("POST", "/messages")
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_body(concat!(
+            "event: ping\n",
+            "data: {\"type\":\"ping\"}\n\n",
+            "event: error\n",
+            "data: {\"type\":\"error\",\"error\":{\"type\":\"overloaded_error\",\"message\":\"Overloaded\"}}\n\n",
+        ))
+        .create_async()
+        .await;
+
+    let client = Claude::new("test-api-key").with_base_url(server.url());
+    let mut stream = client.message().user_content("Hello").stream().await.unwrap();
+
+    assert!(stream.next().await.unwrap().is_ok());
+    let error = stream.next().await.unwrap().unwrap_err();
+    assert!(error.is_overloaded());
+    assert_eq!(error.status(), Some(529));
+}
@@ -0,0 +1,206 @@
+use claude_rs::{Claude, ClaudeError, Content, Image, ImageMediaType, ImageSource, Message, Role, TokenCounter, SimpleTokenCounter};
+use claude_rs::{estimate_image_tokens, MAX_IMAGE_BYTES, MAX_IMAGE_TOKENS};
+use claude_rs::types::*;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde_json::json;
+
+mod mock_api_client;
+use mock_api_client::create_text_response;
+
+/// The start of a PNG file, up to the end of its IHDR chunk
+fn png_header(width: u32, height: u32) -> Vec<u8> {
+    let mut data = vec![0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0, 0, 0, 13];
+    data.extend_from_slice(b"IHDR");
+    data.extend_from_slice(&width.to_be_bytes());
+    data.extend_from_slice(&height.to_be_bytes());
+    data.extend_from_slice(&[8, 6, 0, 0, 0]);
+    data
+}
+
+/// The start of a JPEG file, with an APP0 segment before the frame header
+fn jpeg_header(width: u16, height: u16) -> Vec<u8> {
+    let mut data = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10];
+    data.extend_from_slice(b"JFIF\0\x01\x01\0\0\x01\0\x01\0\0");
+    data.extend_from_slice(&[0xFF, 0xC0, 0x00, 0x11, 0x08]);
+    data.extend_from_slice(&height.to_be_bytes());
+    data.extend_from_slice(&width.to_be_bytes());
+    data
+}
+
+fn gif_header(width: u16, height: u16) -> Vec<u8> {
+    let mut data = b"GIF89a".to_vec();
+    data.extend_from_slice(&width.to_le_bytes());
+    data.extend_from_slice(&height.to_le_bytes());
+    data
+}
+
+fn webp_header(width: u32, height: u32) -> Vec<u8> {
+    let mut data = b"RIFF\0\0\0\0WEBPVP8X\x0a\0\0\0\0\0\0\0".to_vec();
+    data.extend_from_slice(&(width - 1).to_le_bytes()[..3]);
+    data.extend_from_slice(&(height - 1).to_le_bytes()[..3]);
+    data
+}
+
+fn image_message(images: impl IntoIterator<Item = Content>) -> Message {
+    Message {
+        role: Role::User,
+        content: images.into_iter().collect(),
+    }
+}
+
+#[test]
+fn test_media_type_and_dimensions_are_detected() {
+    let cases = [
+        (png_header(640, 480), ImageMediaType::Png, (640, 480)),
+        (jpeg_header(1024, 768), ImageMediaType::Jpeg, (1024, 768)),
+        (gif_header(32, 16), ImageMediaType::Gif, (32, 16)),
+        (webp_header(3000, 2000), ImageMediaType::Webp, (3000, 2000)),
+    ];
+
+    for (data, media_type, (width, height)) in cases {
+        let image = Image::from_bytes(data).unwrap();
+        assert_eq!(image.media_type(), media_type);
+        assert_eq!((image.width(), image.height()), (width, height));
+    }
+
+    let source = Image::from_bytes(png_header(10, 10)).unwrap().into_source().unwrap();
+    assert!(matches!(source, ImageSource::Base64 { ref media_type, .. } if media_type == "image/png"));
+}
+
+#[test]
+fn test_invalid_and_oversized_images_are_rejected() {
+    let unsupported = Image::from_bytes(b"BM not a supported format".to_vec());
+    assert!(matches!(unsupported, Err(ClaudeError::InvalidParameter(_))));
+
+    let truncated = Image::from_bytes(png_header(10, 10)[..12].to_vec());
+    assert!(matches!(truncated, Err(ClaudeError::InvalidParameter(_))));
+
+    let too_wide = Image::from_bytes(png_header(9000, 100)).unwrap();
+    assert!(matches!(too_wide.into_content(), Err(ClaudeError::ValidationError(message)) if message.contains("9000x100")));
+
+    let mut too_big = png_header(100, 100);
+    too_big.resize(MAX_IMAGE_BYTES, 0);
+    let too_big = Image::from_bytes(too_big).unwrap();
+    assert!(too_big.encoded_len() > MAX_IMAGE_BYTES);
+    assert!(matches!(too_big.validate(), Err(ClaudeError::ValidationError(_))));
+}
+
+#[tokio::test]
+async fn test_image_from_file() {
+    let path = std::env::temp_dir().join(format!("claude-rs-image-{}.gif", std::process::id()));
+    std::fs::write(&path, gif_header(300, 200)).unwrap();
+
+    let image = Image::from_file(&path).await.unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!((image.width(), image.height()), (300, 200));
+
+    let missing = Image::from_file(&path).await;
+    assert!(matches!(missing, Err(ClaudeError::InvalidParameter(message)) if message.contains("Failed to read image")));
+}
+
+#[test]
+fn test_image_sources_serialize_for_the_api() {
+    let url = serde_json::to_value(Content::image_url("https://example.com/cat.jpg")).unwrap();
+    assert_eq!(url, json!({"type": "image", "source": {"type": "url", "url": "https://example.com/cat.jpg"}}));
+
+    let base64 = serde_json::to_value(Content::image(ImageSource::base64("image/gif", "R0lGODlh"))).unwrap();
+    assert_eq!(base64, json!({"type": "image", "source": {"type": "base64", "media_type": "image/gif", "data": "R0lGODlh"}}));
+}
+
+#[test]
+fn test_image_tokens_follow_dimensions() {
+    assert_eq!(estimate_image_tokens(200, 200), 54);
+    assert_eq!(estimate_image_tokens(1000, 1000), 1334);
+    // Large images are scaled down before they are counted
+    let large = estimate_image_tokens(4000, 3000);
+    assert!(large > 1500 && large <= MAX_IMAGE_TOKENS);
+
+    let counter = SimpleTokenCounter;
+    let overhead = counter.count_message_tokens(&image_message([]));
+    let small = Image::from_bytes(png_header(200, 200)).unwrap().into_content().unwrap();
+    assert_eq!(counter.count_message_tokens(&image_message([small])) - overhead, 54);
+    // The dimensions of a URL image are unknown, so it counts as the maximum
+    let remote = Content::image_url("https://example.com/cat.jpg");
+    assert_eq!(counter.count_message_tokens(&image_message([remote])) - overhead, MAX_IMAGE_TOKENS);
+}
+
+#[tokio::test]
+async fn test_non_ascii_image_data_does_not_panic() {
+    // A multibyte character straddles the end of the prefix decoded for the header
+    let data = format!("{}é{}", "A".repeat(64 * 1024 - 1), "A".repeat(100));
+    let image = || Content::image(ImageSource::base64("image/png", data.clone()));
+
+    let counter = SimpleTokenCounter;
+    let overhead = counter.count_message_tokens(&image_message([]));
+    assert_eq!(counter.count_message_tokens(&image_message([image()])) - overhead, MAX_IMAGE_TOKENS);
+
+    let client = Claude::new("test-api-key");
+    client.set_request_handler(Box::new(|_request: MessageRequest| {
+        Box::pin(async move { Ok(create_text_response("A picture")) })
+            as std::pin::Pin<Box<dyn std::future::Future<Output = ClaudeResult<MessageResponse>> + Send>>
+    }));
+    assert!(client.message().add_message(image_message([image()])).send().await.is_ok());
+}
+
+#[tokio::test]
+async fn test_request_image_limits_are_enforced() {
+    let client = Claude::new("test-api-key");
+    client.set_request_handler(Box::new(|_request: MessageRequest| {
+        Box::pin(async move { Ok(create_text_response("A picture")) })
+            as std::pin::Pin<Box<dyn std::future::Future<Output = ClaudeResult<MessageResponse>> + Send>>
+    }));
+    let image = |width| Image::from_bytes(png_header(width, 100)).unwrap().into_content().unwrap();
+
+    // Up to 20 images can be 8000 pixels wide
+    let result = client.message().add_message(image_message((0..20).map(|_| image(2500)))).send().await;
+    assert!(result.is_ok());
+
+    // With more images, each side is limited to 2000 pixels
+    let result = client.message().add_message(image_message((0..21).map(|_| image(2500)))).send().await;
+    assert!(matches!(result, Err(ClaudeError::ValidationError(message)) if message.contains("2000")));
+
+    let result = client.message().add_message(image_message((0..101).map(|_| image(10)))).send().await;
+    assert!(matches!(result, Err(ClaudeError::ValidationError(message)) if message.contains("at most 100 images")));
+}
+
+#[tokio::test]
+async fn test_only_image_headers_are_decoded() {
+    let client = Claude::new("test-api-key");
+    client.set_request_handler(Box::new(|_request: MessageRequest| {
+        Box::pin(async move { Ok(create_text_response("A picture")) })
+            as std::pin::Pin<Box<dyn std::future::Future<Output = ClaudeResult<MessageResponse>> + Send>>
+    }));
+
+    // The body after the header is not valid base64, so the dimensions can
+    // only be checked if the header is decoded on its own
+    let mut header = png_header(9000, 100);
+    header.resize(48, 0);
+    let data = format!("{}{}", BASE64.encode(header), "*".repeat(100 * 1024));
+    let image = Content::image(ImageSource::base64("image/png", data));
+
+    let result = client.message().add_message(image_message([image])).send().await;
+    assert!(matches!(result, Err(ClaudeError::ValidationError(message)) if message.contains("9000x100")));
+}
+
+#[cfg(feature = "image")]
+#[test]
+fn test_large_images_are_downscaled() {
+    use claude_rs::OPTIMAL_IMAGE_LONG_EDGE;
+
+    let pixels = image::RgbImage::from_fn(3000, 1000, |x, y| image::Rgb([(x % 256) as u8, (y % 256) as u8, 128]));
+    let mut png = std::io::Cursor::new(Vec::new());
+    image::DynamicImage::ImageRgb8(pixels).write_to(&mut png, image::ImageOutputFormat::Png).unwrap();
+
+    let original = Image::from_bytes(png.into_inner()).unwrap();
+    let resized = original.clone().fit_to_limits().unwrap();
+    assert_eq!(resized.width(), OPTIMAL_IMAGE_LONG_EDGE);
+    assert!((522..=523).contains(&resized.height()));
+    assert_eq!(resized.media_type(), ImageMediaType::Png);
+    // The stored dimensions match the re-encoded data
+    let reloaded = Image::from_bytes(resized.data().to_vec()).unwrap();
+    assert_eq!((reloaded.width(), reloaded.height()), (resized.width(), resized.height()));
+
+    let unchanged = original.clone().downscale(4000).unwrap();
+    assert_eq!(unchanged.data(), original.data());
+}
@@ -136,7 +136,7 @@ async fn test_domain_client_with_middleware() {
     #[async_trait::async_trait]
     impl RequestMiddleware for SentimentAnalysisMiddleware {
         async fn process_request(&self, mut request: MessageRequest) -> Result<MessageRequest, ClaudeError> {
-            request.system = Some("You are a helpful assistant specialized in sentiment analysis.".to_string());
+            request.system = Some("You are a helpful assistant specialized in sentiment analysis.".into());
             Ok(request)
         }
     }
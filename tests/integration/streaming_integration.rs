@@ -2,9 +2,18 @@
 
 #[cfg(feature = "reactive")]
 use claude_rs::reactive::ReactiveResponseStatus;
+#[cfg(feature = "reactive")]
+use futures::StreamExt;
 
 // Initialize the test environment
-// use super::init;
+#[cfg(feature = "reactive")]
+use super::init;
+#[cfg(feature = "reactive")]
+use crate::setup_mock_with_streaming_text;
+#[cfg(feature = "reactive")]
+use crate::create_test_code_analysis_response;
+#[cfg(feature = "reactive")]
+use claude_rs::types::ClaudeModel;
 
 /// Test domain clients with streaming responses
 #[tokio::test]
@@ -19,12 +28,12 @@ async fn test_domain_client_with_streaming() {
     ).await;
     
     // Get a domain client
-    let code_client = client.code();
+    let _code_client = client.code();
     
     // Use regular message builder from the base client
     let builder = client.message().user_content(
         "function test() { return 'hello'; }"
-    ).system_prompt("You are a code analyst. Analyze this function.");
+    ).system("You are a code analyst. Analyze this function.").unwrap();
     
     // Request a streaming response
     let mut stream = builder.stream().await.unwrap();
@@ -46,18 +55,9 @@ async fn test_domain_client_with_streaming() {
     let requests = mock_api.get_request_history();
     assert_eq!(requests.len(), 1);
     
-    // Check that the system message was included
-    let system_message = requests[0].messages.iter()
-        .find(|msg| msg.role == Role::System);
-    
-    assert!(system_message.is_some());
-    if let Some(message) = system_message {
-        if let Content::Text { text } = &message.content[0] {
-            assert!(text.contains("You are a code analyst"));
-        } else {
-            panic!("Expected text content");
-        }
-    }
+    // Check that the system prompt was included
+    let system = requests[0].system.as_ref().expect("Expected a system prompt");
+    assert!(system.text().contains("You are a code analyst"));
 }
 
 /// Test integration between domain client operations and streaming
@@ -73,17 +73,25 @@ async fn test_streaming_domain_client_integration() {
         vec!["Integration ", "test ", "with ", "domain ", "client."]
     ).await;
     
+    // The domain operation gets a regular (non-streaming) response
+    mock_api.add_mock(
+        ClaudeModel::Sonnet,
+        create_test_code_analysis_response(vec![("Missing type checks", "Low")], 2)
+    );
+    
     // Get the domain client
     let code_client = client.code();
     
     // Use domain client methods to build a message
-    let response = code_client.generate_test_harness(
+    let analysis = code_client.analyze_code(
         "function sum(a, b) { return a + b; }", 
         "javascript"
-    ).await.unwrap();
+    ).await.expect("Code analysis should succeed");
+    assert_eq!(analysis.issues.len(), 1);
+    assert_eq!(analysis.complexity_score, 2);
     
     // The domain client method internally just uses Claude client with mocks
-    assert!(mock_api.get_request_history().len() >= 1);
+    assert_eq!(mock_api.get_request_history().len(), 1);
     
     // Use the same client for streaming to confirm both capabilities work
     let builder = client.message().user_content("Stream test");
@@ -97,7 +97,7 @@ async fn test_middleware_with_domains() {
     impl RequestMiddleware for PrefixMiddleware {
         async fn process_request(&self, mut request: MessageRequest) -> ClaudeResult<MessageRequest> {
             if let Some(ref mut system) = request.system {
-                *system = format!("PREFIX: {}", system);
+                *system = format!("PREFIX: {}", system).into();
             }
             Ok(request)
         }
@@ -0,0 +1,113 @@
+use claude_rs::{Claude, RateLimitWindow};
+use claude_rs::types::*;
+use futures::StreamExt;
+
+mod mock_api_client;
+use mock_api_client::create_text_response;
+
+mod test_helpers;
+use test_helpers::response_body;
+
+#[tokio::test]
+async fn test_send_with_metadata_reads_headers() {
+    let mut server = mockito::Server::new_async().await;
+    let _mock = server.mock("POST", "/messages")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_header("request-id", "req_018abc")
+        .with_header("anthropic-ratelimit-requests-limit", "50")
+        .with_header("anthropic-ratelimit-requests-remaining", "49")
+        .with_header("anthropic-ratelimit-requests-reset", "2026-01-01T00:00:01Z")
+        .with_header("anthropic-ratelimit-output-tokens-remaining", "7990")
+        .with_header("x-custom", "value")
+        .with_body(response_body())
+        .create_async()
+        .await;
+
+    let client = Claude::new("test-api-key").with_base_url(server.url());
+    let (response, metadata) = client.message()
+        .user_content("Hello")
+        .send_with_metadata()
+        .await
+        .unwrap();
+
+    assert_eq!(response.id, "msg_1");
+    assert_eq!(metadata.request_id.as_deref(), Some("req_018abc"));
+    assert_eq!(metadata.status, 200);
+    assert_eq!(metadata.rate_limit.requests, Some(RateLimitWindow {
+        limit: Some(50),
+        remaining: Some(49),
+        reset: Some("2026-01-01T00:00:01Z".to_string()),
+    }));
+    assert_eq!(metadata.rate_limit.output_tokens.unwrap().remaining, Some(7990));
+    assert!(metadata.rate_limit.input_tokens.is_none());
+    assert_eq!(metadata.headers.get("x-custom").unwrap(), "value");
+
+    let total = metadata.total_duration.unwrap();
+    assert!(metadata.time_to_headers <= total);
+    assert!(metadata.time_to_first_token.is_none());
+}
+
+#[tokio::test]
+async fn test_stream_metadata_records_first_token_and_duration() {
+    let mut server = mockito::Server::new_async().await;
+    let body = concat!(
+        "event: message_start\n",
+        "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"model\":\"claude-3-sonnet-20240229\",\"content\":[],\"stop_reason\":null,\"stop_sequence\":null}}\n\n",
+        "event: content_block_delta\n",
+        "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Hi\"}}\n\n",
+        "event: message_stop\n",
+        "data: {\"type\":\"message_stop\"}\n\n",
+    );
+    let _mock = server.mock("POST", "/messages")
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_header("request-id", "req_stream")
+        .with_body(body)
+        .create_async()
+        .await;
+
+    let client = Claude::new("test-api-key").with_base_url(server.url());
+    let (mut stream, metadata) = client.message()
+        .user_content("Hello")
+        .stream_with_metadata()
+        .await
+        .unwrap();
+
+    // Headers are available before the stream is consumed
+    let opened = metadata.get();
+    assert_eq!(opened.request_id.as_deref(), Some("req_stream"));
+    assert!(opened.time_to_first_token.is_none());
+    assert!(opened.total_duration.is_none());
+
+    while let Some(event) = stream.next().await {
+        event.unwrap();
+    }
+
+    let finished = metadata.get();
+    let first_token = finished.time_to_first_token.unwrap();
+    let total = finished.total_duration.unwrap();
+    assert!(finished.time_to_headers <= first_token);
+    assert!(first_token <= total);
+}
+
+#[tokio::test]
+async fn test_mock_handler_responses_have_empty_metadata() {
+    let client = Claude::new("test-api-key");
+    client.set_request_handler(Box::new(|_request: MessageRequest| {
+        Box::pin(async move { Ok(create_text_response("Hello")) })
+            as std::pin::Pin<Box<dyn std::future::Future<Output = ClaudeResult<MessageResponse>> + Send>>
+    }));
+
+    let (response, metadata) = client.message()
+        .user_content("Hello")
+        .send_with_metadata()
+        .await
+        .unwrap();
+
+    assert_eq!(response.usage.input_tokens, 10);
+    assert_eq!(metadata.status, 200);
+    assert!(metadata.request_id.is_none());
+    assert!(metadata.headers.is_empty());
+    assert!(metadata.total_duration.is_some());
+}
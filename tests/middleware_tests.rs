@@ -26,9 +26,9 @@ impl RequestMiddleware for TestRequestMiddleware {
         
         // Modify the request in some way
         if let Some(ref mut sys) = request.system {
-            *sys = format!("[Modified] {}", sys);
+            *sys = format!("[Modified] {}", sys).into();
         } else {
-            request.system = Some("[Added by middleware]".to_string());
+            request.system = Some("[Added by middleware]".into());
         }
         
         Ok(request)
@@ -57,7 +57,7 @@ impl ResponseMiddleware for TestResponseMiddleware {
         *self.processed.lock().unwrap() = true;
         
         // Modify the response content if it's text
-        if let Some(Content::Text { text }) = response.content.first_mut() {
+        if let Some(Content::Text { text, .. }) = response.content.first_mut() {
             *text = format!("[Modified] {}", text);
         }
         
@@ -74,21 +74,24 @@ async fn test_request_middleware() {
     let request = MessageRequest {
         model: "claude-3-sonnet-20240229".to_string(),
         messages: vec![],
-        system: Some("Original system prompt".to_string()),
+        system: Some("Original system prompt".into()),
         temperature: None,
         max_tokens: None,
         tools: None,
+        tool_choice: None,
+        thinking: None,
         top_p: None,
         top_k: None,
         stop_sequences: vec![],
         stream: None,
+        cost_tag: None,
     };
     
     // Process the request
     let processed = middleware.process_request(request).await.unwrap();
     
     // Verify modifications
-    assert_eq!(processed.system, Some("[Modified] Original system prompt".to_string()));
+    assert_eq!(processed.system, Some("[Modified] Original system prompt".into()));
     assert_eq!(middleware.get_headers(), vec!["X-Test-Header"]);
 }
 
@@ -100,9 +103,7 @@ async fn test_response_middleware() {
     // Create a response
     let response = MessageResponse {
         id: "msg_123".to_string(),
-        content: vec![Content::Text {
-            text: "Original response".to_string(),
-        }],
+        content: vec![Content::text("Original response".to_string())],
         model: "claude-3-sonnet-20240229".to_string(),
         role: Role::Assistant,
         stop_reason: None,
@@ -110,6 +111,8 @@ async fn test_response_middleware() {
         usage: Usage {
             input_tokens: 10,
             output_tokens: 5,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
         },
         r#type: "message".to_string(),
     };
@@ -118,7 +121,7 @@ async fn test_response_middleware() {
     let processed = middleware.process_response(response).await.unwrap();
     
     // Verify modifications
-    if let Content::Text { text } = &processed.content[0] {
+    if let Content::Text { text, .. } = &processed.content[0] {
         assert_eq!(text, "[Modified] Original response");
     } else {
         panic!("Expected Content::Text variant");
@@ -1,5 +1,7 @@
 use claude_rs::types::*;
 use claude_rs::client::MockApiHandler;
+use claude_rs::Claude;
+use futures::StreamExt;
 use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
 use std::pin::Pin;
@@ -319,18 +321,107 @@ pub fn create_text_response(text: &str) -> MessageResponse {
         model: "claude-3-sonnet-20240229".to_string(),
         r#type: "message".to_string(),
         role: Role::Assistant,
-        content: vec![Content::Text { 
-            text: text.to_string() 
-        }],
+        content: vec![Content::text(text.to_string())],
         usage: Usage {
             input_tokens: 10,
             output_tokens: text.split_whitespace().count() as u32,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
         },
         stop_reason: Some("end_turn".to_string()),
         stop_sequence: None,
     }
 }
 
+/// Requests received by a client from `recording_client`, in order
+#[allow(dead_code)]
+pub type RequestLog = Arc<Mutex<Vec<MessageRequest>>>;
+
+/// Answer every request to `client` with `respond` and record it
+///
+/// `respond` is called with the request and the number of requests received
+/// before it.
+#[allow(dead_code)]
+pub fn recording_client<F>(client: Claude, respond: F) -> (Claude, RequestLog)
+where
+    F: Fn(&MessageRequest, usize) -> ClaudeResult<MessageResponse> + Send + Sync + 'static,
+{
+    let requests: RequestLog = Arc::new(Mutex::new(Vec::new()));
+    let history = requests.clone();
+
+    client.set_request_handler(Box::new(move |request: MessageRequest| {
+        let mut history = history.lock().unwrap();
+        let response = respond(&request, history.len());
+        history.push(request);
+        Box::pin(async move { response })
+            as Pin<Box<dyn Future<Output = ClaudeResult<MessageResponse>> + Send>>
+    }));
+
+    (client, requests)
+}
+
+/// Answer requests with `responses` in order, failing once they run out
+#[allow(dead_code)]
+pub fn scripted(responses: Vec<MessageResponse>) -> impl Fn(&MessageRequest, usize) -> ClaudeResult<MessageResponse> + Send + Sync + 'static {
+    move |_, index| responses.get(index).cloned()
+        .ok_or_else(|| ClaudeError::simple_api_error("No more scripted responses", 500))
+}
+
+/// Fail the first `failures` requests with `error`, then answer with `response`
+#[allow(dead_code)]
+pub fn flaky(failures: usize, error: ClaudeError, response: MessageResponse) -> impl Fn(&MessageRequest, usize) -> ClaudeResult<MessageResponse> + Send + Sync + 'static {
+    move |_, index| if index < failures {
+        Err(error.clone())
+    } else {
+        Ok(response.clone())
+    }
+}
+
+/// Parse a streaming event from its JSON form
+#[allow(dead_code)]
+pub fn delta_event(value: serde_json::Value) -> DeltaEvent {
+    serde_json::from_value(value).unwrap()
+}
+
+/// A text delta for the first content block
+#[allow(dead_code)]
+pub fn text_delta(text: &str) -> DeltaEvent {
+    delta_event(serde_json::json!({
+        "type": "content_block_delta",
+        "index": 0,
+        "delta": {"type": "text_delta", "text": text}
+    }))
+}
+
+/// Answer every streaming request to `client` with `events`
+#[allow(dead_code)]
+pub fn streaming_client(client: Claude, events: Vec<DeltaEvent>) -> Claude {
+    client.set_stream_handler(Box::new(move |_request: MessageRequest| {
+        let events = futures::stream::iter(events.clone().into_iter().map(Ok));
+        Box::pin(async move { Ok(events.boxed() as MessageStream) })
+            as Pin<Box<dyn Future<Output = ClaudeResult<MessageStream>> + Send>>
+    }));
+    client
+}
+
+/// Answer every streaming request to `client` with `events`, then never end
+///
+/// Each event is sent after its delay in milliseconds.
+#[allow(dead_code)]
+pub fn stalling_client(client: Claude, events: Vec<(u64, DeltaEvent)>) -> Claude {
+    client.set_stream_handler(Box::new(move |_request: MessageRequest| {
+        let events = futures::stream::iter(events.clone())
+            .then(|(delay, event)| async move {
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                Ok(event)
+            })
+            .chain(futures::stream::pending());
+        Box::pin(async move { Ok(events.boxed() as MessageStream) })
+            as Pin<Box<dyn Future<Output = ClaudeResult<MessageStream>> + Send>>
+    }));
+    client
+}
+
 // Helper function to create a sample message response with JSON in a code block
 #[allow(dead_code)]
 pub fn create_json_response(json: &str) -> MessageResponse {
@@ -420,94 +511,116 @@ pub fn create_sample_delta_events() -> Vec<DeltaEvent> {
                 stop_sequence: None,
                 role: Some(Role::Assistant),
                 type_field: Some("message".to_string()),
+                usage: None,
             }),
             delta: None,
             usage: None,
             index: Some(0),
+            content_block: None,
         },
         DeltaEvent {
             event_type: "content_block_delta".to_string(),
             message: Some(DeltaMessage {
                 id: "msg_sample123".to_string(),
                 model: "claude-3-sonnet-20240229".to_string(),
-                content: Some(vec![Content::Text {
-                    text: "This is ".to_string(),
-                }]),
+                content: Some(vec![Content::text("This is ".to_string())]),
                 stop_reason: None,
                 stop_sequence: None,
                 role: Some(Role::Assistant),
                 type_field: Some("message".to_string()),
+                usage: None,
             }),
             delta: Some(Delta {
                 text: Some("This is ".to_string()),
                 stop_reason: None,
                 stop_sequence: None,
+                delta_type: None,
+                partial_json: None,
+                thinking: None,
+                signature: None,
+                citation: None,
             }),
             usage: None,
             index: Some(1),
+            content_block: None,
         },
         DeltaEvent {
             event_type: "content_block_delta".to_string(),
             message: Some(DeltaMessage {
                 id: "msg_sample123".to_string(),
                 model: "claude-3-sonnet-20240229".to_string(),
-                content: Some(vec![Content::Text {
-                    text: "a sample ".to_string(),
-                }]),
+                content: Some(vec![Content::text("a sample ".to_string())]),
                 stop_reason: None,
                 stop_sequence: None,
                 role: Some(Role::Assistant),
                 type_field: Some("message".to_string()),
+                usage: None,
             }),
             delta: Some(Delta {
                 text: Some("a sample ".to_string()),
                 stop_reason: None,
                 stop_sequence: None,
+                delta_type: None,
+                partial_json: None,
+                thinking: None,
+                signature: None,
+                citation: None,
             }),
             usage: None,
             index: Some(2),
+            content_block: None,
         },
         DeltaEvent {
             event_type: "content_block_delta".to_string(),
             message: Some(DeltaMessage {
                 id: "msg_sample123".to_string(),
                 model: "claude-3-sonnet-20240229".to_string(),
-                content: Some(vec![Content::Text {
-                    text: "streaming response".to_string(),
-                }]),
+                content: Some(vec![Content::text("streaming response".to_string())]),
                 stop_reason: None,
                 stop_sequence: None,
                 role: Some(Role::Assistant),
                 type_field: Some("message".to_string()),
+                usage: None,
             }),
             delta: Some(Delta {
                 text: Some("streaming response".to_string()),
                 stop_reason: None,
                 stop_sequence: None,
+                delta_type: None,
+                partial_json: None,
+                thinking: None,
+                signature: None,
+                citation: None,
             }),
             usage: None,
             index: Some(3),
+            content_block: None,
         },
         DeltaEvent {
             event_type: "content_block_delta".to_string(),
             message: Some(DeltaMessage {
                 id: "msg_sample123".to_string(),
                 model: "claude-3-sonnet-20240229".to_string(),
-                content: Some(vec![Content::Text {
-                    text: " from the mock API.".to_string(),
-                }]),
+                content: Some(vec![Content::text(" from the mock API.".to_string())]),
                 stop_reason: None,
                 stop_sequence: None,
                 role: Some(Role::Assistant),
                 type_field: Some("message".to_string()),
+                usage: None,
             }),
             delta: Some(Delta {
                 text: Some(" from the mock API.".to_string()),
                 stop_reason: None,
                 stop_sequence: None,
+                delta_type: None,
+                partial_json: None,
+                thinking: None,
+                signature: None,
+                citation: None,
             }),
             usage: None,
             index: Some(4),
+            content_block: None,
         },
         DeltaEvent {
             event_type: "message_delta".to_string(),
@@ -519,17 +632,26 @@ pub fn create_sample_delta_events() -> Vec<DeltaEvent> {
                 stop_sequence: None,
                 role: Some(Role::Assistant),
                 type_field: Some("message".to_string()),
+                usage: None,
             }),
             delta: Some(Delta {
                 text: None,
                 stop_reason: Some("end_turn".to_string()),
                 stop_sequence: None,
+                delta_type: None,
+                partial_json: None,
+                thinking: None,
+                signature: None,
+                citation: None,
             }),
             usage: Some(Usage {
                 input_tokens: 15,
                 output_tokens: 12,
+                cache_creation_input_tokens: 0,
+                cache_read_input_tokens: 0,
             }),
             index: Some(5),
+            content_block: None,
         },
     ]
 }
@@ -0,0 +1,172 @@
+use claude_rs::{get_token_counter, ApiModel, Claude, Claude3TokenCounter, ClaudeModel, ModelInfo, ModelRegistry, TokenCounter};
+use claude_rs::types::*;
+use mockito::Matcher;
+use serde_json::json;
+
+mod mock_api_client;
+use mock_api_client::{create_text_response, RequestLog};
+
+/// Creates a client for `model` that answers every request with "ok" and records it
+fn recording_client(model: ClaudeModel) -> (Claude, RequestLog) {
+    let client = Claude::new("test-api-key").with_model(model);
+    mock_api_client::recording_client(client, |_, _| Ok(create_text_response("ok")))
+}
+
+fn api_model(id: &str, display_name: &str) -> serde_json::Value {
+    json!({"type": "model", "id": id, "display_name": display_name, "created_at": "2025-05-14T00:00:00Z"})
+}
+
+#[test]
+fn test_builtin_models_and_families() {
+    let haiku = ClaudeModel::Haiku.info().unwrap();
+    assert_eq!(haiku.context_window, 200_000);
+    assert_eq!(haiku.max_output_tokens, 4_096);
+    assert!(haiku.supports_vision && haiku.supports_tools && !haiku.supports_thinking);
+
+    assert!(ClaudeModel::Sonnet37.info().unwrap().supports_thinking);
+
+    // Aliases and unknown snapshots of a known family share its limits
+    let alias = ClaudeModel::Custom("claude-sonnet-4-0".to_string()).info().unwrap();
+    assert_eq!(alias.id, "claude-sonnet-4-0");
+    assert_eq!(alias.max_output_tokens, 64_000);
+    let latest = ModelRegistry::global().get("claude-3-5-sonnet-latest").unwrap();
+    assert_eq!(latest.display_name, "Claude Sonnet 3.5 (New)");
+
+    assert!(ClaudeModel::Custom("not-a-model".to_string()).info().is_none());
+}
+
+#[test]
+fn test_refresh_registers_new_snapshots() {
+    let registry = ModelRegistry::with_builtin_models();
+    let models: Vec<ApiModel> = serde_json::from_value(json!([
+        api_model("claude-opus-4-20250514", "Claude Opus 4 (renamed)"),
+        api_model("claude-opus-4-20260101", "Claude Opus 4 (2026)"),
+        api_model("claude-mystery-1-20260101", "Claude Mystery 1"),
+    ])).unwrap();
+
+    let unknown = registry.refresh(&models);
+    assert_eq!(unknown, vec!["claude-mystery-1-20260101".to_string()]);
+    assert_eq!(registry.get("claude-opus-4-20250514").unwrap().display_name, "Claude Opus 4 (renamed)");
+
+    let snapshot = registry.get("claude-opus-4-20260101").unwrap();
+    assert_eq!(snapshot.display_name, "Claude Opus 4 (2026)");
+    assert_eq!(snapshot.max_output_tokens, 32_000);
+    assert!(registry.models().iter().any(|info| info.id == "claude-opus-4-20260101"));
+}
+
+#[tokio::test]
+async fn test_list_follows_pages_and_retrieve() {
+    let mut server = mockito::Server::new_async().await;
+    let _first = server.mock("GET", "/models")
+        .match_query(Matcher::Missing)
+        .match_header("x-api-key", "test-api-key")
+        .with_status(200)
+        .with_body(json!({
+            "data": [api_model("claude-sonnet-4-20250514", "Claude Sonnet 4")],
+            "has_more": true,
+            "first_id": "claude-sonnet-4-20250514",
+            "last_id": "claude-sonnet-4-20250514",
+        }).to_string())
+        .create_async()
+        .await;
+    let _second = server.mock("GET", "/models")
+        .match_query(Matcher::UrlEncoded("after_id".into(), "claude-sonnet-4-20250514".into()))
+        .with_status(200)
+        .with_body(json!({
+            "data": [api_model("claude-3-haiku-20240307", "Claude Haiku 3")],
+            "has_more": false,
+            "first_id": "claude-3-haiku-20240307",
+            "last_id": "claude-3-haiku-20240307",
+        }).to_string())
+        .create_async()
+        .await;
+    let _retrieve = server.mock("GET", "/models/claude-sonnet-4-0")
+        .with_status(200)
+        .with_body(api_model("claude-sonnet-4-20250514", "Claude Sonnet 4").to_string())
+        .create_async()
+        .await;
+
+    let client = Claude::new("test-api-key").with_base_url(server.url());
+    let models = client.models();
+
+    let ids: Vec<String> = models.list().await.unwrap().into_iter().map(|model| model.id).collect();
+    assert_eq!(ids, vec!["claude-sonnet-4-20250514", "claude-3-haiku-20240307"]);
+
+    let model = models.retrieve("claude-sonnet-4-0").await.unwrap();
+    assert_eq!(model.id, "claude-sonnet-4-20250514");
+    assert_eq!(model.display_name, "Claude Sonnet 4");
+}
+
+#[tokio::test]
+async fn test_default_max_tokens_come_from_the_model() {
+    // A custom id of a known model gets its limits
+    let (client, requests) = recording_client(ClaudeModel::Custom("claude-3-5-haiku-latest".to_string()));
+    client.message().user_content("Hello").send().await.unwrap();
+    assert_eq!(requests.lock().unwrap()[0].max_tokens, Some(4_096));
+
+    // The client default is capped at the model's maximum output
+    let (client, requests) = recording_client(ClaudeModel::Haiku);
+    let client = client.with_default_max_tokens(8_000).unwrap();
+    client.message().user_content("Hello").send().await.unwrap();
+    assert_eq!(requests.lock().unwrap()[0].max_tokens, Some(4_096));
+
+    // Thinking budgets are added to the default
+    let (client, requests) = recording_client(ClaudeModel::Sonnet37);
+    client.message().user_content("Hello").thinking(2_000).unwrap().send().await.unwrap();
+    assert_eq!(requests.lock().unwrap()[0].max_tokens, Some(6_096));
+
+    // Unknown models are left to the API
+    let (client, requests) = recording_client(ClaudeModel::Custom("not-a-model".to_string()));
+    client.message().user_content("Hello").send().await.unwrap();
+    assert_eq!(requests.lock().unwrap()[0].max_tokens, None);
+}
+
+#[tokio::test]
+async fn test_requests_are_validated_against_the_model() {
+    let (client, requests) = recording_client(ClaudeModel::Haiku);
+
+    let result = client.message().user_content("Hello").max_tokens(8_000).unwrap().send().await;
+    assert!(matches!(result, Err(ClaudeError::ValidationError(message)) if message.contains("maximum output")));
+
+    let result = client.message()
+        .user_content("Hello")
+        .max_tokens(4_000).unwrap()
+        .thinking(2_000).unwrap()
+        .send()
+        .await;
+    assert!(matches!(result, Err(ClaudeError::ValidationError(message)) if message.contains("thinking")));
+
+    ModelRegistry::global().register(
+        ModelInfo::new("test-text-only-model", "Text Only", 8_000, 1_000).with_vision(false).with_tools(false)
+    );
+    let image = Message {
+        role: Role::User,
+        content: vec![Content::Image {
+            source: ImageSource::base64("image/png", "iVBORw0KGgo="),
+            cache_control: None,
+        }],
+    };
+    let result = client.message()
+        .model(ClaudeModel::Custom("test-text-only-model".to_string()))
+        .add_message(image)
+        .send()
+        .await;
+    assert!(matches!(result, Err(ClaudeError::ValidationError(message)) if message.contains("images")));
+
+    let result = client.message()
+        .model(ClaudeModel::Custom("test-text-only-model".to_string()))
+        .user_content("Hello")
+        .add_tool(Tool::from_type::<serde_json::Value>("lookup", "Look something up"))
+        .send()
+        .await;
+    assert!(matches!(result, Err(ClaudeError::ValidationError(message)) if message.contains("tools")));
+
+    assert!(requests.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_token_counter_uses_registry() {
+    let text = "Counting tokens for a custom model id";
+    let counter = get_token_counter(&ClaudeModel::Custom("claude-sonnet-4-5".to_string()));
+    assert_eq!(counter.count_tokens(text), Claude3TokenCounter.count_tokens(text));
+}
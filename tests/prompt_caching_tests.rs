@@ -0,0 +1,224 @@
+use claude_rs::{Claude, ClaudeModel, DomainPrompt, StreamAccumulator};
+use claude_rs::types::*;
+use serde_json::json;
+
+mod mock_api_client;
+use mock_api_client::{create_text_response, recording_client, scripted};
+
+fn lookup_tool(name: &str) -> Tool {
+    Tool {
+        name: name.to_string(),
+        description: "Look something up".to_string(),
+        input_schema: json!({"type": "object", "properties": {}}),
+        cache_control: None,
+    }
+}
+
+#[test]
+fn test_cache_control_wire_format() {
+    let block = Content::text("Long document").with_cache_control(CacheControl::ephemeral());
+    assert_eq!(
+        serde_json::to_value(&block).unwrap(),
+        json!({"type": "text", "text": "Long document", "cache_control": {"type": "ephemeral"}})
+    );
+
+    let tool = lookup_tool("lookup").with_cache_control(CacheControl::ephemeral_with_ttl(CacheTtl::OneHour));
+    assert_eq!(
+        serde_json::to_value(&tool).unwrap()["cache_control"],
+        json!({"type": "ephemeral", "ttl": "1h"})
+    );
+
+    // Blocks without a breakpoint serialize exactly as before
+    assert_eq!(serde_json::to_value(Content::text("Hi")).unwrap(), json!({"type": "text", "text": "Hi"}));
+    assert!(serde_json::to_value(lookup_tool("lookup")).unwrap().get("cache_control").is_none());
+
+    assert_eq!(serde_json::to_value(SystemPrompt::from("Be brief")).unwrap(), json!("Be brief"));
+    assert_eq!(
+        serde_json::to_value(SystemPrompt::Blocks(vec![SystemBlock::new("Be brief").with_cache_control(CacheControl::ephemeral())])).unwrap(),
+        json!([{"type": "text", "text": "Be brief", "cache_control": {"type": "ephemeral"}}])
+    );
+}
+
+#[test]
+fn test_usage_reports_cache_tokens() {
+    let response: MessageResponse = serde_json::from_value(json!({
+        "id": "msg_1",
+        "model": "claude-3-sonnet-20240229",
+        "type": "message",
+        "role": "assistant",
+        "content": [{"type": "text", "text": "Hi"}],
+        "usage": {
+            "input_tokens": 12,
+            "output_tokens": 5,
+            "cache_creation_input_tokens": 1500,
+            "cache_read_input_tokens": 0
+        },
+        "stop_reason": "end_turn",
+        "stop_sequence": null
+    })).unwrap();
+
+    assert_eq!(response.usage.cache_creation_input_tokens, 1500);
+    assert_eq!(response.usage.total_input_tokens(), 1512);
+
+    let mut total = Usage::default();
+    total += &response.usage;
+    total += &Usage { input_tokens: 12, output_tokens: 5, cache_creation_input_tokens: 0, cache_read_input_tokens: 1500 };
+    assert_eq!(total.cache_creation_input_tokens, 1500);
+    assert_eq!(total.cache_read_input_tokens, 1500);
+}
+
+#[test]
+fn test_usage_accepts_null_cache_tokens() {
+    let usage: Usage = serde_json::from_value(json!({
+        "input_tokens": 12,
+        "output_tokens": 5,
+        "cache_creation_input_tokens": null,
+        "cache_read_input_tokens": null
+    })).unwrap();
+
+    assert_eq!(usage.cache_creation_input_tokens, 0);
+    assert_eq!(usage.cache_read_input_tokens, 0);
+}
+
+#[test]
+fn test_streamed_usage_keeps_cache_tokens() {
+    let events: Vec<DeltaEvent> = serde_json::from_value(json!([
+        {"type": "message_start", "message": {
+            "id": "msg_1", "model": "claude-3-sonnet-20240229", "role": "assistant", "content": [],
+            "usage": {"input_tokens": 12, "output_tokens": 1, "cache_creation_input_tokens": 0, "cache_read_input_tokens": 1500}
+        }},
+        {"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "Hi"}},
+        {"type": "message_delta", "delta": {"stop_reason": "end_turn"},
+         "usage": {"output_tokens": 5, "cache_creation_input_tokens": 300, "cache_read_input_tokens": null}}
+    ])).unwrap();
+
+    let mut accumulator = StreamAccumulator::new();
+    for event in &events {
+        accumulator.apply(event).unwrap();
+    }
+
+    let usage = accumulator.usage();
+    assert_eq!(usage.output_tokens, 5);
+    assert_eq!(usage.cache_creation_input_tokens, 300);
+    assert_eq!(usage.cache_read_input_tokens, 1500);
+}
+
+#[tokio::test]
+async fn test_builder_places_breakpoints() {
+    let client = Claude::new("test-api-key").with_model(ClaudeModel::Sonnet);
+    let (client, requests) = recording_client(client, scripted(vec![create_text_response("ok")]));
+
+    client.message()
+        .cached_system("You are a meticulous librarian.", CacheControl::ephemeral())
+        .unwrap()
+        .add_tool(lookup_tool("search"))
+        .add_tool(lookup_tool("lookup"))
+        .cache_tools(CacheControl::ephemeral())
+        .add_message(Message {
+            role: Role::User,
+            content: vec![
+                Content::text("A long reference document").with_cache_control(CacheControl::ephemeral()),
+                Content::text("What does it say?"),
+            ],
+        })
+        .send()
+        .await
+        .unwrap();
+
+    let requests = requests.lock().unwrap();
+    let request = &requests[0];
+    let tools = request.tools.as_ref().unwrap();
+    assert_eq!(tools[0].cache_control, None);
+    assert_eq!(tools[1].cache_control, Some(CacheControl::ephemeral()));
+    assert!(matches!(&request.system, Some(SystemPrompt::Blocks(blocks)) if blocks[0].cache_control.is_some()));
+    assert_eq!(request.cache_breakpoints(), 3);
+}
+
+#[tokio::test]
+async fn test_too_many_breakpoints_are_rejected() {
+    let client = Claude::new("test-api-key").with_model(ClaudeModel::Sonnet);
+    let (client, requests) = recording_client(client, scripted(vec![create_text_response("unused")]));
+
+    let content = (0..5)
+        .map(|i| Content::text(format!("Part {}", i)).with_cache_control(CacheControl::ephemeral()))
+        .collect();
+    let result = client.message()
+        .add_message(Message { role: Role::User, content })
+        .send()
+        .await;
+
+    assert!(matches!(result, Err(ClaudeError::ValidationError(message)) if message.contains("at most 4")));
+    assert!(requests.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_domain_prompt_content() {
+    let prompt = DomainPrompt::new("Summarize the text.\n\n", "Text: hello");
+    assert_eq!(prompt.text(), "Summarize the text.\n\nText: hello");
+
+    let content = prompt.to_content(None);
+    assert!(matches!(&content[..], [Content::Text { text, cache_control: None, .. }] if text == "Summarize the text.\n\nText: hello"));
+
+    let content = prompt.to_content(Some(&CacheControl::ephemeral()));
+    assert_eq!(content.len(), 2);
+    assert_eq!(content[0].cache_control(), Some(&CacheControl::ephemeral()));
+    assert_eq!(content[1].cache_control(), None);
+
+    // A prompt without fixed instructions has nothing worth caching
+    let content = DomainPrompt::from("Text: hello").to_content(Some(&CacheControl::ephemeral()));
+    assert!(matches!(&content[..], [Content::Text { cache_control: None, .. }]));
+}
+
+#[tokio::test]
+async fn test_domain_clients_cache_instructions() {
+    let response = || {
+        let mut response = create_text_response("");
+        response.content = vec![Content::tool_use(
+            "toolu_1",
+            "report_sentiment",
+            json!({"score": 0.8, "sentiment": "Positive", "aspects": {}}),
+        )];
+        response
+    };
+
+    let client = Claude::new("test-api-key")
+        .with_model(ClaudeModel::Sonnet)
+        .with_domain_prompt_caching(CacheControl::ephemeral());
+    let (client, requests) = recording_client(client, scripted(vec![response()]));
+    client.sentiment().analyze_text("I love it").await.unwrap();
+
+    let message = requests.lock().unwrap()[0].messages[0].clone();
+    assert_eq!(message.content.len(), 2);
+    match (&message.content[0], &message.content[1]) {
+        (Content::Text { text: instructions, cache_control: Some(_), .. }, Content::Text { text: input, cache_control: None, .. }) => {
+            assert!(instructions.starts_with("Analyze the sentiment"));
+            assert!(input.contains("I love it"));
+        }
+        other => panic!("Expected cached instructions followed by the input, got {:?}", other),
+    }
+
+    // Without caching the prompt stays a single block
+    let client = Claude::new("test-api-key").with_model(ClaudeModel::Sonnet);
+    let (client, requests) = recording_client(client, scripted(vec![response()]));
+    client.sentiment().analyze_text("I love it").await.unwrap();
+
+    let message = requests.lock().unwrap()[0].messages[0].clone();
+    assert!(matches!(&message.content[..], [Content::Text { text, cache_control: None, .. }] if text.contains("I love it")));
+}
+
+#[tokio::test]
+async fn test_domain_prompt_caching_applies_to_existing_clients() {
+    let response = create_text_response(r#"{"score": 0.8, "sentiment": "Positive", "aspects": {}}"#);
+    let client = Claude::new("test-api-key").with_model(ClaudeModel::Sonnet);
+    let (client, requests) = recording_client(client, scripted(vec![response.clone(), response]));
+
+    // Domain clients taken before caching is enabled do not hold on to the old setting
+    client.sentiment().analyze_text("I love it").await.unwrap();
+    let caching = client.clone().with_domain_prompt_caching(CacheControl::ephemeral());
+    caching.sentiment().analyze_text("I love it").await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests[0].messages[0].content.len(), 1);
+    assert_eq!(requests[1].messages[0].content.len(), 2);
+    assert!(requests[1].messages[0].content[0].cache_control().is_some());
+}
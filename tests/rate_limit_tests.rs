@@ -0,0 +1,222 @@
+use claude_rs::{Claude, ClaudeModel, RateLimiter, RateLimits};
+use claude_rs::types::*;
+use futures::StreamExt;
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+mod mock_api_client;
+use mock_api_client::{create_text_response, delta_event, stalling_client, streaming_client};
+
+mod test_helpers;
+use test_helpers::response_body;
+
+fn message_start() -> DeltaEvent {
+    delta_event(json!({"type": "message_start", "message": {"id": "msg_1", "model": "claude-3-sonnet-20240229", "role": "assistant", "content": [], "stop_reason": null, "stop_sequence": null, "usage": {"input_tokens": 10, "output_tokens": 1}}}))
+}
+
+#[tokio::test]
+async fn test_requests_wait_for_tokens_to_refill() {
+    // 600 tokens per minute refill at 10 tokens per second
+    let limiter = RateLimiter::new(RateLimits::new().with_input_tokens_per_minute(600));
+
+    let start = Instant::now();
+    limiter.acquire(600, 0).await.settle(&Usage { input_tokens: 600, ..Usage::default() });
+    assert!(start.elapsed() < Duration::from_millis(50));
+
+    limiter.acquire(3, 0).await.settle(&Usage { input_tokens: 3, ..Usage::default() });
+    assert!(start.elapsed() >= Duration::from_millis(250));
+}
+
+#[tokio::test]
+async fn test_waiting_requests_are_served_in_order() {
+    let limiter = RateLimiter::new(RateLimits::new().with_output_tokens_per_minute(600));
+    limiter.acquire(0, 600).await.settle(&Usage { output_tokens: 600, ..Usage::default() });
+
+    let order = Arc::new(Mutex::new(Vec::new()));
+    let mut handles = Vec::new();
+    for i in 0..4 {
+        let limiter = limiter.clone();
+        let order = order.clone();
+        handles.push(tokio::spawn(async move {
+            // Later tasks ask for less, but must not overtake earlier ones
+            let permit = limiter.acquire(0, 4 - i).await;
+            order.lock().unwrap().push(i);
+            permit.release();
+        }));
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    for handle in handles {
+        handle.await.unwrap();
+    }
+
+    assert_eq!(*order.lock().unwrap(), vec![0, 1, 2, 3]);
+}
+
+#[tokio::test]
+async fn test_unused_output_reservation_is_returned() {
+    let limiter = RateLimiter::new(RateLimits::new().with_output_tokens_per_minute(600));
+
+    let permit = limiter.acquire(0, 600).await;
+    assert_eq!(permit.output_tokens(), 600);
+    permit.settle(&Usage { output_tokens: 20, ..Usage::default() });
+
+    let start = Instant::now();
+    limiter.acquire(0, 500).await.release();
+    assert!(start.elapsed() < Duration::from_millis(50));
+}
+
+#[tokio::test]
+async fn test_client_requests_are_throttled_by_estimate() {
+    let client = Claude::new("test-api-key")
+        .with_model(ClaudeModel::Sonnet37)
+        .with_rate_limiter(RateLimiter::new(RateLimits::new().with_output_tokens_per_minute(6_000)));
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let history = requests.clone();
+    client.set_request_handler(Box::new(move |_request: MessageRequest| {
+        history.lock().unwrap().push(Instant::now());
+        Box::pin(async move {
+            // The response uses all of its max_tokens
+            let mut response = create_text_response("ok");
+            response.usage.output_tokens = 6_000;
+            Ok(response)
+        }) as std::pin::Pin<Box<dyn std::future::Future<Output = ClaudeResult<MessageResponse>> + Send>>
+    }));
+
+    client.message().user_content("Hello").max_tokens(6_000).unwrap().send().await.unwrap();
+    // 50 output tokens refill in half a second
+    client.message().user_content("Hello").max_tokens(50).unwrap().send().await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert!(requests[1] - requests[0] >= Duration::from_millis(450));
+}
+
+#[tokio::test]
+async fn test_limits_are_learned_from_headers() {
+    let mut server = mockito::Server::new_async().await;
+    let _mock = server.mock("POST", "/messages")
+        .with_status(200)
+        .with_header("anthropic-ratelimit-requests-limit", "50")
+        .with_header("anthropic-ratelimit-requests-remaining", "49")
+        .with_header("anthropic-ratelimit-input-tokens-limit", "40000")
+        .with_header("anthropic-ratelimit-input-tokens-remaining", "39000")
+        .with_header("anthropic-ratelimit-output-tokens-limit", "8000")
+        .with_header("anthropic-ratelimit-output-tokens-remaining", "8000")
+        .with_body(response_body())
+        .create_async()
+        .await;
+
+    // The configured output limit is lower than the API's and is kept
+    let limiter = RateLimiter::new(RateLimits::new().with_output_tokens_per_minute(4_000));
+    let client = Claude::new("test-api-key")
+        .with_base_url(server.url())
+        .with_rate_limiter(limiter.clone());
+    client.message().user_content("Hello").send().await.unwrap();
+
+    assert_eq!(limiter.limits(), RateLimits {
+        requests_per_minute: Some(50),
+        input_tokens_per_minute: Some(40_000),
+        output_tokens_per_minute: Some(4_000),
+    });
+}
+
+#[tokio::test]
+async fn test_rate_limit_errors_pause_requests() {
+    let mut server = mockito::Server::new_async().await;
+    let _limited = server.mock("POST", "/messages")
+        .with_status(429)
+        .with_header("retry-after-ms", "300")
+        .with_body(json!({"type": "error", "error": {"type": "rate_limit_error", "message": "Slow down"}}).to_string())
+        .expect(1)
+        .create_async()
+        .await;
+
+    let limiter = RateLimiter::default();
+    let client = Claude::new("test-api-key")
+        .with_base_url(server.url())
+        .with_rate_limiter(limiter);
+
+    let result = client.message().user_content("Hello").send().await;
+    assert!(result.unwrap_err().is_rate_limited());
+
+    let _ok = server.mock("POST", "/messages")
+        .with_status(200)
+        .with_body(response_body())
+        .create_async()
+        .await;
+    let start = Instant::now();
+    client.message().user_content("Hello").send().await.unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(250));
+}
+
+#[tokio::test]
+async fn test_streams_settle_when_usage_arrives() {
+    let limiter = RateLimiter::new(RateLimits::new().with_output_tokens_per_minute(600));
+    let client = Claude::new("test-api-key")
+        .with_model(ClaudeModel::Sonnet)
+        .with_rate_limiter(limiter.clone());
+    let client = streaming_client(client, vec![
+        message_start(),
+        delta_event(json!({"type": "message_delta", "delta": {"stop_reason": "end_turn", "stop_sequence": null}, "usage": {"output_tokens": 5}})),
+        delta_event(json!({"type": "message_stop"})),
+    ]);
+
+    // The whole output budget is reserved while the stream is open
+    let stream = client.message().user_content("Hello").max_tokens(600).unwrap().stream().await.unwrap();
+    let _: Vec<_> = stream.collect().await;
+
+    // Only the 5 tokens actually generated are kept
+    let start = Instant::now();
+    limiter.acquire(0, 590).await.release();
+    assert!(start.elapsed() < Duration::from_millis(50));
+}
+
+#[tokio::test]
+async fn test_reported_remaining_is_not_corrected_again() {
+    let mut server = mockito::Server::new_async().await;
+    let _mock = server.mock("POST", "/messages")
+        .with_status(200)
+        .with_header("anthropic-ratelimit-input-tokens-limit", "1000")
+        .with_header("anthropic-ratelimit-input-tokens-remaining", "600")
+        .with_body(json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-3-sonnet-20240229",
+            "content": [{"type": "text", "text": "ok"}],
+            "stop_reason": "end_turn",
+            "stop_sequence": null,
+            "usage": {"input_tokens": 400, "output_tokens": 5}
+        }).to_string())
+        .create_async()
+        .await;
+
+    let limiter = RateLimiter::new(RateLimits::new().with_input_tokens_per_minute(1_000));
+    let client = Claude::new("test-api-key")
+        .with_base_url(server.url())
+        .with_rate_limiter(limiter.clone());
+    client.message().user_content("Hello").send().await.unwrap();
+
+    // The 600 remaining tokens already account for the 400 used
+    let permit = tokio::time::timeout(Duration::from_millis(200), limiter.acquire(550, 0)).await;
+    assert!(permit.is_ok(), "the request was counted twice");
+}
+
+#[tokio::test]
+async fn test_dropped_streams_release_their_reservation() {
+    let limiter = RateLimiter::new(RateLimits::new().with_output_tokens_per_minute(600));
+    let client = Claude::new("test-api-key")
+        .with_model(ClaudeModel::Sonnet)
+        .with_rate_limiter(limiter.clone());
+    // The stream never reports its final usage
+    let client = stalling_client(client, vec![(0, message_start())]);
+
+    let mut stream = client.message().user_content("Hello").max_tokens(600).unwrap().stream().await.unwrap();
+    assert!(stream.next().await.unwrap().is_ok());
+    drop(stream);
+
+    // The whole output budget is available again
+    let start = Instant::now();
+    limiter.acquire(0, 600).await.release();
+    assert!(start.elapsed() < Duration::from_millis(50));
+}
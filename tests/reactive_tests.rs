@@ -40,7 +40,7 @@ async fn test_reactive_response_creation() {
     
     // Check initial state
     assert_eq!(reactive.current_text(), "");
-    assert_eq!(reactive.is_complete(), false);
+    assert!(!reactive.is_complete());
     assert_eq!(reactive.status(), ReactiveResponseStatus::Initializing);
     assert!(reactive.last_error().is_none());
     assert!(!reactive.has_error());
@@ -154,7 +154,7 @@ async fn test_reactive_client_extension() {
     let reactive = claude.send_reactive(builder).await.unwrap();
     
     // Verify that the ReactiveResponse was created
-    assert_eq!(reactive.is_complete(), false);
+    assert!(!reactive.is_complete());
 }
 
 #[tokio::test]
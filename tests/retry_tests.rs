@@ -0,0 +1,170 @@
+use claude_rs::{Claude, ClaudeModel, RetryPolicy};
+use claude_rs::types::*;
+use std::time::Duration;
+
+mod mock_api_client;
+use mock_api_client::{create_text_response, flaky, recording_client, RequestLog};
+
+/// A fast, deterministic policy for tests
+fn test_policy(max_retries: u32) -> RetryPolicy {
+    RetryPolicy::default()
+        .with_max_retries(max_retries)
+        .with_base_delay(Duration::from_millis(1))
+        .with_max_delay(Duration::from_millis(5))
+        .with_jitter(false)
+}
+
+/// Creates a client whose requests fail `failures` times with `error` before succeeding
+fn flaky_client(failures: usize, error: ClaudeError) -> (Claude, RequestLog) {
+    let client = Claude::new("test-api-key").with_model(ClaudeModel::Sonnet);
+    recording_client(client, flaky(failures, error, create_text_response("Recovered")))
+}
+
+#[tokio::test]
+async fn test_retries_disabled_by_default() {
+    let (client, requests) = flaky_client(1, ClaudeError::rate_limited(None));
+
+    let result = client.message().user_content("Hello").send().await;
+
+    assert!(matches!(result, Err(ClaudeError::RateLimited { .. })));
+    assert_eq!(requests.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_retry_recovers_from_overloaded() {
+    let (client, requests) = flaky_client(2, ClaudeError::simple_api_error("Overloaded", 529));
+    let client = client.with_retry_policy(test_policy(3));
+
+    let response = client.message().user_content("Hello").send().await.unwrap();
+
+    assert_eq!(requests.lock().unwrap().len(), 3);
+    assert!(matches!(response.content.first(), Some(Content::Text { text, .. }) if text == "Recovered"));
+}
+
+#[tokio::test]
+async fn test_retry_gives_up_after_max_retries() {
+    let (client, requests) = flaky_client(10, ClaudeError::simple_api_error("Server error", 500));
+    let client = client.with_retry_policy(test_policy(2));
+
+    let result = client.message().user_content("Hello").send().await;
+
+    assert!(matches!(result, Err(ClaudeError::ApiError { status: 500, .. })));
+    assert_eq!(requests.lock().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_non_retryable_status_is_not_retried() {
+    let (client, requests) = flaky_client(1, ClaudeError::simple_api_error("Bad request", 400));
+    let client = client.with_retry_policy(test_policy(3));
+
+    let result = client.message().user_content("Hello").send().await;
+
+    assert!(matches!(result, Err(ClaudeError::ApiError { status: 400, .. })));
+    assert_eq!(requests.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_builder_policy_overrides_client_policy() {
+    let (client, requests) = flaky_client(1, ClaudeError::rate_limited(None));
+    let client = client.with_retry_policy(test_policy(3));
+
+    let result = client.message()
+        .user_content("Hello")
+        .retry_policy(RetryPolicy::none())
+        .send()
+        .await;
+
+    assert!(result.is_err());
+    assert_eq!(requests.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn test_backoff_delay_is_exponential_and_capped() {
+    let policy = RetryPolicy::default()
+        .with_base_delay(Duration::from_millis(100))
+        .with_max_delay(Duration::from_millis(500))
+        .with_jitter(false);
+
+    assert_eq!(policy.backoff_delay(0), Duration::from_millis(100));
+    assert_eq!(policy.backoff_delay(1), Duration::from_millis(200));
+    assert_eq!(policy.backoff_delay(2), Duration::from_millis(400));
+    assert_eq!(policy.backoff_delay(3), Duration::from_millis(500));
+    assert_eq!(policy.backoff_delay(40), Duration::from_millis(500));
+}
+
+#[test]
+fn test_jitter_stays_within_bounds() {
+    let policy = RetryPolicy::default()
+        .with_base_delay(Duration::from_millis(100))
+        .with_jitter(true);
+
+    for _ in 0..100 {
+        let delay = policy.backoff_delay(1);
+        assert!(delay >= Duration::from_millis(100) && delay <= Duration::from_millis(200));
+    }
+}
+
+#[test]
+fn test_retry_after_takes_precedence() {
+    let policy = test_policy(3).with_max_delay(Duration::from_secs(10));
+    let error = ClaudeError::rate_limited(Some(Duration::from_secs(7)));
+
+    assert_eq!(policy.retry_delay(0, &error), Duration::from_secs(7));
+
+    let policy = policy.with_respect_retry_after(false);
+    assert_eq!(policy.retry_delay(0, &error), Duration::from_millis(1));
+}
+
+#[test]
+fn test_retry_after_is_capped_at_max_delay() {
+    let policy = test_policy(3).with_max_delay(Duration::from_secs(30));
+    let error = ClaudeError::rate_limited(Some(Duration::from_secs(3600)));
+
+    assert_eq!(policy.retry_delay(0, &error), Duration::from_secs(30));
+}
+
+#[test]
+fn test_is_retryable_matches_the_default_policy() {
+    let policy = RetryPolicy::default();
+    for status in [400, 401, 404, 408, 409, 429, 500, 501, 502, 503, 504, 505, 529] {
+        let error = ClaudeError::from_api_response(status, "", None, None, None);
+        assert_eq!(error.is_retryable(), policy.should_retry(&error), "status {}", status);
+    }
+    assert!(!ClaudeError::from_api_response(501, "", None, None, None).is_retryable());
+    assert!(ClaudeError::rate_limited(None).is_retryable());
+}
+
+#[tokio::test]
+async fn test_http_retry_after_header_is_honored() {
+    let mut server = mockito::Server::new_async().await;
+    let overloaded = server.mock("POST", "/messages")
+        .with_status(529)
+        .with_header("retry-after-ms", "10")
+        .with_body(r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#)
+        .expect(1)
+        .create_async()
+        .await;
+    let success = server.mock("POST", "/messages")
+        .with_status(200)
+        .with_header("content-type", "application/json")
+        .with_body(r#"{"id":"msg_1","model":"claude-3-sonnet-20240229","type":"message","role":"assistant","content":[{"type":"text","text":"Hi"}],"usage":{"input_tokens":1,"output_tokens":1},"stop_reason":"end_turn","stop_sequence":null}"#)
+        .expect(1)
+        .create_async()
+        .await;
+
+    let client = Claude::new("test-api-key")
+        .with_base_url(server.url())
+        .with_retry_policy(test_policy(2)
+            .with_base_delay(Duration::from_secs(60))
+            .with_max_delay(Duration::from_secs(60)));
+
+    // The 60s backoff would time out the test, so this only passes if retry-after-ms is used
+    let response = tokio::time::timeout(
+        Duration::from_secs(5),
+        client.message().user_content("Hello").send()
+    ).await.expect("retry-after header was not honored").unwrap();
+
+    assert_eq!(response.id, "msg_1");
+    overloaded.assert_async().await;
+    success.assert_async().await;
+}
@@ -69,12 +69,12 @@ fn create_text_response(text: &str) -> MessageResponse {
         model: "claude-3-sonnet-20240229".to_string(),
         r#type: "message".to_string(),
         role: Role::Assistant,
-        content: vec![Content::Text { 
-            text: text.to_string() 
-        }],
+        content: vec![Content::text(text.to_string())],
         usage: Usage {
             input_tokens: 10,
             output_tokens: 5,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
         },
         stop_reason: Some("end_turn".to_string()),
         stop_sequence: None,
@@ -87,12 +87,12 @@ fn create_json_response(json_content: &str) -> MessageResponse {
         model: "claude-3-sonnet-20240229".to_string(),
         r#type: "message".to_string(),
         role: Role::Assistant,
-        content: vec![Content::Text { 
-            text: json_content.to_string() 
-        }],
+        content: vec![Content::text(json_content.to_string())],
         usage: Usage {
             input_tokens: 10,
             output_tokens: 5,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
         },
         stop_reason: Some("end_turn".to_string()),
         stop_sequence: None,
@@ -221,7 +221,7 @@ mod tests {
         
         // Access the text directly from the response
         let text = match &response.content[0] {
-            Content::Text { text } => text,
+            Content::Text { text, .. } => text,
             _ => panic!("Expected text content"),
         };
         
@@ -0,0 +1,254 @@
+use async_trait::async_trait;
+use claude_rs::{Claude, ClaudeModel, ContentEvent, ContextManager, ResponseMiddleware, StreamAccumulator};
+use claude_rs::types::*;
+use futures::StreamExt;
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+
+mod mock_api_client;
+use mock_api_client::delta_event;
+
+/// Build an SSE body from a list of events
+fn sse_body(events: &[Value]) -> String {
+    events.iter()
+        .map(|event| format!("event: {}\ndata: {}\n\n", event["type"].as_str().unwrap(), event))
+        .collect()
+}
+
+fn tool_use_events() -> Vec<Value> {
+    vec![
+        json!({"type": "message_start", "message": {"id": "msg_1", "model": "claude-3-sonnet-20240229", "role": "assistant", "content": [], "stop_reason": null, "stop_sequence": null, "usage": {"input_tokens": 25, "output_tokens": 1}}}),
+        json!({"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": ""}}),
+        json!({"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "Let me check."}}),
+        json!({"type": "content_block_stop", "index": 0}),
+        json!({"type": "content_block_start", "index": 1, "content_block": {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {}}}),
+        json!({"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": ""}}),
+        json!({"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "{\"location\": \"Tok"}}),
+        json!({"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "yo\", \"days\": 3}"}}),
+        json!({"type": "content_block_stop", "index": 1}),
+        json!({"type": "content_block_start", "index": 2, "content_block": {"type": "tool_use", "id": "toolu_2", "name": "get_time", "input": {}}}),
+        json!({"type": "content_block_stop", "index": 2}),
+        json!({"type": "message_delta", "delta": {"stop_reason": "tool_use", "stop_sequence": null}, "usage": {"output_tokens": 42}}),
+        json!({"type": "message_stop"}),
+    ]
+}
+
+/// Creates a client whose streaming requests replay `tool_use_events`
+fn streaming_client() -> Claude {
+    let client = Claude::new("test-api-key").with_model(ClaudeModel::Sonnet);
+    mock_api_client::streaming_client(client, tool_use_events().into_iter().map(delta_event).collect())
+}
+
+#[tokio::test]
+async fn test_stream_content_reassembles_tool_use() {
+    let mut server = mockito::Server::new_async().await;
+    let _mock = server.mock("POST", "/messages")
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_body(sse_body(&tool_use_events()))
+        .create_async()
+        .await;
+
+    let client = Claude::new("test-api-key").with_base_url(server.url());
+    let mut stream = client.message().user_content("Weather?").stream_content().await.unwrap();
+
+    let mut text = String::new();
+    let mut fragments = String::new();
+    let mut completed = Vec::new();
+    while let Some(event) = stream.next().await {
+        match event.unwrap() {
+            ContentEvent::TextDelta { text: chunk, .. } => text.push_str(&chunk),
+            ContentEvent::InputJsonDelta { index, partial_json } => {
+                assert_eq!(index, 1);
+                fragments.push_str(&partial_json);
+            }
+            ContentEvent::BlockComplete { index, block } => completed.push((index, block)),
+            _ => {}
+        }
+    }
+
+    assert_eq!(text, "Let me check.");
+    assert_eq!(fragments, "{\"location\": \"Tokyo\", \"days\": 3}");
+    assert_eq!(completed.len(), 3);
+    assert!(matches!(&completed[1], (1, Content::ToolUse { input, .. }) if input == &json!({"location": "Tokyo", "days": 3})));
+
+    let content = stream.into_content().unwrap();
+    assert_eq!(content.len(), 3);
+    assert!(matches!(&content[0], Content::Text { text, .. } if text == "Let me check."));
+    match &content[1] {
+        Content::ToolUse { id, name, input, .. } => {
+            assert_eq!(id, "toolu_1");
+            assert_eq!(name, "get_weather");
+            assert_eq!(input, &json!({"location": "Tokyo", "days": 3}));
+        }
+        other => panic!("Expected tool_use block, got {:?}", other),
+    }
+    // A tool call without input fragments keeps its empty input
+    assert!(matches!(&content[2], Content::ToolUse { input, .. } if input == &json!({})));
+}
+
+#[tokio::test]
+async fn test_raw_stream_still_yields_tool_events() {
+    let client = streaming_client();
+    let events: Vec<DeltaEvent> = client.message().user_content("Weather?").stream().await.unwrap()
+        .map(|event| event.unwrap())
+        .collect()
+        .await;
+
+    assert!(matches!(&events[4].content_block, Some(Content::ToolUse { name, .. }) if name == "get_weather"));
+    let delta = events[6].delta.as_ref().unwrap();
+    assert_eq!(delta.delta_type.as_deref(), Some("input_json_delta"));
+    assert_eq!(delta.partial_json.as_deref(), Some("{\"location\": \"Tok"));
+    assert_eq!(events[6].to_text(), None);
+}
+
+#[test]
+fn test_accumulator_handles_legacy_text_deltas() {
+    let mut accumulator = StreamAccumulator::new();
+    for (index, text) in [(0, "Hello"), (0, ", world"), (1, "Second")] {
+        let events = accumulator.apply(&delta_event(json!({
+            "type": "content_block_delta", "index": index, "delta": {"text": text}
+        }))).unwrap();
+        assert!(matches!(&events[..], [ContentEvent::TextDelta { .. }]));
+    }
+
+    let content = accumulator.into_content().unwrap();
+    assert!(matches!(&content[..], [Content::Text { text: a, .. }, Content::Text { text: b, .. }] if a == "Hello, world" && b == "Second"));
+}
+
+#[test]
+fn test_invalid_tool_input_is_a_parse_error() {
+    let mut accumulator = StreamAccumulator::new();
+    accumulator.apply(&delta_event(json!({
+        "type": "content_block_start", "index": 0,
+        "content_block": {"type": "tool_use", "id": "toolu_1", "name": "get_weather", "input": {}}
+    }))).unwrap();
+    accumulator.apply(&delta_event(json!({
+        "type": "content_block_delta", "index": 0,
+        "delta": {"type": "input_json_delta", "partial_json": "{\"location\": "}
+    }))).unwrap();
+
+    // Until the block is complete, its input is the one it started with
+    assert!(matches!(&accumulator.content()[..], [Content::ToolUse { input, .. }] if input == &json!({})));
+
+    let result = accumulator.apply(&delta_event(json!({"type": "content_block_stop", "index": 0})));
+    assert!(matches!(result, Err(ClaudeError::ParseError { .. })));
+}
+
+#[test]
+fn test_unknown_block_types_do_not_fail_the_event() {
+    let event = delta_event(json!({
+        "type": "content_block_start", "index": 0,
+        "content_block": {"type": "server_tool_use", "id": "srvtoolu_1"}
+    }));
+    assert!(event.content_block.is_none());
+}
+
+#[test]
+fn test_accumulator_rebuilds_message_response() {
+    let mut accumulator = StreamAccumulator::new();
+    for event in tool_use_events() {
+        accumulator.apply(&delta_event(event)).unwrap();
+    }
+    assert_eq!(accumulator.stop_reason(), Some("tool_use"));
+
+    let response = accumulator.into_response().unwrap();
+    assert_eq!(response.id, "msg_1");
+    assert_eq!(response.model, "claude-3-sonnet-20240229");
+    assert_eq!(response.role, Role::Assistant);
+    assert_eq!(response.stop_reason.as_deref(), Some("tool_use"));
+    assert_eq!(response.stop_sequence, None);
+    assert_eq!(response.content.len(), 3);
+    // Input tokens come from message_start, output tokens from message_delta
+    assert_eq!(response.usage.input_tokens, 25);
+    assert_eq!(response.usage.output_tokens, 42);
+}
+
+struct RecordingMiddleware {
+    stop_reasons: Arc<Mutex<Vec<Option<String>>>>,
+}
+
+#[async_trait]
+impl ResponseMiddleware for RecordingMiddleware {
+    async fn process_response(&self, mut response: MessageResponse) -> ClaudeResult<MessageResponse> {
+        self.stop_reasons.lock().unwrap().push(response.stop_reason.clone());
+        response.id = format!("{}-processed", response.id);
+        Ok(response)
+    }
+}
+
+struct RecordingContextManager {
+    responses: Arc<Mutex<Vec<MessageResponse>>>,
+}
+
+#[async_trait]
+impl ContextManager for RecordingContextManager {
+    async fn process_messages(&self, messages: Vec<Message>) -> ClaudeResult<Vec<Message>> {
+        Ok(messages)
+    }
+
+    async fn update_with_response(&self, response: &MessageResponse) -> ClaudeResult<()> {
+        self.responses.lock().unwrap().push(response.clone());
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_stream_and_collect_applies_response_processing() {
+    let stop_reasons = Arc::new(Mutex::new(Vec::new()));
+    let responses = Arc::new(Mutex::new(Vec::new()));
+    let client = streaming_client()
+        .add_response_middleware(RecordingMiddleware { stop_reasons: stop_reasons.clone() })
+        .with_context_manager(RecordingContextManager { responses: responses.clone() });
+
+    let mut text = String::new();
+    let response = client.message()
+        .user_content("Weather?")
+        .stream_and_collect(|event| {
+            if let ContentEvent::TextDelta { text: chunk, .. } = event {
+                text.push_str(chunk);
+            }
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(text, "Let me check.");
+    assert_eq!(response.id, "msg_1-processed");
+    assert_eq!(response.usage.output_tokens, 42);
+    assert!(matches!(&response.content[1], Content::ToolUse { name, .. } if name == "get_weather"));
+
+    assert_eq!(*stop_reasons.lock().unwrap(), vec![Some("tool_use".to_string())]);
+    let responses = responses.lock().unwrap();
+    assert_eq!(responses.len(), 1);
+    assert_eq!(responses[0].id, "msg_1-processed");
+}
+
+#[tokio::test]
+async fn test_streams_that_end_early_are_not_collected() {
+    // The connection drops before message_delta and message_stop
+    let mut events = tool_use_events();
+    events.truncate(events.len() - 2);
+    let mut server = mockito::Server::new_async().await;
+    let _mock = server.mock("POST", "/messages")
+        .with_status(200)
+        .with_header("content-type", "text/event-stream")
+        .with_body(sse_body(&events))
+        .create_async()
+        .await;
+
+    let stop_reasons = Arc::new(Mutex::new(Vec::new()));
+    let client = Claude::new("test-api-key")
+        .with_base_url(server.url())
+        .add_response_middleware(RecordingMiddleware { stop_reasons: stop_reasons.clone() });
+    let result = client.message().user_content("Weather?").stream_and_collect(|_| {}).await;
+
+    assert!(matches!(result, Err(ClaudeError::RequestError { message, .. }) if message.contains("message_stop")));
+    assert!(stop_reasons.lock().unwrap().is_empty());
+
+    let mut accumulator = StreamAccumulator::new();
+    for event in events {
+        accumulator.apply(&delta_event(event)).unwrap();
+    }
+    assert!(!accumulator.is_complete());
+    assert!(accumulator.into_response().is_err());
+}
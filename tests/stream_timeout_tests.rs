@@ -0,0 +1,103 @@
+use claude_rs::{Claude, ClaudeError, RetryPolicy, StreamStall};
+use claude_rs::types::*;
+use futures::StreamExt;
+use serde_json::json;
+use std::time::Duration;
+
+mod mock_api_client;
+use mock_api_client::{delta_event, stalling_client, text_delta};
+
+fn ping() -> DeltaEvent {
+    delta_event(json!({"type": "ping"}))
+}
+
+/// A client whose streams emit `events`, each after its delay, and then wait forever
+fn scripted_stream_client(events: Vec<(u64, DeltaEvent)>) -> Claude {
+    stalling_client(Claude::new("test-api-key"), events)
+}
+
+#[tokio::test]
+async fn test_first_event_timeout_ignores_pings() {
+    let client = scripted_stream_client(vec![(10, ping()), (10, ping()), (10, ping())]);
+    let mut stream = client.message()
+        .user_content("Hello")
+        .first_event_timeout(Duration::from_millis(100))
+        .stream()
+        .await
+        .unwrap();
+
+    for _ in 0..3 {
+        assert_eq!(stream.next().await.unwrap().unwrap().event_type, "ping");
+    }
+    let error = stream.next().await.unwrap().unwrap_err();
+    assert!(matches!(
+        error,
+        ClaudeError::StreamTimeout { stall: StreamStall::FirstEvent, timeout } if timeout == Duration::from_millis(100)
+    ));
+    assert!(error.is_stream_timeout());
+    assert!(error.is_retryable());
+    assert!(RetryPolicy::default().should_retry(&error));
+    assert!(stream.next().await.is_none());
+}
+
+#[tokio::test]
+async fn test_pings_keep_slow_stream_alive() {
+    let client = scripted_stream_client(vec![
+        (10, text_delta("Once")),
+        (60, ping()),
+        (60, ping()),
+        (60, ping()),
+        (60, text_delta(" upon a time")),
+    ]);
+    let mut stream = client.message()
+        .user_content("Tell me a story")
+        .first_event_timeout(Duration::from_millis(500))
+        .max_event_gap(Duration::from_millis(150))
+        .stream()
+        .await
+        .unwrap();
+
+    let mut text = String::new();
+    let error = loop {
+        match stream.next().await.unwrap() {
+            Ok(event) => text.push_str(&event.to_text().unwrap_or_default()),
+            Err(error) => break error,
+        }
+    };
+
+    assert_eq!(text, "Once upon a time");
+    assert!(matches!(error, ClaudeError::StreamTimeout { stall: StreamStall::Idle, .. }));
+    assert!(stream.next().await.is_none());
+}
+
+#[tokio::test]
+async fn test_streams_without_timeouts_are_unchanged() {
+    let client = scripted_stream_client(vec![(10, text_delta("Hello"))]);
+    let mut stream = client.message().user_content("Hello").stream().await.unwrap();
+
+    assert!(stream.next().await.unwrap().is_ok());
+    let next = tokio::time::timeout(Duration::from_millis(100), stream.next()).await;
+    assert!(next.is_err(), "stream should still be waiting for events");
+}
+
+#[cfg(feature = "reactive")]
+#[tokio::test]
+async fn test_reactive_response_reports_stall() {
+    use claude_rs::reactive::ReactiveResponseStatus;
+
+    let client = scripted_stream_client(vec![(10, text_delta("Once upon"))]);
+    let mut response = client.send_reactive(
+        client.message()
+            .user_content("Tell me a story")
+            .max_event_gap(Duration::from_millis(50))
+    ).await.unwrap();
+
+    response.next().await.unwrap().unwrap();
+    assert!(matches!(response.next().await, Some(Err(ClaudeError::StreamTimeout { .. }))));
+    assert!(response.next().await.is_none());
+
+    assert_eq!(response.status(), ReactiveResponseStatus::TimedOut);
+    assert!(response.is_timed_out());
+    assert!(!response.has_error());
+    assert_eq!(response.current_text(), "Once upon");
+}
@@ -0,0 +1,109 @@
+use claude_rs::{AdaptiveContextManager, Claude, ClaudeModel, ContextManager, SimpleImportanceScorer, SimpleTokenCounter, TokenCounter};
+use claude_rs::types::*;
+use serde_json::json;
+
+mod mock_api_client;
+use mock_api_client::{create_text_response, RequestLog};
+
+/// Creates a client that answers every request with "ok" and records it
+fn recording_client() -> (Claude, RequestLog) {
+    let client = Claude::new("test-api-key").with_model(ClaudeModel::Sonnet);
+    mock_api_client::recording_client(client, |_, _| Ok(create_text_response("ok")))
+}
+
+fn user_message(text: &str) -> Message {
+    Message {
+        role: Role::User,
+        content: vec![Content::text(text)],
+    }
+}
+
+#[tokio::test]
+async fn test_system_blocks_are_sent_in_order() {
+    let (client, requests) = recording_client();
+
+    client.message()
+        .system("You are a meticulous librarian.").unwrap()
+        .system_block(SystemBlock::new("A long style guide").with_cache_control(CacheControl::ephemeral())).unwrap()
+        .system_block(SystemBlock::new("Today's date is 2025-01-01.")).unwrap()
+        .user_content("Hello")
+        .send()
+        .await
+        .unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(
+        serde_json::to_value(&requests[0]).unwrap()["system"],
+        json!([
+            {"type": "text", "text": "You are a meticulous librarian."},
+            {"type": "text", "text": "A long style guide", "cache_control": {"type": "ephemeral"}},
+            {"type": "text", "text": "Today's date is 2025-01-01."},
+        ])
+    );
+    assert_eq!(requests[0].cache_breakpoints(), 1);
+}
+
+#[tokio::test]
+async fn test_plain_system_prompt_is_a_string() {
+    let (client, requests) = recording_client();
+
+    client.message()
+        .system("Be brief.").unwrap()
+        .user_content("Hello")
+        .send()
+        .await
+        .unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(serde_json::to_value(&requests[0]).unwrap()["system"], json!("Be brief."));
+}
+
+#[test]
+fn test_system_blocks_are_validated() {
+    let client = Claude::new("test-api-key");
+
+    assert!(matches!(client.message().system_blocks(Vec::new()), Err(ClaudeError::ValidationError(_))));
+    assert!(matches!(client.message().system_block(SystemBlock::new("")), Err(ClaudeError::ValidationError(_))));
+    assert!(matches!(
+        client.message().system_blocks(vec![SystemBlock::new("Stable"), SystemBlock::new("")]),
+        Err(ClaudeError::ValidationError(_))
+    ));
+}
+
+#[test]
+fn test_token_counter_counts_system_prompt() {
+    let counter = SimpleTokenCounter;
+
+    // 4 characters per token
+    assert_eq!(counter.count_system_tokens(&SystemPrompt::from("12345678")), 2);
+    assert_eq!(
+        counter.count_system_tokens(&SystemPrompt::Blocks(vec![SystemBlock::new("1234"), SystemBlock::new("12345678")])),
+        3
+    );
+}
+
+#[tokio::test]
+async fn test_context_manager_leaves_room_for_system_prompt() {
+    let counter = SimpleTokenCounter;
+    let earlier = Message {
+        role: Role::Assistant,
+        content: vec![Content::text("Short reply")],
+    };
+    let latest = user_message("A much longer follow-up question about the reply");
+    let limit = counter.count_message_tokens(&earlier) + counter.count_message_tokens(&latest);
+
+    let manager = AdaptiveContextManager::with_model(limit, SimpleImportanceScorer, ClaudeModel::Custom("test-model".to_string()));
+    let mut response = create_text_response("Short reply");
+    response.content = earlier.content.clone();
+    manager.update_with_response(&response).await.unwrap();
+
+    // Without a system prompt the whole history fits
+    let messages = manager.process_messages(vec![latest.clone()]).await.unwrap();
+    assert_eq!(messages.len(), 2);
+
+    // The system prompt's tokens push the earlier reply out
+    let system = SystemPrompt::from("You are a meticulous librarian.");
+    let messages = manager.process_request_messages(Some(&system), vec![latest]).await.unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].role, Role::User);
+}
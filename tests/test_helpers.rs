@@ -54,7 +54,7 @@ impl<T> DomainTester<T> {
         }
         
         let content_text = match &user_messages[0].content[0] {
-            Content::Text { text } => text,
+            Content::Text { text, .. } => text,
             _ => return false,
         };
         
@@ -271,10 +271,12 @@ pub fn create_mock_stream_response(
             stop_sequence: None,
             role: Some(Role::Assistant),
             type_field: Some("message".to_string()),
+            usage: None,
         }),
         delta: None,
         usage: None,
         index: Some(0),
+        content_block: None,
     });
     
     // Add text delta events
@@ -284,21 +286,26 @@ pub fn create_mock_stream_response(
             message: Some(DeltaMessage {
                 id: "msg_mock123".to_string(),
                 model: "claude-3-sonnet-20240229".to_string(),
-                content: Some(vec![Content::Text {
-                    text: chunk.to_string(),
-                }]),
+                content: Some(vec![Content::text(chunk.to_string())]),
                 stop_reason: None,
                 stop_sequence: None,
                 role: Some(Role::Assistant),
                 type_field: Some("message".to_string()),
+                usage: None,
             }),
             delta: Some(Delta {
                 text: Some(chunk.to_string()),
                 stop_reason: None,
                 stop_sequence: None,
+                delta_type: None,
+                partial_json: None,
+                thinking: None,
+                signature: None,
+                citation: None,
             }),
             usage: None,
             index: Some(i as u32 + 1),
+            content_block: None,
         });
     }
     
@@ -314,17 +321,26 @@ pub fn create_mock_stream_response(
                 stop_sequence: None,
                 role: Some(Role::Assistant),
                 type_field: Some("message".to_string()),
+                usage: None,
             }),
             delta: Some(Delta {
                 text: None,
                 stop_reason: Some("end_turn".to_string()),
                 stop_sequence: None,
+                delta_type: None,
+                partial_json: None,
+                thinking: None,
+                signature: None,
+                citation: None,
             }),
             usage: Some(Usage {
                 input_tokens: 10,
                 output_tokens: text_chunks.iter().map(|s| s.len() as u32).sum(),
+                cache_creation_input_tokens: 0,
+                cache_read_input_tokens: 0,
             }),
             index: Some(text_chunks.len() as u32 + 1),
+            content_block: None,
         });
     }
     
@@ -476,12 +492,12 @@ pub fn create_json_response(json_content: &str) -> MessageResponse {
         model: "claude-3-sonnet-20240229".to_string(),
         r#type: "message".to_string(),
         role: Role::Assistant,
-        content: vec![Content::Text { 
-            text: json_content.to_string() 
-        }],
+        content: vec![Content::text(json_content.to_string())],
         usage: Usage {
             input_tokens: 10,
             output_tokens: 5,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
         },
         stop_reason: Some("end_turn".to_string()),
         stop_sequence: None,
@@ -496,18 +512,33 @@ pub fn create_text_response(text: &str) -> MessageResponse {
         model: "claude-3-sonnet-20240229".to_string(),
         r#type: "message".to_string(),
         role: Role::Assistant,
-        content: vec![Content::Text { 
-            text: text.to_string() 
-        }],
+        content: vec![Content::text(text.to_string())],
         usage: Usage {
             input_tokens: 10,
             output_tokens: 5,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 0,
         },
         stop_reason: Some("end_turn".to_string()),
         stop_sequence: None,
     }
 }
 
+// Helper to create the JSON body of a plain text response, for HTTP mocks
+#[allow(dead_code)]
+pub fn response_body() -> String {
+    serde_json::json!({
+        "id": "msg_1",
+        "type": "message",
+        "role": "assistant",
+        "model": "claude-3-sonnet-20240229",
+        "content": [{"type": "text", "text": "ok"}],
+        "stop_reason": "end_turn",
+        "stop_sequence": null,
+        "usage": {"input_tokens": 10, "output_tokens": 5}
+    }).to_string()
+}
+
 // Helper to create a code analysis response
 #[allow(dead_code)]
 pub fn create_code_analysis_response(issues: Vec<(&str, &str)>, score: u32) -> MessageResponse {
@@ -0,0 +1,172 @@
+use claude_rs::{Claude, ClaudeModel, StreamAccumulator, ToolRegistry};
+use claude_rs::types::*;
+use serde_json::{json, Value};
+
+mod mock_api_client;
+use mock_api_client::{create_text_response, scripted, RequestLog};
+
+/// Creates a client that replays `responses` in order and records every request
+fn recording_client(responses: Vec<MessageResponse>) -> (Claude, RequestLog) {
+    mock_api_client::recording_client(Claude::new("test-api-key").with_model(ClaudeModel::Sonnet37), scripted(responses))
+}
+
+/// Stream events for a response that thinks, then answers
+fn thinking_events() -> Vec<DeltaEvent> {
+    let events: Vec<Value> = vec![
+        json!({"type": "message_start", "message": {"id": "msg_1", "model": "claude-3-7-sonnet-20250219", "role": "assistant", "content": [], "stop_reason": null, "stop_sequence": null, "usage": {"input_tokens": 30, "output_tokens": 1}}}),
+        json!({"type": "content_block_start", "index": 0, "content_block": {"type": "thinking", "thinking": ""}}),
+        json!({"type": "content_block_delta", "index": 0, "delta": {"type": "thinking_delta", "thinking": "27 is 3 cubed, "}}),
+        json!({"type": "content_block_delta", "index": 0, "delta": {"type": "thinking_delta", "thinking": "so the cube root is 3."}}),
+        json!({"type": "content_block_delta", "index": 0, "delta": {"type": "signature_delta", "signature": "EqQBCgIYAhIM"}}),
+        json!({"type": "content_block_stop", "index": 0}),
+        json!({"type": "content_block_start", "index": 1, "content_block": {"type": "text", "text": ""}}),
+        json!({"type": "content_block_delta", "index": 1, "delta": {"type": "text_delta", "text": "The answer is 3."}}),
+        json!({"type": "content_block_stop", "index": 1}),
+        json!({"type": "message_delta", "delta": {"stop_reason": "end_turn", "stop_sequence": null}, "usage": {"output_tokens": 50}}),
+        json!({"type": "message_stop"}),
+    ];
+    events.into_iter().map(|event| serde_json::from_value(event).unwrap()).collect()
+}
+
+#[test]
+fn test_thinking_wire_format() {
+    assert_eq!(
+        serde_json::to_value(ThinkingConfig::enabled(2048)).unwrap(),
+        json!({"type": "enabled", "budget_tokens": 2048})
+    );
+
+    let blocks: Vec<Content> = serde_json::from_value(json!([
+        {"type": "thinking", "thinking": "Let me think.", "signature": "sig"},
+        {"type": "redacted_thinking", "data": "encrypted"},
+    ])).unwrap();
+    assert!(matches!(&blocks[0], Content::Thinking { thinking, signature } if thinking == "Let me think." && signature == "sig"));
+    assert!(matches!(&blocks[1], Content::RedactedThinking { data } if data == "encrypted"));
+    assert!(blocks.iter().all(Content::is_thinking));
+
+    // Blocks are sent back exactly as they were received
+    assert_eq!(
+        serde_json::to_value(&blocks).unwrap(),
+        json!([
+            {"type": "thinking", "thinking": "Let me think.", "signature": "sig"},
+            {"type": "redacted_thinking", "data": "encrypted"},
+        ])
+    );
+}
+
+#[tokio::test]
+async fn test_thinking_budget_is_validated() {
+    let (client, requests) = recording_client(vec![create_text_response("ok")]);
+
+    assert!(matches!(client.message().thinking(512), Err(ClaudeError::ValidationError(_))));
+
+    let result = client.message()
+        .user_content("Hello")
+        .max_tokens(2000).unwrap()
+        .thinking(2000).unwrap()
+        .send()
+        .await;
+    assert!(matches!(result, Err(ClaudeError::ValidationError(message)) if message.contains("less than max_tokens")));
+
+    let result = client.message()
+        .user_content("Hello")
+        .max_tokens(4000).unwrap()
+        .temperature(0.2).unwrap()
+        .thinking(2000).unwrap()
+        .send()
+        .await;
+    assert!(matches!(result, Err(ClaudeError::ValidationError(message)) if message.contains("temperature")));
+    assert!(requests.lock().unwrap().is_empty());
+
+    client.message()
+        .user_content("Hello")
+        .max_tokens(4000).unwrap()
+        .thinking(2000).unwrap()
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(requests.lock().unwrap()[0].thinking, Some(ThinkingConfig::enabled(2000)));
+}
+
+#[tokio::test]
+async fn test_tool_loop_sends_thinking_blocks_back() {
+    let mut tool_call = create_text_response("");
+    tool_call.content = vec![
+        Content::Thinking { thinking: "I should look this up.".to_string(), signature: "sig_1".to_string() },
+        Content::RedactedThinking { data: "encrypted".to_string() },
+        Content::tool_use("toolu_1", "lookup", json!({})),
+    ];
+    tool_call.stop_reason = Some("tool_use".to_string());
+
+    let (client, requests) = recording_client(vec![tool_call, create_text_response("Done")]);
+    let registry = ToolRegistry::new().with_tool(
+        Tool::from_type::<Value>("lookup", "Look something up"),
+        |_| async move { Ok("found") },
+    );
+
+    client.message()
+        .user_content("Look it up")
+        .max_tokens(4000).unwrap()
+        .thinking(2000).unwrap()
+        .run_tools(&registry)
+        .await
+        .unwrap();
+
+    let requests = requests.lock().unwrap();
+    let assistant_turn = &requests[1].messages[1];
+    assert_eq!(assistant_turn.role, Role::Assistant);
+    assert!(matches!(&assistant_turn.content[0], Content::Thinking { signature, .. } if signature == "sig_1"));
+    assert!(matches!(&assistant_turn.content[1], Content::RedactedThinking { .. }));
+    assert_eq!(requests[1].thinking, Some(ThinkingConfig::enabled(2000)));
+}
+
+#[test]
+fn test_streamed_thinking_is_reassembled() {
+    let mut accumulator = StreamAccumulator::new();
+    let mut answer = String::new();
+    for event in thinking_events() {
+        if let Some(text) = event.to_text() {
+            answer.push_str(&text);
+        }
+        accumulator.apply(&event).unwrap();
+    }
+
+    // Thinking never leaks into the answer text
+    assert_eq!(answer, "The answer is 3.");
+
+    let response = accumulator.into_response().unwrap();
+    match &response.content[0] {
+        Content::Thinking { thinking, signature } => {
+            assert_eq!(thinking, "27 is 3 cubed, so the cube root is 3.");
+            assert_eq!(signature, "EqQBCgIYAhIM");
+        }
+        other => panic!("Expected thinking block, got {:?}", other),
+    }
+    assert!(matches!(&response.content[1], Content::Text { text, .. } if text == "The answer is 3."));
+}
+
+#[cfg(feature = "reactive")]
+mod reactive {
+    use super::thinking_events;
+    use claude_rs::reactive::ReactiveResponse;
+    use futures::StreamExt;
+
+    #[tokio::test]
+    async fn test_reactive_response_separates_thinking() {
+        let events = thinking_events().into_iter().map(Ok);
+        let mut response = ReactiveResponse::new(futures::stream::iter(events));
+        while let Some(event) = response.next().await {
+            event.unwrap();
+        }
+
+        assert_eq!(response.current_thinking(), "27 is 3 cubed, so the cube root is 3.");
+        assert_eq!(response.current_text(), "The answer is 3.");
+
+        let events = thinking_events().into_iter().map(Ok);
+        let thinking: Vec<String> = ReactiveResponse::new(futures::stream::iter(events))
+            .thinking_stream()
+            .map(|chunk| chunk.unwrap())
+            .collect()
+            .await;
+        assert_eq!(thinking, vec!["27 is 3 cubed, ", "so the cube root is 3."]);
+    }
+}
@@ -0,0 +1,204 @@
+use claude_rs::{Claude, ClaudeError, PemIdentity, TlsConfig};
+use serde_json::json;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio_native_tls::{native_tls, TlsAcceptor};
+
+const CA_PEM: &[u8] = include_bytes!("fixtures/tls/ca.pem");
+const SERVER_PEM: &[u8] = include_bytes!("fixtures/tls/server.pem");
+const SERVER_KEY: &[u8] = include_bytes!("fixtures/tls/server.key");
+const CLIENT_PEM: &[u8] = include_bytes!("fixtures/tls/client.pem");
+const CLIENT_KEY: &[u8] = include_bytes!("fixtures/tls/client.key");
+
+/// Start an HTTPS server for `localhost` that answers every request with a message
+///
+/// Also returns the number of requests the server has received.
+async fn start_tls_server() -> (String, Arc<AtomicUsize>) {
+    let received = Arc::new(AtomicUsize::new(0));
+    let counter = received.clone();
+    let identity = native_tls::Identity::from_pkcs8(SERVER_PEM, SERVER_KEY).unwrap();
+    let acceptor = TlsAcceptor::from(native_tls::TlsAcceptor::new(identity).unwrap());
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let acceptor = acceptor.clone();
+            let counter = counter.clone();
+            tokio::spawn(async move {
+                // Clients that reject the certificate fail the handshake
+                let Ok(mut stream) = acceptor.accept(stream).await else {
+                    return;
+                };
+                let mut request = Vec::new();
+                let mut buffer = [0u8; 4096];
+                while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+                    match stream.read(&mut buffer).await {
+                        Ok(0) | Err(_) => return,
+                        Ok(read) => request.extend_from_slice(&buffer[..read]),
+                    }
+                }
+                counter.fetch_add(1, Ordering::SeqCst);
+
+                let body = json!({
+                    "id": "msg_tls",
+                    "type": "message",
+                    "role": "assistant",
+                    "model": "claude-3-sonnet-20240229",
+                    "content": [{"type": "text", "text": "secure"}],
+                    "stop_reason": "end_turn",
+                    "stop_sequence": null,
+                    "usage": {"input_tokens": 10, "output_tokens": 5}
+                }).to_string();
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = stream.write_all(response.as_bytes()).await;
+                let _ = stream.shutdown().await;
+            });
+        }
+    });
+
+    (format!("https://localhost:{}", port), received)
+}
+
+fn private_ca() -> TlsConfig {
+    TlsConfig::new()
+        .with_root_certificates_pem(CA_PEM)
+        .unwrap()
+        .without_built_in_roots()
+}
+
+#[tokio::test]
+async fn test_clients_use_their_own_root_certificates() {
+    let (url, _) = start_tls_server().await;
+
+    let trusting = Claude::builder("test-api-key")
+        .base_url(&url)
+        .tls_config(private_ca())
+        .build()
+        .unwrap();
+    let default = Claude::builder("test-api-key")
+        .base_url(&url)
+        .build()
+        .unwrap();
+
+    let response = trusting.message().user_content("Hello").send().await.unwrap();
+    assert_eq!(response.id, "msg_tls");
+    assert!(default.message().user_content("Hello").send().await.is_err());
+}
+
+#[tokio::test]
+async fn test_pinned_certificate_must_match_server() {
+    let (url, received) = start_tls_server().await;
+
+    let pinned = Claude::builder("test-api-key")
+        .base_url(&url)
+        .tls_config(private_ca().with_pinned_certificates_pem(SERVER_PEM).unwrap())
+        .build()
+        .unwrap();
+    assert!(pinned.message().user_content("Hello").send().await.is_ok());
+    assert_eq!(received.load(Ordering::SeqCst), 1);
+
+    let mismatched = Claude::builder("test-api-key")
+        .base_url(&url)
+        .tls_config(private_ca().with_pinned_certificates_pem(CLIENT_PEM).unwrap())
+        .build()
+        .unwrap();
+    let error = mismatched.message().user_content("Hello").send().await.unwrap_err();
+    assert!(error.to_string().contains("does not match any pinned certificate"));
+    // The handshake failed, so the request and API key never reached the server
+    assert_eq!(received.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_pinned_clients_present_pem_identities() {
+    let (url, _) = start_tls_server().await;
+    let pinned = || private_ca().with_pinned_certificates_pem(SERVER_PEM).unwrap();
+
+    let with_pem = Claude::builder("test-api-key")
+        .base_url(&url)
+        .tls_config(pinned().with_identity_pem(CLIENT_PEM, CLIENT_KEY).unwrap())
+        .build()
+        .unwrap();
+    assert!(with_pem.message().user_content("Hello").send().await.is_ok());
+
+    let identity = reqwest::Identity::from_pkcs8_pem(CLIENT_PEM, CLIENT_KEY).unwrap();
+    let opaque = Claude::builder("test-api-key")
+        .base_url(&url)
+        .tls_config(pinned().with_identity(identity))
+        .build();
+    assert!(matches!(opaque, Err(ClaudeError::InvalidParameter(message)) if message.contains("with_identity_pem")));
+}
+
+#[tokio::test]
+async fn test_pinning_rejects_plain_http() {
+    let server = mockito::Server::new_async().await;
+
+    let result = Claude::builder("test-api-key")
+        .base_url(server.url())
+        .tls_config(TlsConfig::new().with_pinned_certificates_pem(SERVER_PEM).unwrap())
+        .build();
+    assert!(matches!(result, Err(ClaudeError::InvalidParameter(message)) if message.contains("requires a TLS connection")));
+}
+
+#[test]
+fn test_certificate_and_identity_parsing() {
+    assert!(TlsConfig::new().with_identity_pem(CLIENT_PEM, CLIENT_KEY).is_ok());
+
+    let invalid_key = TlsConfig::new().with_identity_pem(CLIENT_PEM, b"not a key");
+    assert!(matches!(invalid_key, Err(ClaudeError::InvalidParameter(_))));
+
+    let no_roots = TlsConfig::new().with_root_certificates_pem(b"not a certificate");
+    assert!(matches!(no_roots, Err(ClaudeError::InvalidParameter(_))));
+
+    let no_pins = TlsConfig::new().with_pinned_certificates_pem(b"");
+    assert!(matches!(no_pins, Err(ClaudeError::InvalidParameter(_))));
+
+    let invalid_pin = TlsConfig::new().with_pinned_certificate(b"not a certificate".to_vec());
+    assert!(matches!(invalid_pin, Err(ClaudeError::InvalidParameter(message)) if message.contains("pinned")));
+    let server_der = rustls_pemfile::certs(&mut &SERVER_PEM[..]).unwrap().remove(0);
+    assert!(TlsConfig::new().with_pinned_certificate(server_der).is_ok());
+
+    // TLS settings belong to the HTTP client the builder creates
+    let external = Claude::builder("test-api-key")
+        .http_client(reqwest::Client::new())
+        .tls_config(private_ca())
+        .build();
+    assert!(matches!(external, Err(ClaudeError::InvalidParameter(_))));
+}
+
+#[test]
+fn test_tls_config_struct_literal() {
+    let config = TlsConfig {
+        cert_verification: false,
+        ..Default::default()
+    };
+    assert!(!config.cert_verification);
+    assert!(config.built_in_root_certs);
+
+    let identity = TlsConfig::new().with_identity_pem(CLIENT_PEM, CLIENT_KEY).unwrap();
+    let copied = TlsConfig {
+        identity: identity.identity.clone(),
+        identity_pem: identity.identity_pem.clone(),
+        ..private_ca()
+    };
+    assert_eq!(copied.identity_pem.unwrap().certificates.len(), 1);
+
+    // A PEM identity alone is presented by unpinned clients too
+    let pem_only = TlsConfig {
+        identity_pem: identity.identity_pem.clone(),
+        ..private_ca()
+    };
+    assert!(Claude::builder("test-api-key").tls_config(pem_only).build().is_ok());
+    let broken = TlsConfig {
+        identity_pem: Some(PemIdentity { certificates: vec![b"not a certificate".to_vec()], key: b"not a key".to_vec() }),
+        ..private_ca()
+    };
+    let result = Claude::builder("test-api-key").tls_config(broken).build();
+    assert!(matches!(result, Err(ClaudeError::InvalidParameter(message)) if message.contains("client identity")));
+}
@@ -0,0 +1,143 @@
+use claude_rs::{Claude, ClaudeModel, Sentiment, ToolRegistry};
+use claude_rs::types::*;
+use serde_json::json;
+use std::sync::Arc;
+
+mod mock_api_client;
+use mock_api_client::{create_text_response, scripted, RequestLog};
+
+fn lookup_tool() -> Tool {
+    Tool {
+        name: "lookup".to_string(),
+        description: "Look something up".to_string(),
+        input_schema: json!({"type": "object", "properties": {}}),
+        cache_control: None,
+    }
+}
+
+/// Creates a client that replays `responses` in order and records every request
+fn recording_client(responses: Vec<MessageResponse>) -> (Arc<Claude>, RequestLog) {
+    let client = Claude::new("test-api-key").with_model(ClaudeModel::Sonnet);
+    let (client, requests) = mock_api_client::recording_client(client, scripted(responses));
+    (Arc::new(client), requests)
+}
+
+#[test]
+fn test_tool_choice_wire_format() {
+    let cases = [
+        (ToolChoice::auto(), json!({"type": "auto"})),
+        (ToolChoice::any().disable_parallel_tool_use(), json!({"type": "any", "disable_parallel_tool_use": true})),
+        (ToolChoice::tool("lookup"), json!({"type": "tool", "name": "lookup"})),
+        (ToolChoice::none().disable_parallel_tool_use(), json!({"type": "none"})),
+    ];
+
+    for (choice, expected) in cases {
+        assert_eq!(serde_json::to_value(&choice).unwrap(), expected);
+        assert_eq!(serde_json::from_value::<ToolChoice>(expected).unwrap(), choice);
+    }
+}
+
+#[tokio::test]
+async fn test_forced_tool_must_be_declared() {
+    let (client, requests) = recording_client(vec![create_text_response("unused")]);
+
+    let result = client.message()
+        .user_content("Hello")
+        .add_tool(lookup_tool())
+        .tool_choice(ToolChoice::tool("missing"))
+        .send()
+        .await;
+    assert!(matches!(result, Err(ClaudeError::ValidationError(message)) if message.contains("missing")));
+
+    let result = client.message()
+        .user_content("Hello")
+        .tool_choice(ToolChoice::any())
+        .send()
+        .await;
+    assert!(matches!(result, Err(ClaudeError::ValidationError(_))));
+
+    assert!(requests.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_tool_choice_is_sent() {
+    let (client, requests) = recording_client(vec![create_text_response("ok")]);
+
+    client.message()
+        .user_content("Hello")
+        .add_tool(lookup_tool())
+        .tool_choice(ToolChoice::tool("lookup"))
+        .disable_parallel_tool_use()
+        .send()
+        .await
+        .unwrap();
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(
+        requests[0].tool_choice,
+        Some(ToolChoice::Tool { name: "lookup".to_string(), disable_parallel_tool_use: true })
+    );
+}
+
+#[tokio::test]
+async fn test_run_tools_only_forces_the_first_call() {
+    let mut tool_call = create_text_response("");
+    tool_call.content = vec![Content::tool_use("toolu_1", "lookup", json!({}))];
+    tool_call.stop_reason = Some("tool_use".to_string());
+
+    let (client, requests) = recording_client(vec![tool_call, create_text_response("Done")]);
+    let registry = ToolRegistry::new().with_tool(lookup_tool(), |_| async move { Ok("found") });
+
+    let run = client.message()
+        .user_content("Look it up")
+        .tool_choice(ToolChoice::any())
+        .run_tools(&registry)
+        .await
+        .unwrap();
+
+    assert_eq!(run.iterations, 2);
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests[0].tool_choice, Some(ToolChoice::any()));
+    assert_eq!(requests[1].tool_choice, Some(ToolChoice::auto()));
+}
+
+#[tokio::test]
+async fn test_sentiment_client_forces_tool_call() {
+    let mut response = create_text_response("");
+    response.content = vec![Content::tool_use(
+        "toolu_1",
+        "report_sentiment",
+        json!({"score": 0.8, "sentiment": "Positive", "aspects": {}}),
+    )];
+    response.stop_reason = Some("tool_use".to_string());
+
+    let (client, requests) = recording_client(vec![response]);
+    let result = client.sentiment().analyze_text("I love it").await.unwrap();
+
+    assert_eq!(result.sentiment, Sentiment::Positive);
+    assert!((result.score - 0.8).abs() < f32::EPSILON);
+
+    let requests = requests.lock().unwrap();
+    let tools = requests[0].tools.as_ref().unwrap();
+    assert_eq!(tools[0].name, "report_sentiment");
+    assert_eq!(tools[0].input_schema["required"], json!(["score", "sentiment", "aspects"]));
+    assert_eq!(requests[0].tool_choice, Some(ToolChoice::tool("report_sentiment")));
+}
+
+#[tokio::test]
+async fn test_invalid_tool_input_is_a_domain_error() {
+    let mut response = create_text_response("");
+    response.content = vec![Content::tool_use(
+        "toolu_1",
+        "report_sentiment",
+        json!({"score": 0.8, "sentiment": "Ecstatic", "aspects": {}}),
+    )];
+
+    let (client, _) = recording_client(vec![response]);
+    let error = client.sentiment().analyze_text("I love it").await.unwrap_err();
+
+    match error {
+        ClaudeError::DomainError { message, .. } => assert!(message.contains("`sentiment`"), "{}", message),
+        other => panic!("Expected DomainError, got {:?}", other),
+    }
+}
@@ -0,0 +1,180 @@
+use claude_rs::{AdaptiveContextManager, Claude, ClaudeModel, SimpleImportanceScorer, ToolRegistry};
+use claude_rs::types::*;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+
+mod mock_api_client;
+use mock_api_client::{create_text_response, recording_client, scripted, RequestLog};
+
+fn weather_tool(name: &str) -> Tool {
+    Tool {
+        name: name.to_string(),
+        description: format!("Tool {}", name),
+        input_schema: json!({"type": "object", "properties": {"location": {"type": "string"}}}),
+        cache_control: None,
+    }
+}
+
+fn tool_use_response(calls: Vec<Content>) -> MessageResponse {
+    let mut response = create_text_response("Let me check.");
+    response.content.extend(calls);
+    response.stop_reason = Some("tool_use".to_string());
+    response
+}
+
+/// Creates a client that replays `responses` in order and records every request
+fn scripted_client(responses: Vec<MessageResponse>) -> (Claude, RequestLog) {
+    recording_client(Claude::new("test-api-key").with_model(ClaudeModel::Sonnet), scripted(responses))
+}
+
+#[tokio::test]
+async fn test_run_tools_executes_calls_until_done() {
+    let (client, requests) = scripted_client(vec![
+        tool_use_response(vec![
+            Content::tool_use("toolu_1", "weather", json!({"location": "Tokyo"})),
+            Content::tool_use("toolu_2", "weather", json!({"location": "Paris"})),
+        ]),
+        create_text_response("Tokyo is sunny and Paris is rainy"),
+    ]);
+
+    let registry = ToolRegistry::new().with_tool(weather_tool("weather"), |input| async move {
+        Ok(format!("Weather for {}", input["location"].as_str().unwrap()))
+    });
+
+    let run = client.message()
+        .user_content("Compare the weather")
+        .run_tools(&registry)
+        .await
+        .unwrap();
+
+    assert_eq!(run.iterations, 2);
+    assert!(!run.max_iterations_reached);
+    assert_eq!(run.response.stop_reason.as_deref(), Some("end_turn"));
+    assert_eq!(run.usage.input_tokens, 20);
+    assert_eq!(run.messages.len(), 4);
+
+    // The second request carries both results, in call order, in one user message
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].tools.as_ref().unwrap()[0].name, "weather");
+
+    let results = &requests[1].messages[2];
+    assert_eq!(results.role, Role::User);
+    let ids: Vec<(&str, String)> = results.content.iter().map(|block| match block {
+        Content::ToolResult { tool_use_id, content: Some(ToolResultContent::Text(text)), is_error: false, .. } => {
+            (tool_use_id.as_str(), text.clone())
+        }
+        other => panic!("Expected successful tool_result, got {:?}", other),
+    }).collect();
+    assert_eq!(ids, vec![
+        ("toolu_1", "Weather for Tokyo".to_string()),
+        ("toolu_2", "Weather for Paris".to_string()),
+    ]);
+}
+
+#[tokio::test]
+async fn test_parallel_calls_run_concurrently() {
+    let (client, _) = scripted_client(vec![
+        tool_use_response(vec![
+            Content::tool_use("toolu_1", "wait", json!({})),
+            Content::tool_use("toolu_2", "wait", json!({})),
+        ]),
+        create_text_response("Done"),
+    ]);
+
+    // Each call waits for the other, so sequential execution would never finish
+    let barrier = Arc::new(tokio::sync::Barrier::new(2));
+    let registry = ToolRegistry::new().with_tool(weather_tool("wait"), move |_| {
+        let barrier = barrier.clone();
+        async move {
+            barrier.wait().await;
+            Ok("ready")
+        }
+    });
+
+    let run = tokio::time::timeout(
+        Duration::from_secs(5),
+        client.message().user_content("Go").run_tools(&registry)
+    ).await.expect("tool calls were not run concurrently").unwrap();
+
+    assert_eq!(run.iterations, 2);
+}
+
+#[tokio::test]
+async fn test_tool_failures_are_reported_to_the_model() {
+    let (client, requests) = scripted_client(vec![
+        tool_use_response(vec![
+            Content::tool_use("toolu_1", "broken", json!({})),
+            Content::tool_use("toolu_2", "missing", json!({})),
+        ]),
+        create_text_response("Sorry, the tools failed"),
+    ]);
+
+    let registry = ToolRegistry::new().with_tool(weather_tool("broken"), |_| async move {
+        Err::<String, _>(ClaudeError::ValidationError("location is required".to_string()))
+    });
+
+    client.message().user_content("Go").run_tools(&registry).await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    let results = &requests[1].messages[2].content;
+    assert!(matches!(&results[0], Content::ToolResult { is_error: true, content: Some(ToolResultContent::Text(text)), .. }
+        if text.contains("location is required")));
+    assert!(matches!(&results[1], Content::ToolResult { is_error: true, content: Some(ToolResultContent::Text(text)), .. }
+        if text == "Unknown tool: missing"));
+}
+
+#[tokio::test]
+async fn test_iteration_cap_stops_the_loop() {
+    let looping = tool_use_response(vec![Content::tool_use("toolu_1", "weather", json!({}))]);
+    let (client, requests) = scripted_client(vec![looping.clone(), looping.clone(), looping]);
+
+    let registry = ToolRegistry::new().with_tool(weather_tool("weather"), |_| async move { Ok("again") });
+
+    let run = client.message()
+        .user_content("Go")
+        .max_tool_iterations(2).unwrap()
+        .run_tools(&registry)
+        .await
+        .unwrap();
+
+    assert_eq!(run.iterations, 2);
+    assert!(run.max_iterations_reached);
+    assert_eq!(requests.lock().unwrap().len(), 2);
+    // The final assistant turn still holds the unanswered tool call
+    assert_eq!(run.messages.last().unwrap().role, Role::Assistant);
+}
+
+#[tokio::test]
+async fn test_context_manager_sees_the_tool_loop_once() {
+    let (client, requests) = scripted_client(vec![
+        tool_use_response(vec![Content::tool_use("toolu_1", "weather", json!({"location": "Tokyo"}))]),
+        create_text_response("Tokyo is sunny"),
+        create_text_response("You're welcome"),
+    ]);
+    let client = client.with_context_manager(AdaptiveContextManager::new(100_000, SimpleImportanceScorer));
+    let registry = ToolRegistry::new().with_tool(weather_tool("weather"), |_| async move { Ok("Sunny") });
+
+    client.message().user_content("Weather in Tokyo?").run_tools(&registry).await.unwrap();
+    client.message().user_content("Thanks").send().await.unwrap();
+
+    let requests = requests.lock().unwrap();
+    // The follow-up call holds each turn of the loop exactly once
+    let roles: Vec<Role> = requests[1].messages.iter().map(|message| message.role.clone()).collect();
+    assert_eq!(roles, vec![Role::User, Role::Assistant, Role::User]);
+    // Only the final answer of the loop is kept as history
+    assert_eq!(requests[2].messages.len(), 2);
+    assert!(matches!(&requests[2].messages[0].content[..], [Content::Text { text, .. }] if text == "Tokyo is sunny"));
+}
+
+#[test]
+fn test_registry_replaces_tools_by_name() {
+    let mut registry = ToolRegistry::new();
+    registry.register(weather_tool("weather"), |_| async move { Ok("first") });
+    registry.register(weather_tool("weather"), |_| async move { Ok("second") });
+
+    assert_eq!(registry.len(), 1);
+    assert!(registry.contains("weather"));
+    assert!(!registry.contains("other"));
+}